@@ -0,0 +1,11 @@
+use std::{env, fs, path::PathBuf};
+
+fn main() {
+    // memory.x do bootloader (e não o da aplicação, no diretório acima)
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::copy("memory.x", out.join("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed=memory.x");
+    println!("cargo:rustc-link-arg-bins=--nmagic");
+    println!("cargo:rustc-link-arg-bins=-Tlink.x");
+}
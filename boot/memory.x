@@ -0,0 +1,7 @@
+/* Bootloader no setor 0 da flash. Pilha e variáveis na CCMRAM: a SRAM da
+   aplicação (histórico do shell em .uninit) passa intacta pelo reset */
+MEMORY
+{
+    FLASH : ORIGIN = 0x08000000, LENGTH = 16K
+    RAM   : ORIGIN = 0x10000000, LENGTH = 32K
+}
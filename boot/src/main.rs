@@ -0,0 +1,173 @@
+// Bootloader da atualização A/B (setor 0 da flash): conclui a troca de
+// slots pedida pelo `fw update` (ou o rollback de uma imagem que não se
+// confirmou), liga o watchdog da imagem em teste e salta para o ACTIVE.
+// Gravado uma vez pelo probe (`cargo run --release` neste diretório); depois
+// a aplicação é gravada normalmente, já ligada no ACTIVE (ver `memory.x`)
+
+#![no_std]
+#![no_main]
+
+use core::panic::PanicInfo;
+use core::ptr;
+use cortex_m::peripheral::SCB;
+use cortex_m_rt::entry;
+
+// Código compartilhado com a aplicação (sem as dependências do embassy)
+#[path = "../../src/shell/sha256.rs"]
+pub mod sha256;
+mod shell {
+    pub use super::sha256; // Caminho usado em `boot`
+}
+#[allow(dead_code)] // Parte só da aplicação (`install`, confirmação)
+#[path = "../../src/boot.rs"]
+mod boot;
+
+use boot::{BootFlash, Region, ACTIVE_ADDR, FLASH_BASE, REGION_OFFSETS, SLOT_SIZE, STATE_SIZE};
+
+// Registradores da flash (RM0090, seção 3.9)
+const FLASH_REGS: usize = 0x4002_3C00;
+const KEYR: usize = FLASH_REGS + 0x04;
+const SR: usize = FLASH_REGS + 0x0C;
+const CR: usize = FLASH_REGS + 0x10;
+const KEYS: [u32; 2] = [0x4567_0123, 0xCDEF_89AB];
+const SR_BSY: u32 = 1 << 16;
+const SR_ERRORS: u32 = 0xF2; // OPERR, WRPERR, PGAERR, PGPERR, PGSERR
+const CR_PG: u32 = 1 << 0;
+const CR_SER: u32 = 1 << 1;
+const CR_PSIZE_X32: u32 = 2 << 8;
+const CR_STRT: u32 = 1 << 16;
+const CR_LOCK: u32 = 1 << 31;
+
+// Setores de cada região (mesma ordem de `Region`)
+const SECTORS: [&[u32]; 4] = [&[5, 6], &[7, 8], &[9, 10], &[4]];
+
+// Watchdog independente (RM0090, seção 21.4): LSI/256 com recarga 1000 = ~8 s
+const IWDG_REGS: usize = 0x4000_3000;
+const IWDG_KR: usize = IWDG_REGS;
+const IWDG_PR: usize = IWDG_REGS + 0x04;
+const IWDG_RLR: usize = IWDG_REGS + 0x08;
+const IWDG_SR: usize = IWDG_REGS + 0x0C;
+
+// Flash interna por registradores (sem cache: o bootloader roda com o clock
+// do reset, sem o acelerador ligado)
+struct InternalFlash;
+
+impl InternalFlash {
+    fn unlock() -> Self {
+        unsafe {
+            if ptr::read_volatile(CR as *const u32) & CR_LOCK != 0 {
+                ptr::write_volatile(KEYR as *mut u32, KEYS[0]);
+                ptr::write_volatile(KEYR as *mut u32, KEYS[1]);
+            }
+        }
+        Self
+    }
+
+    fn lock(&self) {
+        unsafe { ptr::write_volatile(CR as *mut u32, CR_LOCK) }
+    }
+
+    // Espera a operação terminar; false (e limpa os erros) se falhou
+    fn wait(&self) -> bool {
+        unsafe {
+            while ptr::read_volatile(SR as *const u32) & SR_BSY != 0 {}
+            let errors = ptr::read_volatile(SR as *const u32) & SR_ERRORS;
+            ptr::write_volatile(SR as *mut u32, errors);
+            ptr::write_volatile(CR as *mut u32, 0);
+            errors == 0
+        }
+    }
+
+    // Endereço de `len` bytes em `offset` da região (None se passa do fim)
+    fn address(&self, region: Region, offset: usize, len: usize) -> Option<usize> {
+        (offset + len <= self.region_size(region))
+            .then(|| (FLASH_BASE + REGION_OFFSETS[region as usize]) as usize + offset)
+    }
+}
+
+impl BootFlash for InternalFlash {
+    fn region_size(&self, region: Region) -> usize {
+        if region == Region::State { STATE_SIZE } else { SLOT_SIZE }
+    }
+
+    fn read(&self, region: Region, offset: usize, buf: &mut [u8]) -> bool {
+        let Some(address) = self.address(region, offset, buf.len()) else {
+            return false;
+        };
+        unsafe { ptr::copy_nonoverlapping(address as *const u8, buf.as_mut_ptr(), buf.len()) };
+        true
+    }
+
+    fn write(&self, region: Region, offset: usize, data: &[u8]) -> bool {
+        let Some(address) = self.address(region, offset, data.len()) else {
+            return false;
+        };
+        if !address.is_multiple_of(boot::WORD) || !data.len().is_multiple_of(boot::WORD) {
+            return false;
+        }
+        for (i, word) in data.chunks_exact(boot::WORD).enumerate() {
+            let value = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+            unsafe {
+                ptr::write_volatile(CR as *mut u32, CR_PSIZE_X32 | CR_PG);
+                ptr::write_volatile((address + i * boot::WORD) as *mut u32, value);
+            }
+            if !self.wait() {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn erase(&self, region: Region) -> bool {
+        SECTORS[region as usize].iter().all(|&sector| {
+            unsafe {
+                ptr::write_volatile(CR as *mut u32, CR_PSIZE_X32 | CR_SER | (sector << 3));
+                ptr::write_volatile(CR as *mut u32, CR_PSIZE_X32 | CR_SER | (sector << 3) | CR_STRT);
+            }
+            self.wait()
+        })
+    }
+}
+
+// Liga o IWDG (não pode mais ser desligado; a aplicação passa a alimentá-lo)
+fn start_watchdog() {
+    unsafe {
+        ptr::write_volatile(IWDG_KR as *mut u32, 0xCCCC); // Liga (e o LSI junto)
+        ptr::write_volatile(IWDG_KR as *mut u32, 0x5555); // Libera PR e RLR
+        ptr::write_volatile(IWDG_PR as *mut u32, 6); // LSI/256
+        ptr::write_volatile(IWDG_RLR as *mut u32, 1000);
+        while ptr::read_volatile(IWDG_SR as *const u32) != 0 {}
+        ptr::write_volatile(IWDG_KR as *mut u32, 0xAAAA); // Recarrega
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let flash = InternalFlash::unlock();
+    let mut buf = [0u8; 1024];
+    // Falha da flash no meio da troca: reinicia e continua do último passo
+    let Ok(testing) = boot::boot(&flash, &mut buf) else {
+        SCB::sys_reset();
+    };
+    flash.lock();
+    if testing {
+        start_watchdog();
+    }
+
+    // ACTIVE vazio (aplicação ainda não gravada): espera o probe
+    let vectors = ACTIVE_ADDR as *const u32;
+    if unsafe { ptr::read_volatile(vectors.add(1)) } == 0xFFFF_FFFF {
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+    unsafe {
+        (*SCB::PTR).vtor.write(ACTIVE_ADDR);
+        cortex_m::asm::bootload(vectors)
+    }
+}
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    SCB::sys_reset()
+}
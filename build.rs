@@ -1,5 +1,114 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use stm32_metapac::metadata::ir::{Access, Array, BitOffset, BlockItemInner, IR};
+use stm32_metapac::metadata::METADATA;
+
 fn main() {
     println!("cargo:rustc-link-arg-bins=--nmagic");
     println!("cargo:rustc-link-arg-bins=-Tlink.x");
     println!("cargo:rustc-link-arg-bins=-Tdefmt.x");
-}
\ No newline at end of file
+
+    let out = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    std::fs::write(out.join("peripherals.rs"), peripherals()).unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+// Tabelas do `reg` (ver src/shell/registers.rs) a partir do metadata do chip:
+// um `PERIPHERALS` com todos os periféricos que têm registradores descritos e
+// uma lista de registradores por versão do bloco (compartilhada entre as
+// instâncias, ex: GPIOA..GPIOI). Sem as descrições: só nomes e bits
+fn peripherals() -> String {
+    let mut code = String::from("// Gerado pelo build.rs a partir do metadata do stm32-metapac\n\n");
+    let mut tables = BTreeMap::new();
+    let mut instances = Vec::new();
+    for peripheral in METADATA.peripherals {
+        let Some(registers) = &peripheral.registers else {
+            continue;
+        };
+        let table = format!("{}_{}", registers.kind, registers.version).to_uppercase().replace('-', "_");
+        if !tables.contains_key(&table) {
+            let mut items = String::new();
+            block(registers.ir, registers.block, "", 0, &mut items);
+            tables.insert(table.clone(), items);
+        }
+        instances.push((peripheral.name, peripheral.address, table));
+    }
+
+    for (table, items) in &tables {
+        let _ = writeln!(code, "const {}: &[Register] = &[\n{}];\n", table, items);
+    }
+    let _ = writeln!(code, "static PERIPHERALS: [Peripheral; {}] = [", instances.len());
+    for (name, address, table) in instances {
+        let _ = writeln!(code, "    Peripheral {{ name: {:?}, base: 0x{:08x}, registers: {} }},", name, address, table);
+    }
+    code.push_str("];\n");
+    code
+}
+
+// Registradores do bloco `name` em `base`; blocos internos (ex: os streams do
+// DMA) entram com o nome como prefixo, e listas de registradores ganham o
+// índice no nome. Os só de escrita ficam de fora (a leitura não diz nada)
+fn block(ir: &IR, name: &str, prefix: &str, base: u32, out: &mut String) {
+    // O metadata dá o bloco do periférico como `ADC_COMMON`; o IR, `AdcCommon`
+    let key = |n: &str| n.replace('_', "").to_lowercase();
+    let items = ir.blocks.iter().find(|b| key(b.name) == key(name)).unwrap().items;
+    for item in items {
+        for (index, offset) in positions(&item.array) {
+            let mut item_name = format!("{}{}", prefix, item.name.to_uppercase());
+            if let Some(index) = index {
+                let _ = write!(item_name, "{}", index);
+            }
+            let offset = base + item.byte_offset + offset;
+            match &item.inner {
+                BlockItemInner::Block(inner) => block(ir, inner.block, &format!("{}_", item_name), offset, out),
+                BlockItemInner::Register(register) => {
+                    if register.access == Access::Write {
+                        continue;
+                    }
+                    let fields = register.fieldset.map(|f| fields(ir, f)).unwrap_or_default();
+                    let _ = writeln!(out, "    Register::new({:?}, 0x{:x}, &[{}]),", item_name, offset, fields);
+                },
+            }
+        }
+    }
+}
+
+// Campos de um registrador. Listas regulares viram um `Field::array`; as de
+// posições avulsas, um campo por posição. Campos com os bits espalhados em
+// pedaços não são decodificados
+fn fields(ir: &IR, name: &str) -> String {
+    let fieldset = ir.fieldsets.iter().find(|f| f.name == name).unwrap();
+    let mut out = String::new();
+    for field in fieldset.fields {
+        let BitOffset::Regular(bit) = &field.bit_offset else {
+            continue;
+        };
+        let name = field.name.to_uppercase();
+        let (offset, width) = (bit.offset, field.bit_size);
+        match &field.array {
+            None => {
+                let _ = write!(out, "Field::bits({:?}, {}, {}), ", name, offset, width);
+            },
+            Some(Array::Regular(array)) => {
+                let _ = write!(out, "Field::array({:?}, {}, {}, {}, {}), ", name, offset, width, array.len, array.stride);
+            },
+            Some(Array::Cursed(array)) => {
+                for (index, at) in array.offsets.iter().enumerate() {
+                    let _ = write!(out, "Field::bits(\"{}{}\", {}, {}), ", name, index, offset + at, width);
+                }
+            },
+        }
+    }
+    out
+}
+
+// Índice (nas listas) e deslocamento de cada cópia de um item do bloco
+fn positions(array: &Option<Array>) -> Vec<(Option<u32>, u32)> {
+    match array {
+        None => vec![(None, 0)],
+        Some(Array::Regular(array)) => (0..array.len).map(|i| (Some(i), i * array.stride)).collect(),
+        Some(Array::Cursed(array)) => array.offsets.iter().enumerate().map(|(i, &at)| (Some(i as u32), at)).collect(),
+    }
+}
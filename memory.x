@@ -1,11 +1,22 @@
 MEMORY
 {
-    FLASH    : ORIGIN = 0x08000000, LENGTH =  512K /* BANK_1 */
+    /* Slot ACTIVE da atualização A/B (ver src/boot.rs): antes dele ficam o
+       bootloader (boot/, setor 0), configuração e senhas (setores 1-3) e o
+       log STATE (setor 4); o fim do slot guarda os descritores */
+    FLASH    : ORIGIN = 0x08020000, LENGTH =  256K - 128
     RAM      : ORIGIN = 0x20000000, LENGTH =   80K
     SRAM2    : ORIGIN = 0x20014000, LENGTH =   16K
     CCMRAM   : ORIGIN = 0x10000000, LENGTH =   32K
 }
 
+/* RAMs liberadas para o `peek`/`poke`/`dump` (ver `memory_map` em src/main.rs) */
+__memmap_ram_start = ORIGIN(RAM);
+__memmap_ram_end = ORIGIN(RAM) + LENGTH(RAM);
+__memmap_sram2_start = ORIGIN(SRAM2);
+__memmap_sram2_end = ORIGIN(SRAM2) + LENGTH(SRAM2);
+__memmap_ccmram_start = ORIGIN(CCMRAM);
+__memmap_ccmram_end = ORIGIN(CCMRAM) + LENGTH(CCMRAM);
+
 
 /* # Sections */
 SECTIONS
@@ -0,0 +1,590 @@
+// Atualização de firmware A/B: layout da flash, estado da troca de slots e
+// verificação da imagem. Usado pelo bootloader (`boot/`) e pela aplicação
+// (`fw update` e a confirmação de que a imagem nova está saudável)
+//
+// Flash do STM32F407VG (1M):
+//   0x08000000  setor 0       16K  bootloader
+//   0x08004000  setores 1-3   48K  configuração e senhas da aplicação
+//   0x08010000  setor 4       64K  STATE: log do estado da troca
+//   0x08020000  setores 5-6  256K  ACTIVE: imagem em execução
+//   0x08060000  setores 7-8  256K  DFU: imagem nova (depois da troca, a anterior)
+//   0x080A0000  setores 9-10 256K  SCRATCH: cópia do ACTIVE durante a troca
+// (setor 11: aliases e autoexec da aplicação)
+//
+// Troca (atualização ou rollback): ACTIVE -> SCRATCH, DFU -> ACTIVE,
+// SCRATCH -> DFU. Cada passo concluído é gravado no log; depois de uma queda de
+// energia o passo seguinte é refeito do início. A imagem nova inicia em teste:
+// se não se confirmar saudável até o próximo reset (inclusive o do watchdog),
+// o bootloader troca de volta
+
+use crate::shell::sha256::{Sha256, DIGEST_LEN};
+
+// Endereços (a aplicação é ligada no ACTIVE, ver `memory.x`)
+pub const FLASH_BASE: u32 = 0x0800_0000;
+pub const ACTIVE_ADDR: u32 = 0x0802_0000;
+pub const SLOT_SIZE: usize = 256 * 1024;
+pub const STATE_SIZE: usize = 64 * 1024;
+
+// Gravação em palavras de 32 bits (exigência da flash do STM32F4)
+pub const WORD: usize = 4;
+
+// Descritor no fim do slot: "STFW", tamanho da imagem (u32) e SHA-256 dela.
+// O `fw update` recebe a imagem seguida do descritor (ver `tools/fwpack.py`)
+// e o copia para cá
+pub const DESCRIPTOR_LEN: usize = 8 + DIGEST_LEN;
+const DESCRIPTOR_MAGIC: [u8; 4] = *b"STFW";
+
+// Regiões usadas na troca
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region {
+    Active,
+    Dfu,
+    Scratch,
+    State,
+}
+
+// Início de cada região em relação a FLASH_BASE (mesma ordem de `Region`)
+pub const REGION_OFFSETS: [u32; 4] = [0x0002_0000, 0x0006_0000, 0x000A_0000, 0x0001_0000];
+
+// Flash com as regiões da troca (NOR: apagar põe 0xFF e gravar só troca bits 1
+// por 0; gravações alinhadas a WORD). Retornam false em falha
+pub trait BootFlash {
+    fn region_size(&self, region: Region) -> usize;
+    fn read(&self, region: Region, offset: usize, buf: &mut [u8]) -> bool;
+    fn write(&self, region: Region, offset: usize, data: &[u8]) -> bool;
+    fn erase(&self, region: Region) -> bool;
+}
+
+// Leitura, gravação ou apagamento falhou
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlashError;
+
+// Passo da troca já concluído
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    Requested, // Nada copiado ainda
+    Backup,    // ACTIVE copiado para o SCRATCH
+    Active,    // DFU copiado para o ACTIVE
+}
+
+// Estado gravado no log
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BootState {
+    Idle,           // Imagem confirmada (ou nunca houve atualização)
+    Update(Step),   // Troca para a imagem do DFU em andamento
+    Testing,        // Imagem nova rodando, ainda sem confirmação
+    Rollback(Step), // Volta para a imagem anterior em andamento
+    RolledBack,     // A imagem nova não se confirmou; a anterior voltou
+}
+
+impl BootState {
+    // Nome para `fw status`
+    pub fn name(self) -> &'static str {
+        match self {
+            BootState::Idle => "idle",
+            BootState::Update(_) => "update",
+            BootState::Testing => "testing",
+            BootState::Rollback(_) => "rollback",
+            BootState::RolledBack => "rolled_back",
+        }
+    }
+
+    // Registro do log: marcador, tipo, passo e verificação (uma palavra
+    // interrompida pela queda de energia não confere e é ignorada)
+    fn encode(self) -> [u8; WORD] {
+        let (kind, step) = match self {
+            BootState::Idle => (1, Step::Requested),
+            BootState::Update(step) => (2, step),
+            BootState::Testing => (3, Step::Requested),
+            BootState::Rollback(step) => (4, step),
+            BootState::RolledBack => (5, Step::Requested),
+        };
+        let step = step as u8;
+        [RECORD_MARK, kind, step, !(RECORD_MARK ^ kind ^ step)]
+    }
+
+    fn decode(record: [u8; WORD]) -> Option<Self> {
+        let [mark, kind, step, check] = record;
+        if mark != RECORD_MARK || check != !(mark ^ kind ^ step) {
+            return None;
+        }
+        let step = [Step::Requested, Step::Backup, Step::Active].get(step as usize).copied()?;
+        match kind {
+            1 => Some(BootState::Idle),
+            2 => Some(BootState::Update(step)),
+            3 => Some(BootState::Testing),
+            4 => Some(BootState::Rollback(step)),
+            5 => Some(BootState::RolledBack),
+            _ => None,
+        }
+    }
+}
+
+const RECORD_MARK: u8 = 0xB7;
+const ERASED: [u8; WORD] = [0xFF; WORD];
+
+// Registros de uma atualização completa com rollback (pedido, 2 passos, teste,
+// pedido de volta, 2 passos e o fim): o log nunca é apagado no meio de uma troca
+const MAX_RECORDS: usize = 8;
+
+// Último estado válido e a posição do próximo registro
+fn scan(flash: &dyn BootFlash) -> Result<(BootState, usize), FlashError> {
+    let size = flash.region_size(Region::State);
+    let mut state = BootState::Idle;
+    let mut record = [0u8; WORD];
+    let mut offset = 0;
+    while offset < size {
+        if !flash.read(Region::State, offset, &mut record) {
+            return Err(FlashError);
+        }
+        if record == ERASED {
+            break;
+        }
+        if let Some(decoded) = BootState::decode(record) {
+            state = decoded;
+        }
+        offset += WORD;
+    }
+    Ok((state, offset))
+}
+
+// Estado atual da troca
+pub fn state(flash: &dyn BootFlash) -> Result<BootState, FlashError> {
+    scan(flash).map(|(state, _)| state)
+}
+
+// Acrescenta um registro; com o log cheio recomeça o setor (só acontece fora
+// de uma troca, ver `request_update`)
+fn set_state(flash: &dyn BootFlash, state: BootState) -> Result<(), FlashError> {
+    let (_, mut offset) = scan(flash)?;
+    if offset + WORD > flash.region_size(Region::State) {
+        if !flash.erase(Region::State) {
+            return Err(FlashError);
+        }
+        offset = 0;
+    }
+    if !flash.write(Region::State, offset, &state.encode()) {
+        return Err(FlashError);
+    }
+    Ok(())
+}
+
+// Pede a troca para a imagem do DFU no próximo boot (a aplicação verifica a
+// imagem antes, ver `install`)
+pub fn request_update(flash: &dyn BootFlash) -> Result<(), FlashError> {
+    let (_, offset) = scan(flash)?;
+    if offset + MAX_RECORDS * WORD > flash.region_size(Region::State) && !flash.erase(Region::State) {
+        return Err(FlashError);
+    }
+    set_state(flash, BootState::Update(Step::Requested))
+}
+
+// A imagem em teste está saudável: fica (sem efeito fora do teste)
+pub fn mark_booted(flash: &dyn BootFlash) -> Result<(), FlashError> {
+    if state(flash)? == BootState::Testing {
+        set_state(flash, BootState::Idle)?;
+    }
+    Ok(())
+}
+
+// Copia uma região de slot inteira para outra (`buf`: espaço de trabalho,
+// múltiplo de WORD)
+fn copy(flash: &dyn BootFlash, from: Region, to: Region, buf: &mut [u8]) -> Result<(), FlashError> {
+    if !flash.erase(to) {
+        return Err(FlashError);
+    }
+    let size = flash.region_size(from).min(flash.region_size(to));
+    let mut offset = 0;
+    while offset < size {
+        let n = buf.len().min(size - offset);
+        let chunk = &mut buf[..n];
+        if !flash.read(from, offset, chunk) {
+            return Err(FlashError);
+        }
+        // Trechos apagados não precisam ser gravados
+        if chunk.iter().any(|&b| b != 0xFF) && !flash.write(to, offset, chunk) {
+            return Err(FlashError);
+        }
+        offset += chunk.len();
+    }
+    Ok(())
+}
+
+// Máquina de estados do bootloader: conclui uma troca pendente (ou começa o
+// rollback de uma imagem que não se confirmou). Retorna true se a imagem do
+// ACTIVE está em teste (o bootloader liga o watchdog antes de iniciá-la)
+pub fn boot(flash: &dyn BootFlash, buf: &mut [u8]) -> Result<bool, FlashError> {
+    loop {
+        let next = match state(flash)? {
+            BootState::Idle | BootState::RolledBack => return Ok(false),
+            // Reset antes da confirmação: volta para a imagem anterior
+            BootState::Testing => BootState::Rollback(Step::Requested),
+            // Imagem corrompida depois do pedido: desiste da troca
+            BootState::Update(Step::Requested) if verify(flash, Region::Dfu)?.is_none() => BootState::Idle,
+            BootState::Update(step) => {
+                swap_step(flash, step, buf)?;
+                match step {
+                    Step::Requested => BootState::Update(Step::Backup),
+                    Step::Backup => BootState::Update(Step::Active),
+                    Step::Active => BootState::Testing,
+                }
+            },
+            BootState::Rollback(step) => {
+                swap_step(flash, step, buf)?;
+                match step {
+                    Step::Requested => BootState::Rollback(Step::Backup),
+                    Step::Backup => BootState::Rollback(Step::Active),
+                    Step::Active => BootState::RolledBack,
+                }
+            },
+        };
+        set_state(flash, next)?;
+        if next == BootState::Testing {
+            return Ok(true);
+        }
+    }
+}
+
+// Passo seguinte ao já concluído
+fn swap_step(flash: &dyn BootFlash, done: Step, buf: &mut [u8]) -> Result<(), FlashError> {
+    match done {
+        Step::Requested => copy(flash, Region::Active, Region::Scratch, buf),
+        Step::Backup => copy(flash, Region::Dfu, Region::Active, buf),
+        Step::Active => copy(flash, Region::Scratch, Region::Dfu, buf),
+    }
+}
+
+// Imagem descrita no fim de um slot
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub len: usize,
+    pub digest: [u8; DIGEST_LEN],
+}
+
+// Descritor lido de `offset` (None se ausente ou com tamanho impossível)
+fn read_descriptor(flash: &dyn BootFlash, region: Region, offset: usize) -> Result<Option<ImageInfo>, FlashError> {
+    let mut raw = [0u8; DESCRIPTOR_LEN];
+    if !flash.read(region, offset, &mut raw) {
+        return Err(FlashError);
+    }
+    let (head, digest) = raw.split_at(8);
+    let len = u32::from_le_bytes([head[4], head[5], head[6], head[7]]) as usize;
+    // A imagem (mais o descritor recebido junto) não pode alcançar o fim do slot
+    // (`len` vem da imagem recebida: a soma não pode dar a volta em 32 bits)
+    let size = flash.region_size(region);
+    if head[..4] != DESCRIPTOR_MAGIC || len < 8 || len.checked_add(2 * DESCRIPTOR_LEN).is_none_or(|end| end > size) {
+        return Ok(None);
+    }
+    let mut info = ImageInfo { len, digest: [0; DIGEST_LEN] };
+    info.digest.copy_from_slice(digest);
+    Ok(Some(info))
+}
+
+// Imagem do slot, se o descritor existir (sem conferir o conteúdo)
+pub fn image_info(flash: &dyn BootFlash, region: Region) -> Result<Option<ImageInfo>, FlashError> {
+    read_descriptor(flash, region, flash.region_size(region) - DESCRIPTOR_LEN)
+}
+
+// Confere o descritor, o SHA-256 e o vetor de reset (a imagem precisa ter sido
+// ligada para o ACTIVE)
+pub fn verify(flash: &dyn BootFlash, region: Region) -> Result<Option<ImageInfo>, FlashError> {
+    let Some(info) = image_info(flash, region)? else {
+        return Ok(None);
+    };
+    let mut vectors = [0u8; 8];
+    if !flash.read(region, 0, &mut vectors) {
+        return Err(FlashError);
+    }
+    let reset = u32::from_le_bytes([vectors[4], vectors[5], vectors[6], vectors[7]]);
+    if !(ACTIVE_ADDR..ACTIVE_ADDR + info.len as u32).contains(&(reset & !1)) {
+        return Ok(None);
+    }
+
+    let mut hash = Sha256::new();
+    let mut chunk = [0u8; 256];
+    let mut offset = 0;
+    while offset < info.len {
+        let part = &mut chunk[..(info.len - offset).min(256)];
+        if !flash.read(region, offset, part) {
+            return Err(FlashError);
+        }
+        hash.update(part);
+        offset += part.len();
+    }
+    Ok((hash.finish() == info.digest).then_some(info))
+}
+
+// Imagem recebida no DFU com `len` bytes (imagem + descritor): copia o
+// descritor para o fim do slot, verifica e pede a troca. None = imagem inválida
+pub fn install(flash: &dyn BootFlash, len: usize) -> Result<Option<ImageInfo>, FlashError> {
+    let Some(start) = len.checked_sub(DESCRIPTOR_LEN) else {
+        return Ok(None);
+    };
+    let Some(info) = read_descriptor(flash, Region::Dfu, start)? else {
+        return Ok(None);
+    };
+    if info.len != start {
+        return Ok(None);
+    }
+    let mut raw = [0u8; DESCRIPTOR_LEN];
+    if !flash.read(Region::Dfu, start, &mut raw)
+        || !flash.write(Region::Dfu, flash.region_size(Region::Dfu) - DESCRIPTOR_LEN, &raw)
+    {
+        return Err(FlashError);
+    }
+    let Some(info) = verify(flash, Region::Dfu)? else {
+        return Ok(None);
+    };
+    request_update(flash)?;
+    Ok(Some(info))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::{Cell, RefCell};
+
+    use super::*;
+
+    // Flash simulada para testes no host (com queda de energia programável)
+    struct SimBootFlash<const SLOT: usize, const STATE: usize> {
+        slots: RefCell<[[u8; SLOT]; 3]>, // ACTIVE, DFU, SCRATCH
+        state: RefCell<[u8; STATE]>,
+        cut: Cell<Option<usize>>, // Operações restantes até a queda (None = nunca)
+    }
+
+    impl<const SLOT: usize, const STATE: usize> Default for SimBootFlash<SLOT, STATE> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<const SLOT: usize, const STATE: usize> SimBootFlash<SLOT, STATE> {
+        const fn new() -> Self {
+            Self {
+                slots: RefCell::new([[0xFF; SLOT]; 3]),
+                state: RefCell::new([0xFF; STATE]),
+                cut: Cell::new(None),
+            }
+        }
+
+        // Consome uma operação; false se a energia acabou
+        fn step(&self) -> bool {
+            match self.cut.get() {
+                Some(0) => false,
+                Some(n) => {
+                    self.cut.set(Some(n - 1));
+                    true
+                },
+                None => true,
+            }
+        }
+
+        fn with_region<T>(&self, region: Region, f: impl FnOnce(&mut [u8]) -> T) -> T {
+            match region {
+                Region::State => f(&mut self.state.borrow_mut()[..]),
+                slot => f(&mut self.slots.borrow_mut()[slot as usize][..]),
+            }
+        }
+    }
+
+    impl<const SLOT: usize, const STATE: usize> BootFlash for SimBootFlash<SLOT, STATE> {
+        fn region_size(&self, region: Region) -> usize {
+            if region == Region::State { STATE } else { SLOT }
+        }
+
+        fn read(&self, region: Region, offset: usize, buf: &mut [u8]) -> bool {
+            if self.cut.get() == Some(0) {
+                return false;
+            }
+            self.with_region(region, |data| match data.get(offset..offset + buf.len()) {
+                Some(data) => {
+                    buf.copy_from_slice(data);
+                    true
+                },
+                None => false,
+            })
+        }
+
+        fn write(&self, region: Region, offset: usize, data: &[u8]) -> bool {
+            if !offset.is_multiple_of(WORD) || !data.len().is_multiple_of(WORD) || offset + data.len() > self.region_size(region) {
+                return false;
+            }
+            self.with_region(region, |memory| {
+                for (i, word) in data.chunks(WORD).enumerate() {
+                    if !self.step() {
+                        return false; // Palavras anteriores ficam gravadas
+                    }
+                    for (j, &byte) in word.iter().enumerate() {
+                        memory[offset + i * WORD + j] &= byte; // NOR: só 1 -> 0
+                    }
+                }
+                true
+            })
+        }
+
+        fn erase(&self, region: Region) -> bool {
+            self.with_region(region, |memory| {
+                if !self.step() {
+                    let half = memory.len() / 2;
+                    memory[..half].fill(0xFF); // Apagamento interrompido
+                    return false;
+                }
+                memory.fill(0xFF);
+                true
+            })
+        }
+    }
+
+    const SLOT: usize = 4096;
+    type Flash = SimBootFlash<SLOT, 256>;
+
+    // Imagem de `len` bytes ligada no ACTIVE, seguida do descritor (como a
+    // gerada por `tools/fwpack.py`)
+    fn image(seed: u8, len: usize) -> Vec<u8> {
+        let mut data: Vec<u8> = (0..len).map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed)).collect();
+        data[4..8].copy_from_slice(&(ACTIVE_ADDR + 0x101).to_le_bytes());
+        let digest = Sha256::digest(&[&data]);
+        data.extend_from_slice(&DESCRIPTOR_MAGIC);
+        data.extend_from_slice(&(len as u32).to_le_bytes());
+        data.extend_from_slice(&digest);
+        data
+    }
+
+    fn slot(flash: &Flash, region: Region, len: usize) -> Vec<u8> {
+        flash.slots.borrow()[region as usize][..len].to_vec()
+    }
+
+    // ACTIVE com a imagem `old` (gravada pelo probe) e `new` recebida no DFU
+    // e instalada (troca pedida)
+    fn installed(old: &[u8], new: &[u8]) -> Flash {
+        let flash = Flash::new();
+        flash.slots.borrow_mut()[Region::Active as usize][..old.len()].copy_from_slice(old);
+        assert!(flash.write(Region::Dfu, 0, new));
+        assert!(install(&flash, new.len()).unwrap().is_some());
+        assert_eq!(state(&flash), Ok(BootState::Update(Step::Requested)));
+        flash
+    }
+
+    // Roda o bootloader com queda de energia depois de cada operação possível
+    // (`cut` = 0, 1, 2, ...); cada queda é seguida de um boot normal, que
+    // precisa terminar no mesmo resultado
+    fn boot_with_cuts(setup: impl Fn() -> Flash, check: impl Fn(&Flash, bool)) {
+        let mut buf = [0u8; 256];
+        for cut in 0.. {
+            let flash = setup();
+            flash.cut.set(Some(cut));
+            let first = boot(&flash, &mut buf);
+            flash.cut.set(None);
+            if let Ok(testing) = first {
+                // A energia durou o boot inteiro
+                check(&flash, testing);
+                assert!(cut > 3 * SLOT / 256, "sem operações suficientes para cobrir a troca");
+                break;
+            }
+            check(&flash, boot(&flash, &mut buf).unwrap());
+        }
+    }
+
+    #[test]
+    fn swap_installs_new_image_in_testing() {
+        let (old, new) = (image(1, 1000), image(2, 1200));
+        let flash = installed(&old, &new);
+        assert_eq!(boot(&flash, &mut [0u8; 256]), Ok(true));
+        assert_eq!(state(&flash), Ok(BootState::Testing));
+        assert_eq!(slot(&flash, Region::Active, new.len()), new);
+        assert_eq!(slot(&flash, Region::Dfu, old.len()), old);
+        assert_eq!(verify(&flash, Region::Active).unwrap().map(|info| info.len), Some(1200));
+    }
+
+    #[test]
+    fn confirmed_image_stays() {
+        let (old, new) = (image(1, 1000), image(2, 1200));
+        let flash = installed(&old, &new);
+        let mut buf = [0u8; 256];
+        assert_eq!(boot(&flash, &mut buf), Ok(true));
+        mark_booted(&flash).unwrap();
+        assert_eq!(state(&flash), Ok(BootState::Idle));
+        assert_eq!(boot(&flash, &mut buf), Ok(false));
+        assert_eq!(slot(&flash, Region::Active, new.len()), new);
+    }
+
+    #[test]
+    fn unconfirmed_image_rolls_back() {
+        let (old, new) = (image(1, 1000), image(2, 1200));
+        let flash = installed(&old, &new);
+        let mut buf = [0u8; 256];
+        assert_eq!(boot(&flash, &mut buf), Ok(true));
+        // Reset (ou watchdog) sem `mark_booted`
+        assert_eq!(boot(&flash, &mut buf), Ok(false));
+        assert_eq!(state(&flash), Ok(BootState::RolledBack));
+        assert_eq!(slot(&flash, Region::Active, old.len()), old);
+        assert_eq!(slot(&flash, Region::Dfu, new.len()), new);
+        // A confirmação não vale mais para a imagem descartada
+        mark_booted(&flash).unwrap();
+        assert_eq!(state(&flash), Ok(BootState::RolledBack));
+    }
+
+    #[test]
+    fn corrupt_update_is_abandoned() {
+        let (old, new) = (image(1, 1000), image(2, 1200));
+        let flash = installed(&old, &new);
+        flash.slots.borrow_mut()[Region::Dfu as usize][100] ^= 0x01;
+        assert_eq!(boot(&flash, &mut [0u8; 256]), Ok(false));
+        assert_eq!(state(&flash), Ok(BootState::Idle));
+        assert_eq!(slot(&flash, Region::Active, old.len()), old);
+    }
+
+    #[test]
+    fn swap_resumes_after_power_cut() {
+        let (old, new) = (image(1, 1000), image(2, 1200));
+        boot_with_cuts(
+            || installed(&old, &new),
+            |flash, testing| {
+                assert!(testing);
+                assert_eq!(state(flash), Ok(BootState::Testing));
+                assert_eq!(slot(flash, Region::Active, new.len()), new);
+                assert_eq!(slot(flash, Region::Dfu, old.len()), old);
+            },
+        );
+    }
+
+    #[test]
+    fn rollback_resumes_after_power_cut() {
+        let (old, new) = (image(1, 1000), image(2, 1200));
+        boot_with_cuts(
+            || {
+                let flash = installed(&old, &new);
+                assert_eq!(boot(&flash, &mut [0u8; 256]), Ok(true));
+                flash
+            },
+            |flash, testing| {
+                assert!(!testing);
+                assert_eq!(state(flash), Ok(BootState::RolledBack));
+                assert_eq!(slot(flash, Region::Active, old.len()), old);
+                assert_eq!(slot(flash, Region::Dfu, new.len()), new);
+            },
+        );
+    }
+
+    #[test]
+    fn oversized_descriptor_is_rejected() {
+        let mut new = image(2, 1200);
+        let at = new.len() - DESCRIPTOR_LEN + 4;
+        new[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let flash = Flash::new();
+        assert!(flash.write(Region::Dfu, 0, &new));
+        assert_eq!(install(&flash, new.len()), Ok(None));
+        assert_eq!(state(&flash), Ok(BootState::Idle));
+    }
+
+    #[test]
+    fn state_log_survives_torn_record() {
+        let flash = Flash::new();
+        set_state(&flash, BootState::Testing).unwrap();
+        // Registro interrompido no meio: não confere e é ignorado
+        flash.state.borrow_mut()[4..6].copy_from_slice(&[RECORD_MARK, 1]);
+        assert_eq!(state(&flash), Ok(BootState::Testing));
+        set_state(&flash, BootState::Idle).unwrap();
+        assert_eq!(state(&flash), Ok(BootState::Idle));
+    }
+}
@@ -0,0 +1,6 @@
+// Núcleo do shell independente do hardware (sem stdlib)
+// Pode ser compilado no host para testes com um transporte simulado
+#![cfg_attr(not(test), no_std)]
+
+pub mod boot;  // Troca de slots A/B do bootloader e da atualização (`fw update`)
+pub mod shell; // Shell/terminal genérico sobre embedded-io-async
@@ -2,9 +2,15 @@
 #![no_std]  // Não usar a biblioteca padrão do Rust
 #![no_main] // Ponto de entrada personalizado (não é main())
 
+mod rtt; // Transporte RTT (shell e logs defmt pelo probe de debug)
+
 // Importações de bibliotecas e módulos
+use cortex_m::peripheral::DWT; // Contador de ciclos (`ps`/`top`)
 use cortex_m_rt::pre_init; // Para código executado antes do main
 use core::arch::asm;      // Para assembly inline
+use core::cell::RefCell;  // Flash compartilhada entre shell e jobs
+use core::mem::MaybeUninit; // Memória não inicializada (seção .uninit)
+use core::ptr::{addr_of, addr_of_mut}; // Ponteiro para static sem criar referência
 use defmt::*;            // Framework de logging para embedded
 use embassy_executor::Spawner; // Executor assíncrono
 use embassy_stm32::peripherals::ADC1; // Periférico ADC1
@@ -12,25 +18,213 @@ use embassy_stm32::time::Hertz; // Tipo para frequência
 use embassy_stm32::Config; // Configuração do microcontrolador
 use embassy_stm32::adc::{self, Adc, AdcChannel, AnyAdcChannel, SampleTime}; // ADC
 use embassy_stm32::gpio::{Output, Pull, Level, Speed}; // GPIO
-use embassy_stm32::interrupt; // Interrupções
 use embassy_stm32::exti::ExtiInput; // Entrada com interrupção
-use embassy_time::{Duration, Timer}; // Temporizador
-use {defmt_rtt as _, panic_probe as _}; // Configuração de panic e logging
+use embassy_stm32::flash::{Blocking, Flash}; // Flash interna (aliases/autoexec)
+use embassy_stm32::peripherals::IWDG; // Watchdog da imagem em teste
+use embassy_stm32::wdg::IndependentWatchdog;
+use embassy_time::{Duration, Instant, Timer}; // Temporizador
+use panic_probe as _; // Configuração de panic (logs defmt vão pelo RTT, ver `rtt`)
 use embassy_stm32::bind_interrupts; // Vinculação de interrupções
-use embassy_stm32::usart::{self, Uart}; // Comunicação serial
-use heapless::String; // String de tamanho fixo (sem alocação dinâmica)
+use embassy_stm32::usart::{self, RingBufferedUartRx, Uart, UartTx}; // Comunicação serial
 use embassy_sync::blocking_mutex::raw::ThreadModeRawMutex;
+use embassy_sync::blocking_mutex::Mutex; // Monitor das tasks (ganchos do executor)
 use embassy_sync::channel::Channel;
-use itoa; // Biblioteca para conversão de números inteiros em strings
+use embedded_io_async::{ErrorType, Read, Write}; // Transportes das sessões do shell
+use static_cell::StaticCell; // Estado da aplicação compartilhado entre as sessões
+use rust_stm32g4_demo::shell::{App, Board, Shared, Shell}; // Núcleo do shell (biblioteca)
+use rust_stm32g4_demo::shell::startup::STARTUP_BYTES; // Tamanho dos aliases/autoexec
+use rust_stm32g4_demo::shell::auth::CREDENTIALS_BYTES; // Tamanho das senhas
+use rust_stm32g4_demo::shell::sha256::Sha256; // Mistura do sal das senhas
+use rust_stm32g4_demo::shell::params::Param; // Parâmetros ajustáveis (`set`)
+use rust_stm32g4_demo::shell::config::ConfigFlash; // Parâmetros gravados (`config`)
+use rust_stm32g4_demo::boot::{self, BootFlash, BootState, Region, FLASH_BASE, REGION_OFFSETS, SLOT_SIZE, STATE_SIZE}; // Atualização A/B
+use rust_stm32g4_demo::shell::memory::{MemoryRegion, Width}; // `peek`/`poke`/`dump`
+use rust_stm32g4_demo::shell::registers::{Field, Peripheral, Register}; // `reg`
+use rust_stm32g4_demo::shell::gpio::{self, PinId, PinMode}; // `gpio`
+use rust_stm32g4_demo::shell::tasks::TaskMonitor; // `ps`/`top`
+use embassy_stm32::pac; // Endereços dos periféricos
 
 // Variável global para controle do LED (acessada de forma unsafe)
 static mut LED_ENABLED: bool = true;
 
 static ADC_CHANNEL: Channel<ThreadModeRawMutex, u16, 32> = Channel::new();
 
+// Clock da CPU (contador de ciclos do monitor das tasks)
+const SYSCLK_HZ: u32 = 168_000_000;
+
+// Contadores das tasks, atualizados pelos ganchos de trace do executor (só o
+// executor de thread; por isso o mutex de thread mode)
+static TASKS: Mutex<ThreadModeRawMutex, RefCell<TaskMonitor>> =
+    Mutex::new(RefCell::new(TaskMonitor::new(SYSCLK_HZ, embassy_time::TICK_HZ as u32)));
+
+// Hardware, aliases e senhas: um só para todas as sessões do shell
+static APP: StaticCell<App<Hardware>> = StaticCell::new();
+
+// Buffers circulares do DMA de recepção das UARTs do shell (uma linha colada
+// inteira cabe antes de a sessão ler)
+const UART_RX_LEN: usize = 256;
+static USART1_RX: StaticCell<[u8; UART_RX_LEN]> = StaticCell::new();
+static USART2_RX: StaticCell<[u8; UART_RX_LEN]> = StaticCell::new();
+
+// Histórico do shell em RAM não inicializada: sobrevive a resets (não à falta
+// de energia - conteúdo inválido é descartado pela soma de verificação)
+#[link_section = ".uninit.SHELL_HISTORY"]
+static mut SHELL_HISTORY: MaybeUninit<[u8; 512]> = MaybeUninit::uninit();
+
+// Aliases e autoexec no setor 11 da flash (128K em 0x080E0000), depois dos
+// slots da atualização (ver `boot`)
+const STARTUP_OFFSET: u32 = 0x000E_0000;
+const STARTUP_SECTOR: u32 = 128 * 1024;
+
+// Senhas do login (hashes) no setor 3 (16K em 0x0800C000), separadas dos
+// aliases para que um não apague o outro
+const CREDENTIALS_OFFSET: u32 = 0x0000_C000;
+const SMALL_SECTOR: u32 = 16 * 1024;
+
+// Configuração (`config`) nos setores 1 e 2 (16K cada em 0x08004000 e
+// 0x08008000): o armazenamento alterna entre os dois
+const CONFIG_OFFSETS: [u32; 2] = [0x0000_4000, 0x0000_8000];
+
+// Imagem nova em teste: o bootloader liga o IWDG (~8 s); a aplicação o
+// alimenta e, depois de HEALTHY_MS rodando, confirma a imagem
+const WATCHDOG_US: u32 = 8_000_000;
+const WATCHDOG_PET_MS: u64 = 1000;
+const HEALTHY_MS: u64 = 10_000;
+
+// Flash inteira do STM32F407VG (o `memory.x` só cobre o slot ACTIVE)
+const FLASH_SIZE: u32 = 1024 * 1024;
+
+// Limites das RAMs, definidos no `memory.x` (o endereço do símbolo é o valor)
+extern "C" {
+    static __memmap_ram_start: u8;
+    static __memmap_ram_end: u8;
+    static __memmap_sram2_start: u8;
+    static __memmap_sram2_end: u8;
+    static __memmap_ccmram_start: u8;
+    static __memmap_ccmram_end: u8;
+}
+
+// Regiões do `peek`/`poke`/`dump`: RAMs do linker, flash (só leitura),
+// memória de sistema (ID único) e os blocos de periféricos que existem no F407
+// (RM0090, seção 2.3: ler um buraco reservado do AHB dá bus fault). Os
+// registradores que a leitura altera vêm antes, sem leitura: SR e DR das
+// USARTs (ler os dois limpa RXNE/ORE/IDLE), SR1/SR2/DR dos I2Cs, DR dos SPIs,
+// dos ADCs e da DCMI e as FIFOs do SDIO e do USB
+fn memory_map() -> [MemoryRegion; 38] {
+    let region = |name, start: *const u8, end: *const u8| MemoryRegion {
+        name,
+        start: start as u32,
+        end: end as u32,
+        writable: true,
+        readable: true,
+    };
+    let fixed = |name, start, end, writable| MemoryRegion { name, start, end, writable, readable: true };
+    let clears = |name, start, len| MemoryRegion { name, start, end: start + len, writable: true, readable: false };
+    [
+        clears("usart2", 0x4000_4400, 8),
+        clears("usart3", 0x4000_4800, 8),
+        clears("uart4", 0x4000_4C00, 8),
+        clears("uart5", 0x4000_5000, 8),
+        clears("usart1", 0x4001_1000, 8),
+        clears("usart6", 0x4001_1400, 8),
+        clears("i2c1", 0x4000_5410, 0x0C),
+        clears("i2c2", 0x4000_5810, 0x0C),
+        clears("i2c3", 0x4000_5C10, 0x0C),
+        clears("spi2", 0x4000_380C, 4),
+        clears("spi3", 0x4000_3C0C, 4),
+        clears("spi1", 0x4001_300C, 4),
+        clears("adc1", 0x4001_204C, 4),
+        clears("adc2", 0x4001_214C, 4),
+        clears("adc3", 0x4001_224C, 4),
+        clears("sdio_fifo", 0x4001_2C80, 0x80),
+        clears("dcmi_dr", 0x5005_0028, 4),
+        clears("otg_hs_fifo", 0x4004_1000, 0x1_0000),
+        clears("otg_fs_fifo", 0x5000_1000, 0x1_0000),
+        region("sram", addr_of!(__memmap_ram_start), addr_of!(__memmap_ram_end)),
+        region("sram2", addr_of!(__memmap_sram2_start), addr_of!(__memmap_sram2_end)),
+        region("ccmram", addr_of!(__memmap_ccmram_start), addr_of!(__memmap_ccmram_end)),
+        fixed("flash", FLASH_BASE, FLASH_BASE + FLASH_SIZE, false),
+        fixed("system", 0x1FFF_0000, 0x1FFF_7A20, false),
+        fixed("apb1", 0x4000_0000, 0x4000_8000, true),
+        fixed("apb2", 0x4001_0000, 0x4001_4C00, true),
+        // AHB1
+        fixed("gpio", 0x4002_0000, 0x4002_2400, true), // GPIOA a GPIOI
+        fixed("crc", 0x4002_3000, 0x4002_3400, true),
+        fixed("rcc", 0x4002_3800, 0x4002_3C00, true),
+        fixed("flash_if", 0x4002_3C00, 0x4002_4000, true),
+        fixed("bkpsram", 0x4002_4000, 0x4002_5000, true),
+        fixed("dma1", 0x4002_6000, 0x4002_6400, true),
+        fixed("dma2", 0x4002_6400, 0x4002_6800, true),
+        fixed("eth", 0x4002_8000, 0x4002_9400, true),
+        fixed("otg_hs", 0x4004_0000, 0x4005_1000, true), // Registradores e FIFOs
+        // AHB2 (o F407 não tem CRYP nem HASH)
+        fixed("otg_fs", 0x5000_0000, 0x5001_1000, true),
+        fixed("dcmi", 0x5005_0000, 0x5005_0400, true),
+        fixed("rng", 0x5006_0800, 0x5006_0C00, true),
+    ]
+}
+
+// Periféricos do `reg`: `PERIPHERALS`, gerado pelo build.rs do metadata do chip
+include!(concat!(env!("OUT_DIR"), "/peripherals.rs"));
+
+// Pinos dos periféricos da aplicação (o `gpio` não os reconfigura)
+const CLAIMED_PINS: [(&str, &str); 13] = [
+    ("PA0", "button"),
+    ("PA1", "adc"),
+    ("PA2", "usart2"),
+    ("PA3", "usart2"),
+    ("PA9", "usart1"),
+    ("PA10", "usart1"),
+    ("PA13", "swd"), // Probe (e o RTT)
+    ("PA14", "swd"),
+    ("PB3", "swo"),
+    ("PD12", "led"),
+    ("PD13", "led"),
+    ("PH0", "hse"), // Cristal
+    ("PH1", "hse"),
+];
+
+// Porta GPIO de um pino (GPIOA a GPIOI ficam a 0x400 bytes uma da outra)
+fn gpio_port(pin: PinId) -> pac::gpio::Gpio {
+    unsafe { pac::gpio::Gpio::from_ptr((pac::GPIOA.as_ptr() as usize + 0x400 * pin.port as usize) as _) }
+}
+
+// Ganchos de trace do embassy-executor (feature `trace`)
+#[no_mangle]
+fn _embassy_trace_task_new(_executor: u32, task: u32) {
+    TASKS.lock(|tasks| tasks.borrow_mut().task_new(task));
+}
+
+#[no_mangle]
+fn _embassy_trace_task_exec_begin(_executor: u32, task: u32) {
+    let ticks = Instant::now().as_ticks();
+    TASKS.lock(|tasks| tasks.borrow_mut().exec_begin(task, DWT::cycle_count(), ticks));
+}
+
+#[no_mangle]
+fn _embassy_trace_task_exec_end(_executor: u32, _task: u32) {
+    let ticks = Instant::now().as_ticks();
+    TASKS.lock(|tasks| tasks.borrow_mut().exec_end(DWT::cycle_count(), ticks));
+}
+
+// Chamado também de interrupções (wakers): não mexe no monitor
+#[no_mangle]
+fn _embassy_trace_task_ready_begin(_executor: u32, _task: u32) {}
+
+#[no_mangle]
+fn _embassy_trace_executor_idle(_executor: u32) {
+    let ticks = Instant::now().as_ticks();
+    TASKS.lock(|tasks| tasks.borrow_mut().idle(ticks));
+}
+
+// Dá nome à task recém-criada pelo `spawn` (para `ps`/`top`)
+fn name_task(name: &'static str) {
+    TASKS.lock(|tasks| tasks.borrow_mut().name_last(name));
+}
+
 // Task para leitura ADC
 #[embassy_executor::task]
-async fn adc_task(mut adc: adc::Adc<'static, ADC1>, mut adc_pin: AnyAdcChannel<ADC1>) {
+async fn adc_task(mut adc: adc::Adc<'static, ADC1>, mut adc_pin: AnyAdcChannel<ADC1>, app: &'static App<Hardware>) {
     adc.set_sample_time(SampleTime::CYCLES144);
 
     loop {
@@ -40,177 +234,359 @@ async fn adc_task(mut adc: adc::Adc<'static, ADC1>, mut adc_pin: AnyAdcChannel<A
             let _ = ADC_CHANNEL.try_receive();
         }
         let _ = ADC_CHANNEL.try_send(measured);
-        Timer::after_millis(100).await; // Intervalo menor para mais amostras
+        // Intervalo lido a cada ciclo: `set adc_period` vale na próxima leitura
+        Timer::after_millis(app.params.get(Param::AdcPeriod) as u64).await;
     }
 }
 
-// Vinculação de interrupções para a USART1
+// Vinculação de interrupções para as USARTs do shell
 bind_interrupts!(struct Irqs {
     USART1 => embassy_stm32::usart::InterruptHandler<embassy_stm32::peripherals::USART1>;
+    USART2 => embassy_stm32::usart::InterruptHandler<embassy_stm32::peripherals::USART2>;
 });
 
-// Estrutura para armazenar e processar comandos do shell
-struct ShellCommand {
-    buffer: String<64>, // Buffer para armazenar o comando (tamanho máximo 64 bytes)
-    ready: bool,       // Flag indicando se o comando está pronto para processamento
+// Acesso ao hardware para o shell (LED global, canal do ADC e flash)
+struct Hardware {
+    flash: RefCell<Flash<'static, Blocking>>,
+    memory: [MemoryRegion; 38], // Ver `memory_map`
 }
 
-impl ShellCommand {
-    // Cria uma nova instância do ShellCommand
-    fn new() -> Self {
-        Self {
-            buffer: String::new(), // Buffer vazio
-            ready: false,         // Comando não está pronto
+impl Board for Hardware {
+    fn led_enabled(&self) -> bool {
+        unsafe { LED_ENABLED }
+    }
+
+    fn set_led_enabled(&self, enabled: bool) {
+        unsafe { LED_ENABLED = enabled; }
+    }
+
+    fn try_adc_sample(&self) -> Option<u16> {
+        ADC_CHANNEL.try_receive().ok()
+    }
+
+    async fn delay_ms(&self, ms: u64) {
+        Timer::after_millis(ms).await;
+    }
+
+    fn uptime_ms(&self) -> u64 {
+        Instant::now().as_millis()
+    }
+
+    fn with_history_store(&self, f: &mut dyn FnMut(&mut [u8])) {
+        // Apenas o shell_task acessa este buffer (e só durante a chamada)
+        f(unsafe { &mut *(*addr_of_mut!(SHELL_HISTORY)).as_mut_ptr() })
+    }
+
+    fn load_startup(&self, buf: &mut [u8]) {
+        let _ = self.flash.borrow_mut().blocking_read(STARTUP_OFFSET, buf);
+    }
+
+    fn store_startup(&self, data: &[u8]) -> bool {
+        let mut buf = [0xFFu8; STARTUP_BYTES];
+        self.store_sector(STARTUP_OFFSET, STARTUP_SECTOR, data, &mut buf)
+    }
+
+    fn load_credentials(&self, buf: &mut [u8]) {
+        let _ = self.flash.borrow_mut().blocking_read(CREDENTIALS_OFFSET, buf);
+    }
+
+    fn store_credentials(&self, data: &[u8]) -> bool {
+        let mut buf = [0xFFu8; CREDENTIALS_BYTES.next_multiple_of(4)];
+        self.store_sector(CREDENTIALS_OFFSET, SMALL_SECTOR, data, &mut buf)
+    }
+
+    fn config_flash(&self) -> Option<&dyn ConfigFlash> {
+        Some(self)
+    }
+
+    fn boot_flash(&self) -> Option<&dyn BootFlash> {
+        Some(self)
+    }
+
+    fn reset(&self) {
+        cortex_m::peripheral::SCB::sys_reset();
+    }
+
+    fn memory_map(&self) -> &[MemoryRegion] {
+        &self.memory
+    }
+
+    fn peripherals(&self) -> &[Peripheral] {
+        &PERIPHERALS
+    }
+
+    fn read_memory(&self, address: u32, width: Width) -> u32 {
+        let address = address as usize;
+        unsafe {
+            match width {
+                Width::Byte => core::ptr::read_volatile(address as *const u8) as u32,
+                Width::Half => core::ptr::read_volatile(address as *const u16) as u32,
+                Width::Word => core::ptr::read_volatile(address as *const u32),
+            }
         }
     }
 
-    // Adiciona um caractere ao buffer de comando
-    fn add_char(&mut self, c: char) {
-        if c == '\r' || c == '\n' { // Enter - marca o comando como pronto
-            self.ready = true;
-        } else if c == '\x08' || c == '\x7f' { // Backspace - remove último caractere
-            self.buffer.pop();
-        } else if self.buffer.len() < 63 { // Adiciona caractere se houver espaço
-            let _ = self.buffer.push(c);
+    fn write_memory(&self, address: u32, width: Width, value: u32) {
+        let address = address as usize;
+        unsafe {
+            match width {
+                Width::Byte => core::ptr::write_volatile(address as *mut u8, value as u8),
+                Width::Half => core::ptr::write_volatile(address as *mut u16, value as u16),
+                Width::Word => core::ptr::write_volatile(address as *mut u32, value),
+            }
         }
     }
 
-    // Retorna o comando se estiver pronto
-    fn get_command(&mut self) -> Option<String<64>> {
-        if self.ready {
-            let cmd = self.buffer.clone(); // Copia o comando
-            self.buffer.clear(); // Limpa o buffer
-            self.ready = false;  // Reseta a flag
-            Some(cmd)           // Retorna o comando
-        } else {
-            None
+    // LQFP100 (VG): portas A a E inteiras e só PH0/PH1 da porta H
+    fn has_pin(&self, pin: PinId) -> bool {
+        pin.port <= 4 || (pin.port == 7 && pin.number <= 1)
+    }
+
+    fn configure_pin(&self, pin: PinId, mode: PinMode, pull: gpio::Pull) {
+        use pac::gpio::vals::{Moder, Ot, Pupdr};
+        let port = gpio_port(pin);
+        let n = pin.number as usize;
+        // Os registradores da porta são compartilhados com os pinos do embassy
+        cortex_m::interrupt::free(|_| {
+            pac::RCC.ahb1enr().modify(|w| w.0 |= 1 << pin.port);
+            port.otyper().modify(|w| w.set_ot(n, if mode == PinMode::OpenDrain { Ot::OPEN_DRAIN } else { Ot::PUSH_PULL }));
+            port.pupdr().modify(|w| {
+                w.set_pupdr(n, match pull {
+                    gpio::Pull::None => Pupdr::FLOATING,
+                    gpio::Pull::Up => Pupdr::PULL_UP,
+                    gpio::Pull::Down => Pupdr::PULL_DOWN,
+                })
+            });
+            port.moder().modify(|w| w.set_moder(n, if mode == PinMode::Input { Moder::INPUT } else { Moder::OUTPUT }));
+        });
+    }
+
+    fn release_pin(&self, pin: PinId) {
+        use pac::gpio::vals::{Moder, Ot, Pupdr};
+        let port = gpio_port(pin);
+        let n = pin.number as usize;
+        cortex_m::interrupt::free(|_| {
+            port.moder().modify(|w| w.set_moder(n, Moder::ANALOG));
+            port.otyper().modify(|w| w.set_ot(n, Ot::PUSH_PULL));
+            port.pupdr().modify(|w| w.set_pupdr(n, Pupdr::FLOATING));
+        });
+    }
+
+    fn read_pin(&self, pin: PinId) -> bool {
+        gpio_port(pin).idr().read().idr(pin.number as usize) == pac::gpio::vals::Idr::HIGH
+    }
+
+    fn write_pin(&self, pin: PinId, high: bool) {
+        // BSRR: escrita atômica, sem ler a porta
+        let n = pin.number as usize;
+        gpio_port(pin).bsrr().write(|w| if high { w.set_bs(n, true) } else { w.set_br(n, true) });
+    }
+
+    fn task_monitor(&self) -> Option<TaskMonitor> {
+        let ticks = Instant::now().as_ticks();
+        Some(TASKS.lock(|tasks| {
+            let mut tasks = tasks.borrow_mut();
+            tasks.update(ticks);
+            tasks.clone()
+        }))
+    }
+
+    fn salt(&self, salt: &mut [u8]) {
+        // ID único do chip + instante da troca: difere entre placas e entre senhas
+        let ticks = Instant::now().as_ticks().to_le_bytes();
+        let digest = Sha256::digest(&[&embassy_stm32::uid::uid()[..], &ticks]);
+        for (byte, value) in salt.iter_mut().zip(digest) {
+            *byte = value;
         }
     }
 }
 
+impl ConfigFlash for Hardware {
+    fn sector_size(&self) -> usize {
+        SMALL_SECTOR as usize
+    }
 
+    fn read(&self, sector: usize, offset: usize, buf: &mut [u8]) -> bool {
+        self.flash.borrow_mut().blocking_read(CONFIG_OFFSETS[sector] + offset as u32, buf).is_ok()
+    }
 
-fn adc_to_voltage(adc_value: u16, vref_mv: u32) -> u32 {
-    (adc_value as u32 * vref_mv) / 4095
+    fn write(&self, sector: usize, offset: usize, data: &[u8]) -> bool {
+        self.flash.borrow_mut().blocking_write(CONFIG_OFFSETS[sector] + offset as u32, data).is_ok()
+    }
+
+    fn erase(&self, sector: usize) -> bool {
+        let start = CONFIG_OFFSETS[sector];
+        self.flash.borrow_mut().blocking_erase(start, start + SMALL_SECTOR).is_ok()
+    }
 }
 
-// Função para processar comandos recebidos
-async fn process_command(cmd: &str, uart: &mut Uart<'static, embassy_stm32::mode::Async>) {
-    // Processa o comando e gera a resposta apropriada
-    let response = match cmd.trim() {
-"help" => "Comandos disponíveis:\r\n- help: Mostra esta ajuda\r\n- led on/off/toggle\r\n- status\r\n- adc cont: Mostra leituras ADC (q para sair)\r\n",        "led on" => {
-            unsafe { LED_ENABLED = true; }
-            "LED ligado\r\n"
-        },
-        "led off" => {
-            unsafe { LED_ENABLED = false; }
-            "LED desligado\r\n"
-        },
-        "led toggle" => {
-            unsafe { LED_ENABLED = !LED_ENABLED; }
-            if unsafe { LED_ENABLED } {
-                "LED ligado\r\n"
-            } else {
-                "LED desligado\r\n"
-            }
-        },
-        "status" => {
-            if unsafe { LED_ENABLED } {
-                "Sistema OK - LED ativo\r\n"
-            } else {
-                "Sistema OK - LED inativo\r\n"
-            }
-        },
-      "adc cont" => {
-    const VREF_MV: u32 = 3300; // 3.3V em mV
-    const CORRECTION_FACTOR: u32 = 33333; // 1/0.27 ≈ 3.7037 (escalado x10000)
-    
-    uart.write(b"Modo continuo (q + Enter para sair):\r\n").await.unwrap();
-    uart.write(b"Formato: [valor bruto] -> [tensao] mV\r\n").await.unwrap();
-    
-    let mut exit = false;
-    let mut buffer = [0u8; 64];
-    let mut cmd_buf = [0u8; 1];
-    
-    while !exit {
-        // Verificação não-bloqueante com timeout
-        match uart.read_until_idle(&mut cmd_buf).await {
-            Ok(_) => {
-                if cmd_buf[0] == b'q' {
-                    exit = true;
-                    // Limpa buffer adicional se necessário
-                    while uart.read_until_idle(&mut cmd_buf).await.is_ok() {}
-                }
-            },
-            Err(_) => {} // Timeout ou outro erro - continuamos normalmente
+impl BootFlash for Hardware {
+    fn region_size(&self, region: Region) -> usize {
+        if region == Region::State { STATE_SIZE } else { SLOT_SIZE }
+    }
+
+    fn read(&self, region: Region, offset: usize, buf: &mut [u8]) -> bool {
+        self.flash.borrow_mut().blocking_read(REGION_OFFSETS[region as usize] + offset as u32, buf).is_ok()
+    }
+
+    fn write(&self, region: Region, offset: usize, data: &[u8]) -> bool {
+        self.flash.borrow_mut().blocking_write(REGION_OFFSETS[region as usize] + offset as u32, data).is_ok()
+    }
+
+    fn erase(&self, region: Region) -> bool {
+        let start = REGION_OFFSETS[region as usize];
+        self.flash.borrow_mut().blocking_erase(start, start + self.region_size(region) as u32).is_ok()
+    }
+}
+
+impl Hardware {
+    // Apaga o setor de `size` bytes em `offset` (bloqueia até ~1s) e grava
+    // `data` em palavras de 32 bits (`buf` é o espaço para completar com 0xFF)
+    fn store_sector(&self, offset: u32, size: u32, data: &[u8], buf: &mut [u8]) -> bool {
+        let Some(dest) = buf.get_mut(..data.len()) else {
+            return false;
+        };
+        dest.copy_from_slice(data);
+        let len = data.len().next_multiple_of(4);
+
+        let mut flash = self.flash.borrow_mut();
+        flash.blocking_erase(offset, offset + size).is_ok()
+            && flash.blocking_write(offset, &buf[..len]).is_ok()
+    }
+}
+
+// Uma sessão do shell sobre qualquer transporte (tasks do embassy não podem
+// ser genéricas: cada tipo de transporte tem a sua task abaixo). A sessão
+// principal roda o autoexec e guarda o histórico; cada uma tem echo, idioma e
+// formato de saída próprios, e todas compartilham o `App`
+async fn shell_session(rx: PortRx, tx: PortTx, app: &'static App<Hardware>, primary: bool, autoexec: bool) {
+    // O núcleo do shell é genérico (embedded-io-async) e fica na biblioteca;
+    // a saída é compartilhada com os jobs em segundo plano da sessão
+    let shared = Shared::new(tx, app);
+    let mut shell = Shell::new(rx, &shared);
+    shell.set_autoexec(primary && autoexec);
+    shell.set_history_store(primary);
+    if shell.run().await.is_err() {
+        warn!("Sessão do shell encerrada por erro no transporte");
+    }
+}
+
+// Task do shell no RTT (pelo probe de debug)
+#[embassy_executor::task]
+async fn rtt_shell_task(rx: rtt::RttRx, tx: rtt::RttTx, app: &'static App<Hardware>) {
+    shell_session(PortRx::Rtt(rx), PortTx::Rtt(tx), app, false, false).await;
+}
+
+// Task do shell numa UART (uma instância por USART). A recepção usa DMA
+// circular em `rx_buf`: só a versão com buffer implementa `Read`
+#[embassy_executor::task(pool_size = 2)]
+async fn uart_shell_task(
+    uart: Uart<'static, embassy_stm32::mode::Async>,
+    rx_buf: &'static mut [u8],
+    config: usart::Config,
+    app: &'static App<Hardware>,
+    primary: bool,
+    autoexec: bool,
+) {
+    let (tx, rx) = uart.split();
+    let rx = PortRx::Uart(rx.into_ring_buffered(rx_buf));
+    shell_session(rx, PortTx::Uart(UartOut { tx, config, app }), app, primary, autoexec).await;
+}
+
+// Transportes das sessões num tipo só: o shell é instanciado uma vez para
+// todos (uma cópia por transporte não cabe no slot da atualização, ver `boot`)
+enum PortRx {
+    Uart(RingBufferedUartRx<'static>),
+    Rtt(rtt::RttRx),
+}
+
+enum PortTx {
+    Uart(UartOut),
+    Rtt(rtt::RttTx),
+}
+
+// O RTT não falha; os erros são sempre da UART
+impl ErrorType for PortRx {
+    type Error = usart::Error;
+}
+
+impl ErrorType for PortTx {
+    type Error = usart::Error;
+}
+
+impl Read for PortRx {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, usart::Error> {
+        match self {
+            PortRx::Uart(rx) => Read::read(rx, buf).await,
+            PortRx::Rtt(rx) => rx.read(buf).await.map_err(|never| match never {}),
         }
-        
-        // Processa leituras ADC
-        while let Ok(raw_value) = ADC_CHANNEL.try_receive() {
-            let raw_mv = (raw_value as u32 * VREF_MV) / 4095;
-            let real_mv = (raw_mv * CORRECTION_FACTOR) / 10000;
-            
-            uart.write(b"ADC: ").await.unwrap();
-            uart.write(itoa::Buffer::new().format(raw_value).as_bytes()).await.unwrap();
-            uart.write(b" -> ").await.unwrap();
-            uart.write(itoa::Buffer::new().format(real_mv).as_bytes()).await.unwrap();
-            uart.write(b" mV\r\n").await.unwrap();
+    }
+}
+
+impl Write for PortTx {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, usart::Error> {
+        match self {
+            PortTx::Uart(tx) => tx.write(buf).await,
+            PortTx::Rtt(tx) => tx.write(buf).await.map_err(|never| match never {}),
         }
-        
-        Timer::after_millis(50).await;
     }
-    
-    uart.write(b"Modo continuo encerrado\r\n").await.unwrap();
-    ""
-},
-        "" => "", // Comando vazio (não faz nada)
-        _ => "Comando não reconhecido. Digite 'help' para ajuda.\r\n",
-    };
 
-    // Envia a resposta se não for vazia
-    if !response.is_empty() {
-        uart.write(response.as_bytes()).await.unwrap();
+    async fn flush(&mut self) -> Result<(), usart::Error> {
+        match self {
+            PortTx::Uart(tx) => tx.flush().await,
+            PortTx::Rtt(tx) => tx.flush().await.map_err(|never| match never {}),
+        }
     }
 }
 
-// Task principal do shell/terminal
-#[embassy_executor::task]
-async fn shell_task(mut uart: Uart<'static, embassy_stm32::mode::Async>) {
-    let mut shell_cmd = ShellCommand::new(); // Inicializa o processador de comandos
-    let mut buffer = [0u8; 1]; // Buffer para leitura de um caractere por vez
+// Saída de uma UART do shell que aplica o parâmetro `baud` antes de escrever
+// (a resposta do próprio `set baud` já sai na nova velocidade)
+struct UartOut {
+    tx: UartTx<'static, embassy_stm32::mode::Async>,
+    config: usart::Config,
+    app: &'static App<Hardware>,
+}
 
-    // Mensagem de boas-vindas
-    let welcome_msg = "\r\n=== STM32F407 Shell Terminal ===\r\n";
-    uart.write(welcome_msg.as_bytes()).await.unwrap();
-    let prompt_msg = "Digite 'help' para ver os comandos disponíveis.\r\nstm32> ";
-    uart.write(prompt_msg.as_bytes()).await.unwrap();
+impl ErrorType for UartOut {
+    type Error = usart::Error;
+}
 
-    loop {
-        // Lê um caractere da UART
-        uart.read(&mut buffer).await.unwrap();
-        let received_char = buffer[0] as char;
-
-        // Echo do caractere (exceto para caracteres especiais)
-        if received_char.is_ascii_graphic() || received_char == ' ' {
-            uart.write(&buffer).await.unwrap();
-        } else if received_char == '\r' {
-            uart.write("\r\n".as_bytes()).await.unwrap();
-        } else if received_char == '\x08' || received_char == '\x7f' {
-            // Backspace - remove caractere do terminal (espaço + backspace)
-            uart.write("\x08 \x08".as_bytes()).await.unwrap();
+impl Write for UartOut {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, usart::Error> {
+        let baud = self.app.params.get(Param::Baud) as u32;
+        if baud != self.config.baudrate {
+            Write::flush(&mut self.tx).await?; // Termina o que saía na velocidade antiga
+            self.config.baudrate = baud;
+            if self.tx.set_config(&self.config).is_err() {
+                warn!("Velocidade não suportada: {}", baud);
+            }
         }
+        Write::write(&mut self.tx, buf).await
+    }
 
-        // Adiciona o caractere ao buffer de comando
-        shell_cmd.add_char(received_char);
+    async fn flush(&mut self) -> Result<(), usart::Error> {
+        Write::flush(&mut self.tx).await
+    }
+}
 
-        // Verifica se o comando está pronto para processamento
-        if let Some(command) = shell_cmd.get_command() {
-            // Processa o comando
-            process_command(&command, &mut uart).await;
-            
-            // Mostra o prompt novamente
-            uart.write("stm32> ".as_bytes()).await.unwrap();
+// Task da imagem em teste: alimenta o watchdog ligado pelo bootloader e
+// confirma a imagem nova depois de HEALTHY_MS (travou ou reiniciou antes
+// disso: o bootloader volta à anterior)
+#[embassy_executor::task]
+async fn health_task(mut watchdog: IndependentWatchdog<'static, IWDG>, app: &'static App<Hardware>) {
+    watchdog.unleash();
+    let started = Instant::now();
+    let mut confirmed = false;
+    loop {
+        watchdog.pet();
+        if !confirmed && started.elapsed() >= Duration::from_millis(HEALTHY_MS) {
+            confirmed = true;
+            match boot::mark_booted(&app.board) {
+                Ok(()) => info!("Imagem nova confirmada"),
+                Err(_) => warn!("Falha ao confirmar a imagem nova"),
+            }
         }
+        Timer::after_millis(WATCHDOG_PET_MS).await;
     }
 }
 
@@ -240,9 +616,11 @@ async fn button_task(mut button: ExtiInput<'static>) {
 }
 
 // Variáveis em seções especiais de memória (CCMRAM e DATA2)
+#[used]
 #[link_section = ".ccmram"]
 static mut TESTE: i32 = 60;
 
+#[used]
 #[link_section = ".data2"]
 static mut TESTE2: i32 = 70;
 
@@ -283,8 +661,18 @@ unsafe fn before_main() {
 // Função principal (executada após o pre_init)
 #[embassy_executor::main]
 async fn main(spawner: Spawner) {
-    // Habilita clock para GPIOC (unsafe pois acessa registrador diretamente)
-    unsafe { embassy_stm32::pac::RCC.ahb1enr().modify(|r| r.set_gpiocen(true)); }
+    // A task do `main` já foi criada pelo executor; o contador de ciclos mede
+    // a duração dos polls (ver `TASKS`)
+    name_task("main");
+    let mut core = unsafe { cortex_m::Peripherals::steal() };
+    core.DCB.enable_trace();
+    core.DWT.enable_cycle_counter();
+
+    // Canais RTT antes de qualquer log defmt
+    let (rtt_rx, rtt_tx) = rtt::init();
+
+    // Habilita clock para GPIOC (registrador direto, pelo PAC)
+    pac::RCC.ahb1enr().modify(|r| r.set_gpiocen(true));
     
     // Configuração do sistema de clock
     let mut config = Config::default();
@@ -317,16 +705,18 @@ async fn main(spawner: Spawner) {
 
     // Configuração dos periféricos:
     // - Botão com interrupção (PA0)
-    let mut button = ExtiInput::new(p.PA0, p.EXTI0, Pull::Down);
+    let button = ExtiInput::new(p.PA0, p.EXTI0, Pull::Down);
+    // Botão pressionado no reset: pula o autoexec do shell
+    let autoexec = button.is_low();
     // - ADC1
     let adc = Adc::new(p.ADC1);
 
-    // Configuração da UART (2400 baud, 8N1)
+    // Configuração das UARTs (8N1; velocidade inicial do parâmetro `baud`)
     let mut uart_config = usart::Config::default();
-    uart_config.baudrate = 2400;
+    uart_config.baudrate = Param::Baud.spec().default as u32;
     
     // Inicializa a UART1 (TX=PA9, RX=PA10) com DMA
-    let usart = Uart::new(
+    let usart1 = Uart::new(
         p.USART1, 
         p.PA10, // RX
         p.PA9,  // TX
@@ -336,27 +726,64 @@ async fn main(spawner: Spawner) {
         uart_config
     ).unwrap();
 
+    // Inicializa a UART2 (TX=PA2, RX=PA3) com DMA: segunda sessão do shell
+    let usart2 = Uart::new(
+        p.USART2,
+        p.PA3, // RX
+        p.PA2, // TX
+        Irqs,  // Interrupções
+        p.DMA1_CH6, // DMA para TX
+        p.DMA1_CH5, // DMA para RX
+        uart_config
+    ).unwrap();
+
+    // Estado comum às sessões (lê aliases, autoexec, senhas e parâmetros da flash)
+    let app: &'static App<Hardware> = APP.init(App::new(Hardware {
+        flash: RefCell::new(Flash::new_blocking(p.FLASH)),
+        memory: memory_map(),
+    }));
+    app.load();
+    for (pin, owner) in CLAIMED_PINS {
+        app.pins.claim(PinId::parse(pin).unwrap(), owner).unwrap();
+    }
+
     // Spawn das tasks assíncronas:
     // - Task do ADC (leitura contínua)
-    spawner.spawn(adc_task(adc, p.PA1.degrade_adc())).unwrap();
+    spawner.spawn(adc_task(adc, p.PA1.degrade_adc(), app)).unwrap();
+    name_task("adc");
     // - Task do botão (tratamento de interrupção)
     spawner.spawn(button_task(button)).unwrap();
-    // - Task do shell (interface serial)
-    spawner.spawn(shell_task(usart)).unwrap();
+    name_task("button");
+    // - Sessões do shell (USART1 é a principal; RTT sem adaptador serial)
+    let rx_buf = USART1_RX.init([0; UART_RX_LEN]);
+    spawner.spawn(uart_shell_task(usart1, rx_buf, uart_config, app, true, autoexec)).unwrap();
+    name_task("shell1");
+    let rx_buf = USART2_RX.init([0; UART_RX_LEN]);
+    spawner.spawn(uart_shell_task(usart2, rx_buf, uart_config, app, false, autoexec)).unwrap();
+    name_task("shell2");
+    spawner.spawn(rtt_shell_task(rtt_rx, rtt_tx, app)).unwrap();
+    name_task("rtt");
+    // - Imagem nova em teste (depois de um `fw update`): watchdog e confirmação
+    if boot::state(&app.board) == Ok(BootState::Testing) {
+        let watchdog = IndependentWatchdog::new(p.IWDG, WATCHDOG_US);
+        spawner.spawn(health_task(watchdog, app)).unwrap();
+        name_task("health");
+    }
 
     // Configura LEDs como saídas (PD12 e PD13)
     let mut led1 = Output::new(p.PD12, Level::High, Speed::Low);
-    let mut led2 = Output::new(p.PD13, Level::High, Speed::Low);
+    let _led2 = Output::new(p.PD13, Level::High, Speed::Low); // Fica aceso
 
     // Loop principal - pisca o LED1 conforme o estado global
     loop {
         unsafe {
             if LED_ENABLED {
-                // Pisca o LED a cada 500ms se habilitado
+                // Pisca o LED se habilitado (tempo do parâmetro `blink_period`)
+                let period = app.params.get(Param::BlinkPeriod) as u64;
                 led1.set_high();
-                Timer::after_millis(500).await;
+                Timer::after_millis(period).await;
                 led1.set_low();
-                Timer::after_millis(500).await;
+                Timer::after_millis(period).await;
             } else {
                 // Mantém LED desligado e espera 100ms
                 led1.set_low();
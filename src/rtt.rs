@@ -0,0 +1,76 @@
+// Transporte do shell sobre RTT: o probe de debug lê e escreve buffers na RAM
+// (sem adaptador USB-serial). Não há interrupção quando o host envia dados,
+// então a espera é feita por polling
+//
+// Canais (ver `init`):
+// - up 0 / down 0: terminal do shell (`probe-rs attach`, cargo-embed, RTT Viewer)
+// - up 1: logs defmt (decodificados pelo probe-rs)
+
+use core::convert::Infallible;
+use embassy_time::Timer;
+use embedded_io_async::{ErrorType, Read, Write};
+use rtt_target::{rtt_init, ChannelMode, DownChannel, UpChannel};
+
+// Intervalo de verificação dos buffers
+const POLL_MS: u64 = 10;
+
+// Cria o bloco de controle RTT com os canais do shell e do defmt e direciona
+// os logs defmt ao seu canal (chamar antes de qualquer log)
+pub fn init() -> (RttRx, RttTx) {
+    let channels = rtt_init! {
+        up: {
+            0: { size: 1024, mode: ChannelMode::NoBlockTrim, name: "Terminal" }
+            1: { size: 1024, mode: ChannelMode::NoBlockSkip, name: "defmt" }
+        }
+        down: {
+            0: { size: 64, name: "Terminal" }
+        }
+    };
+    rtt_target::set_defmt_channel(channels.up.1);
+    (RttRx(channels.down.0), RttTx(channels.up.0))
+}
+
+// Entrada do shell (canal down 0)
+pub struct RttRx(DownChannel);
+
+impl ErrorType for RttRx {
+    type Error = Infallible;
+}
+
+impl Read for RttRx {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let n = self.0.read(buf);
+            if n > 0 {
+                return Ok(n);
+            }
+            Timer::after_millis(POLL_MS).await;
+        }
+    }
+}
+
+// Saída do shell (canal up 0). Sem o host lendo, o buffer enche e a sessão
+// espera; as outras sessões não são afetadas
+pub struct RttTx(UpChannel);
+
+impl ErrorType for RttTx {
+    type Error = Infallible;
+}
+
+impl Write for RttTx {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let n = self.0.write(buf);
+            if n > 0 {
+                return Ok(n);
+            }
+            Timer::after_millis(POLL_MS).await;
+        }
+    }
+}
@@ -0,0 +1,518 @@
+// Tokenizador da linha de comando e extração tipada de argumentos
+// (inteiros decimais/hex, valores com unidade como `500ms` ou `1.2V`, enums)
+
+use core::fmt;
+use heapless::{String, Vec};
+
+use super::editor::LINE_LEN;
+use super::i18n::{Lang, Msg};
+
+// Limites da linha tokenizada
+pub const MAX_TOKENS: usize = 8;          // Nome do comando + argumentos
+pub const TOKEN_BYTES: usize = LINE_LEN; // Texto de todos os tokens (sem aspas/escapes)
+
+// Unidade aceita por um argumento de ponto fixo
+pub struct Unit {
+    pub suffix: &'static str, // Sufixo digitado (ex: "ms")
+    pub scale: i64,           // Fator para a unidade base (ex: "s" = 1000 ms)
+}
+
+// Tempo em milissegundos
+pub static TIME: &[Unit] = &[Unit { suffix: "ms", scale: 1 }, Unit { suffix: "s", scale: 1000 }];
+
+// Tensão em milivolts
+pub static VOLTAGE: &[Unit] = &[Unit { suffix: "mV", scale: 1 }, Unit { suffix: "V", scale: 1000 }];
+
+// Um token com sua posição na linha original (em caracteres)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str, // Texto sem aspas e com escapes resolvidos
+    pub col: usize,    // Coluna inicial na linha digitada
+    pub width: usize,  // Largura na linha digitada (inclui aspas)
+}
+
+// Tipos de erro de tokenização e de argumento
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArgErrorKind {
+    UnterminatedQuote,                   // Aspas sem fechamento
+    BadEscape,                           // Sequência de escape desconhecida
+    TooManyTokens,                       // Mais tokens que MAX_TOKENS / TOKEN_BYTES
+    Missing(Msg),                        // Argumento obrigatório ausente
+    Unexpected,                          // Argumento a mais
+    InvalidInt,                          // Não é um inteiro
+    InvalidNumber,                       // Não é um número decimal
+    OutOfRange { min: i64, max: i64 },   // Fora do intervalo permitido
+    BadUnit(&'static [Unit]),            // Unidade desconhecida
+    Precision,                           // Casas decimais além da unidade base
+    NotInChoices(&'static [&'static str]), // Valor fora da lista
+}
+
+// Erro apontando para uma posição da linha
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArgError {
+    pub kind: ArgErrorKind,
+    pub col: usize,   // Coluna do token com problema
+    pub width: usize, // Largura a destacar
+}
+
+impl ArgError {
+    fn at(kind: ArgErrorKind, token: &Token) -> Self {
+        Self { kind, col: token.col, width: token.width }
+    }
+}
+
+impl PartialEq for Unit {
+    fn eq(&self, other: &Self) -> bool {
+        self.suffix == other.suffix && self.scale == other.scale
+    }
+}
+
+impl Eq for Unit {}
+
+impl fmt::Debug for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.suffix)
+    }
+}
+
+impl ArgErrorKind {
+    // Código estável do erro (modo json)
+    pub fn code(&self) -> &'static str {
+        match self {
+            ArgErrorKind::UnterminatedQuote => "unterminated_quote",
+            ArgErrorKind::BadEscape => "bad_escape",
+            ArgErrorKind::TooManyTokens => "too_many_tokens",
+            ArgErrorKind::Missing(_) => "missing_argument",
+            ArgErrorKind::Unexpected => "unexpected_argument",
+            ArgErrorKind::InvalidInt => "invalid_int",
+            ArgErrorKind::InvalidNumber => "invalid_number",
+            ArgErrorKind::OutOfRange { .. } => "out_of_range",
+            ArgErrorKind::BadUnit(_) => "bad_unit",
+            ArgErrorKind::Precision => "precision",
+            ArgErrorKind::NotInChoices(_) => "invalid_choice",
+        }
+    }
+
+    // Descrição do erro no idioma pedido
+    pub fn describe(&self, lang: Lang) -> Describe<'_> {
+        Describe { kind: self, lang }
+    }
+}
+
+// Erro de argumento formatado em um idioma
+pub struct Describe<'a> {
+    kind: &'a ArgErrorKind,
+    lang: Lang,
+}
+
+impl fmt::Display for Describe<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = |msg: Msg| msg.text(self.lang);
+        match self.kind {
+            ArgErrorKind::UnterminatedQuote => f.write_str(text(Msg::UnterminatedQuote)),
+            ArgErrorKind::BadEscape => f.write_str(text(Msg::BadEscape)),
+            ArgErrorKind::TooManyTokens => f.write_str(text(Msg::TooManyTokens)),
+            ArgErrorKind::Missing(name) => write!(f, "{} <{}>", text(Msg::MissingArg), text(*name)),
+            ArgErrorKind::Unexpected => f.write_str(text(Msg::UnexpectedArg)),
+            ArgErrorKind::InvalidInt => f.write_str(text(Msg::InvalidInt)),
+            ArgErrorKind::InvalidNumber => f.write_str(text(Msg::InvalidNumber)),
+            ArgErrorKind::OutOfRange { min, max } => write!(f, "{} [{}, {}]", text(Msg::OutOfRange), min, max),
+            ArgErrorKind::BadUnit(units) => {
+                f.write_str(text(Msg::BadUnit))?;
+                for (i, unit) in units.iter().enumerate() {
+                    f.write_str(if i > 0 { ", " } else { " " })?;
+                    f.write_str(unit.suffix)?;
+                }
+                f.write_str(")")
+            },
+            ArgErrorKind::Precision => f.write_str(text(Msg::Precision)),
+            ArgErrorKind::NotInChoices(choices) => {
+                f.write_str(text(Msg::NotInChoices))?;
+                for (i, choice) in choices.iter().enumerate() {
+                    f.write_str(if i > 0 { "|" } else { " " })?;
+                    f.write_str(choice)?;
+                }
+                f.write_str(")")
+            },
+        }
+    }
+}
+
+// Posição de um token no buffer e na linha original
+#[derive(Clone, Copy)]
+struct Span {
+    start: usize, // Início no buffer (bytes)
+    end: usize,   // Fim no buffer (bytes)
+    col: usize,   // Coluna na linha (caracteres)
+    width: usize, // Largura na linha (caracteres)
+}
+
+// Linha tokenizada: tratamento de espaços, aspas ('...' e "...") e escapes (\)
+pub struct Tokens {
+    buf: String<TOKEN_BYTES>,
+    spans: Vec<Span, MAX_TOKENS>,
+    end_col: usize, // Coluna após o último caractere da linha
+}
+
+impl Tokens {
+    // Separa a linha em tokens
+    pub fn parse(line: &str) -> Result<Self, ArgError> {
+        let mut tokens = Self { buf: String::new(), spans: Vec::new(), end_col: 0 };
+        let mut quote: Option<(char, usize)> = None; // Aspas abertas e sua coluna
+        let mut current: Option<Span> = None;        // Token em construção
+        let mut chars = line.chars().enumerate();
+
+        while let Some((col, c)) = chars.next() {
+            tokens.end_col = col + 1;
+
+            // Dentro de aspas simples tudo é literal
+            if let Some(('\'', _)) = quote {
+                if c == '\'' {
+                    quote = None;
+                } else {
+                    tokens.push_char(c, col)?;
+                }
+                continue;
+            }
+
+            match c {
+                '\\' => {
+                    let Some((_, next)) = chars.next() else {
+                        return Err(ArgError { kind: ArgErrorKind::BadEscape, col, width: 1 });
+                    };
+                    tokens.end_col = col + 2;
+                    let value = match next {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        '\\' | '"' | '\'' | ' ' | ';' | '&' => next, // ';' e '&' literais (ver `chain`)
+                        _ => return Err(ArgError { kind: ArgErrorKind::BadEscape, col, width: 2 }),
+                    };
+                    current = Some(tokens.start(current, col));
+                    tokens.push_char(value, col)?;
+                },
+                '"' | '\'' if quote.is_none() => {
+                    current = Some(tokens.start(current, col));
+                    quote = Some((c, col));
+                },
+                '"' => quote = None, // Fecha aspas duplas
+                c if c.is_whitespace() && quote.is_none() => {
+                    if let Some(span) = current.take() {
+                        tokens.finish(span, col)?;
+                    }
+                },
+                _ => {
+                    current = Some(tokens.start(current, col));
+                    tokens.push_char(c, col)?;
+                },
+            }
+        }
+
+        if let Some((_, col)) = quote {
+            return Err(ArgError { kind: ArgErrorKind::UnterminatedQuote, col, width: 1 });
+        }
+        if let Some(span) = current {
+            let end_col = tokens.end_col;
+            tokens.finish(span, end_col)?;
+        }
+        Ok(tokens)
+    }
+
+    // Inicia um token (ou continua o atual)
+    fn start(&self, current: Option<Span>, col: usize) -> Span {
+        current.unwrap_or(Span { start: self.buf.len(), end: 0, col, width: 0 })
+    }
+
+    // Acrescenta um caractere ao token atual
+    fn push_char(&mut self, c: char, col: usize) -> Result<(), ArgError> {
+        self.buf
+            .push(c)
+            .map_err(|_| ArgError { kind: ArgErrorKind::TooManyTokens, col, width: 1 })
+    }
+
+    // Fecha o token atual
+    fn finish(&mut self, mut span: Span, end_col: usize) -> Result<(), ArgError> {
+        span.end = self.buf.len();
+        span.width = end_col - span.col;
+        let (col, width) = (span.col, span.width);
+        self.spans
+            .push(span)
+            .map_err(|_| ArgError { kind: ArgErrorKind::TooManyTokens, col, width })
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    // Token na posição `index`
+    pub fn get(&self, index: usize) -> Option<Token<'_>> {
+        self.spans.get(index).map(|span| Token {
+            text: &self.buf[span.start..span.end],
+            col: span.col,
+            width: span.width,
+        })
+    }
+
+    // Argumentos a partir do segundo token (o primeiro é o nome do comando)
+    pub fn args(&self) -> Args<'_> {
+        Args { tokens: self, first: 1 }
+    }
+}
+
+// Argumentos de um comando com extração tipada
+pub struct Args<'a> {
+    tokens: &'a Tokens,
+    first: usize,
+}
+
+impl<'a> Args<'a> {
+    pub fn len(&self) -> usize {
+        self.tokens.len().saturating_sub(self.first)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Token do argumento `index` (se presente)
+    pub fn get(&self, index: usize) -> Option<Token<'a>> {
+        self.tokens.get(self.first + index)
+    }
+
+    // Argumento obrigatório; ausente gera erro no fim da linha
+    pub fn token(&self, index: usize, name: Msg) -> Result<Token<'a>, ArgError> {
+        self.get(index).ok_or(ArgError {
+            kind: ArgErrorKind::Missing(name),
+            col: self.tokens.end_col,
+            width: 1,
+        })
+    }
+
+    // Texto do argumento
+    pub fn str(&self, index: usize) -> Option<&'a str> {
+        self.get(index).map(|t| t.text)
+    }
+
+    // Inteiro decimal ou hexadecimal (0x..) dentro de [min, max]
+    pub fn int(&self, index: usize, name: Msg, min: i64, max: i64) -> Result<i64, ArgError> {
+        let token = self.token(index, name)?;
+        check_range(parse_int(token.text), min, max).map_err(|kind| ArgError::at(kind, &token))
+    }
+
+    // Valor de ponto fixo com unidade, convertido para a unidade base
+    pub fn fixed(&self, index: usize, name: Msg, units: &'static [Unit], min: i64, max: i64) -> Result<i64, ArgError> {
+        let token = self.token(index, name)?;
+        check_range(parse_fixed(token.text, units), min, max).map_err(|kind| ArgError::at(kind, &token))
+    }
+
+    // Índice do valor na lista de opções
+    pub fn choice(&self, index: usize, name: Msg, choices: &'static [&'static str]) -> Result<usize, ArgError> {
+        let token = self.token(index, name)?;
+        choices
+            .iter()
+            .position(|c| *c == token.text)
+            .ok_or(ArgError::at(ArgErrorKind::NotInChoices(choices), &token))
+    }
+
+    // Erro para o primeiro argumento além de `count`
+    pub fn expect_at_most(&self, count: usize) -> Result<(), ArgError> {
+        match self.get(count) {
+            Some(token) => Err(ArgError::at(ArgErrorKind::Unexpected, &token)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn check_range(value: Result<i64, ArgErrorKind>, min: i64, max: i64) -> Result<i64, ArgErrorKind> {
+    let value = value?;
+    if value < min || value > max {
+        Err(ArgErrorKind::OutOfRange { min, max })
+    } else {
+        Ok(value)
+    }
+}
+
+// Separa o sinal do restante do texto
+fn split_sign(text: &str) -> (bool, &str) {
+    match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    }
+}
+
+// Converte "123", "-5", "0x1F" ou "0b101" em inteiro
+pub fn parse_int(text: &str) -> Result<i64, ArgErrorKind> {
+    let (negative, digits) = split_sign(text);
+    let (radix, digits) = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        (16, hex)
+    } else if let Some(bin) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+        (2, bin)
+    } else {
+        (10, digits)
+    };
+    if digits.is_empty() {
+        return Err(ArgErrorKind::InvalidInt);
+    }
+
+    let mut value: i64 = 0;
+    for c in digits.chars() {
+        let digit = c.to_digit(radix).ok_or(ArgErrorKind::InvalidInt)?;
+        value = value
+            .checked_mul(radix as i64)
+            .and_then(|v| v.checked_add(digit as i64))
+            .ok_or(ArgErrorKind::OutOfRange { min: i64::MIN, max: i64::MAX })?;
+    }
+    Ok(if negative { -value } else { value })
+}
+
+// Converte "500ms", "1.2V" ou "250" (unidade base) para a unidade base
+pub fn parse_fixed(text: &str, units: &'static [Unit]) -> Result<i64, ArgErrorKind> {
+    let (negative, rest) = split_sign(text);
+    let number_len = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    let (number, suffix) = rest.split_at(number_len);
+
+    let scale = if suffix.is_empty() {
+        1
+    } else {
+        units
+            .iter()
+            .find(|u| u.suffix == suffix)
+            .map(|u| u.scale)
+            .ok_or(ArgErrorKind::BadUnit(units))?
+    };
+
+    let (int_part, frac_part) = number.split_once('.').unwrap_or((number, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return Err(ArgErrorKind::InvalidNumber);
+    }
+    if !int_part.bytes().all(|b| b.is_ascii_digit()) || !frac_part.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ArgErrorKind::InvalidNumber);
+    }
+
+    let overflow = ArgErrorKind::OutOfRange { min: i64::MIN, max: i64::MAX };
+    let mut value: i64 = 0;
+    for b in int_part.bytes() {
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add((b - b'0') as i64))
+            .ok_or(overflow)?;
+    }
+    value = value.checked_mul(scale).ok_or(overflow)?;
+
+    // Parte fracionária precisa caber exatamente na unidade base
+    let mut frac: i64 = 0;
+    let mut divisor: i64 = 1;
+    for b in frac_part.bytes() {
+        frac = frac.checked_mul(10).and_then(|v| v.checked_add((b - b'0') as i64)).ok_or(overflow)?;
+        divisor = divisor.checked_mul(10).ok_or(ArgErrorKind::Precision)?;
+    }
+    let scaled = frac.checked_mul(scale).ok_or(overflow)?;
+    if scaled % divisor != 0 {
+        return Err(ArgErrorKind::Precision);
+    }
+    value = value.checked_add(scaled / divisor).ok_or(overflow)?;
+
+    Ok(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(line: &str) -> std::vec::Vec<std::string::String> {
+        let tokens = Tokens::parse(line).unwrap();
+        (0..tokens.len()).map(|i| tokens.get(i).unwrap().text.into()).collect()
+    }
+
+    fn kind(line: &str) -> ArgErrorKind {
+        Tokens::parse(line).err().unwrap().kind
+    }
+
+    #[test]
+    fn quotes_and_escapes() {
+        assert_eq!(texts("  set  x   1 "), ["set", "x", "1"]);
+        assert_eq!(texts(r#"echo "a b" 'c "d"' e"f"g"#), ["echo", "a b", r#"c "d""#, "efg"]);
+        assert_eq!(texts(r#"echo a\ b \"x\" \; \& \\"#), ["echo", "a b", "\"x\"", ";", "&", "\\"]);
+        assert_eq!(texts(r#"echo "tab\there" 'no\t'"#), ["echo", "tab\there", "no\\t"]);
+        assert_eq!(texts(r#"echo "" x"#), ["echo", "", "x"]);
+        assert_eq!(kind(r#"echo "aberta"#), ArgErrorKind::UnterminatedQuote);
+        assert_eq!(kind("echo 'aberta"), ArgErrorKind::UnterminatedQuote);
+        assert_eq!(kind(r"echo \q"), ArgErrorKind::BadEscape);
+        assert_eq!(kind("echo \\"), ArgErrorKind::BadEscape);
+    }
+
+    #[test]
+    fn positions_include_quotes() {
+        let tokens = Tokens::parse(r#"led "on" x"#).unwrap();
+        let on = tokens.get(1).unwrap();
+        assert_eq!((on.text, on.col, on.width), ("on", 4, 4));
+        let x = tokens.get(2).unwrap();
+        assert_eq!((x.col, x.width), (9, 1));
+        // Argumento ausente aponta para o fim da linha
+        let error = tokens.args().token(2, Msg::ArgId).unwrap_err();
+        assert_eq!((error.kind, error.col), (ArgErrorKind::Missing(Msg::ArgId), 10));
+    }
+
+    #[test]
+    fn too_many_tokens() {
+        assert_eq!(Tokens::parse("a b c d e f g h").unwrap().len(), MAX_TOKENS);
+        let error = Tokens::parse("a b c d e f g h i").err().unwrap();
+        assert_eq!((error.kind, error.col, error.width), (ArgErrorKind::TooManyTokens, 16, 1));
+        let long = "x".repeat(TOKEN_BYTES + 1);
+        assert_eq!(kind(&long), ArgErrorKind::TooManyTokens);
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(parse_int("123"), Ok(123));
+        assert_eq!(parse_int("-5"), Ok(-5));
+        assert_eq!(parse_int("+7"), Ok(7));
+        assert_eq!(parse_int("0x1F"), Ok(31));
+        assert_eq!(parse_int("0X1f"), Ok(31));
+        assert_eq!(parse_int("0b101"), Ok(5));
+        assert_eq!(parse_int("0B101"), Ok(5));
+        assert_eq!(parse_int("-0x10"), Ok(-16));
+        for bad in ["", "-", "0x", "0b2", "12a", "1 2", "٣"] {
+            assert_eq!(parse_int(bad), Err(ArgErrorKind::InvalidInt), "{:?}", bad);
+        }
+        assert!(matches!(parse_int("0x8000000000000000"), Err(ArgErrorKind::OutOfRange { .. })));
+    }
+
+    #[test]
+    fn ranges() {
+        let tokens = Tokens::parse("cmd 10 0x20 -1").unwrap();
+        let args = tokens.args();
+        assert_eq!(args.int(0, Msg::ArgId, 0, 10), Ok(10));
+        assert_eq!(args.int(1, Msg::ArgId, 0, 0xFF), Ok(0x20));
+        let error = args.int(2, Msg::ArgId, 0, 10).unwrap_err();
+        assert_eq!((error.kind, error.col, error.width), (ArgErrorKind::OutOfRange { min: 0, max: 10 }, 12, 2));
+        assert_eq!(args.expect_at_most(3), Ok(()));
+        assert_eq!(args.expect_at_most(2).unwrap_err().kind, ArgErrorKind::Unexpected);
+    }
+
+    #[test]
+    fn fixed_point_units() {
+        assert_eq!(parse_fixed("500ms", TIME), Ok(500));
+        assert_eq!(parse_fixed("1.5s", TIME), Ok(1500));
+        assert_eq!(parse_fixed(".25s", TIME), Ok(250));
+        assert_eq!(parse_fixed("250", TIME), Ok(250));
+        assert_eq!(parse_fixed("-1.2V", VOLTAGE), Ok(-1200));
+        assert_eq!(parse_fixed("3300mV", VOLTAGE), Ok(3300));
+        assert_eq!(parse_fixed("1.0005s", TIME), Err(ArgErrorKind::Precision));
+        assert_eq!(parse_fixed("1.5ms", TIME), Err(ArgErrorKind::Precision));
+        assert_eq!(parse_fixed("2min", TIME), Err(ArgErrorKind::BadUnit(TIME)));
+        assert_eq!(parse_fixed("1.2.3s", TIME), Err(ArgErrorKind::InvalidNumber));
+        assert_eq!(parse_fixed("s", TIME), Err(ArgErrorKind::InvalidNumber));
+        assert!(matches!(parse_fixed("9999999999999999999s", TIME), Err(ArgErrorKind::OutOfRange { .. })));
+
+        let tokens = Tokens::parse("sleep 2s").unwrap();
+        assert_eq!(tokens.args().fixed(0, Msg::ArgId, TIME, 0, 1000).unwrap_err().kind, ArgErrorKind::OutOfRange { min: 0, max: 1000 });
+    }
+}
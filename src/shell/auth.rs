@@ -0,0 +1,276 @@
+// Login do shell: senhas de usuário e administrador guardadas como hash
+// SHA-256 com sal (iterado), níveis de privilégio dos comandos, bloqueio após
+// tentativas erradas e fim da sessão por inatividade
+
+use core::cell::{Cell, RefCell};
+
+use super::frame::crc16;
+use super::sha256::{Sha256, DIGEST_LEN};
+
+// Tentativas erradas seguidas antes do bloqueio
+pub const MAX_ATTEMPTS: u8 = 3;
+// Tempo de bloqueio após MAX_ATTEMPTS erros
+pub const LOCKOUT_MS: u64 = 30_000;
+// Sessão sem digitação por este tempo volta ao login
+pub const SESSION_TIMEOUT_MS: u64 = 5 * 60_000;
+
+// Sal por senha e rodadas do hash (deixa a força bruta offline mais lenta)
+pub const SALT_LEN: usize = 16;
+const ROUNDS: usize = 1000;
+
+// Cópia persistente: "AUTH" + presença (bit 0 = user, bit 1 = admin) + 2 hashes + CRC-16
+const MAGIC: [u8; 4] = *b"AUTH";
+const ENTRY_LEN: usize = SALT_LEN + DIGEST_LEN;
+pub const CREDENTIALS_BYTES: usize = 4 + 1 + 2 * ENTRY_LEN + 2;
+
+// Nível de privilégio de uma sessão (e o mínimo exigido por um comando)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Guest, // Sessão aguardando a senha (nenhum comando)
+    User,  // Comandos de operação
+    Admin, // Configuração persistente e acesso ao hardware
+}
+
+// Níveis com senha (mesma ordem de `Level::USERS`)
+pub const LEVELS: &[&str] = &["user", "admin"];
+
+impl Level {
+    pub const USERS: [Level; 2] = [Level::User, Level::Admin];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Guest => "guest",
+            Level::User => "user",
+            Level::Admin => "admin",
+        }
+    }
+}
+
+// Hash de uma senha com o sal usado
+#[derive(Clone, Copy)]
+struct PasswordHash {
+    salt: [u8; SALT_LEN],
+    digest: [u8; DIGEST_LEN],
+}
+
+impl PasswordHash {
+    fn new(salt: [u8; SALT_LEN], password: &str) -> Self {
+        let mut digest = Sha256::digest(&[&salt, password.as_bytes()]);
+        for _ in 1..ROUNDS {
+            digest = Sha256::digest(&[&salt, &digest]);
+        }
+        Self { salt, digest }
+    }
+
+    // Compara sem sair no primeiro byte diferente
+    fn matches(&self, password: &str) -> bool {
+        let other = Self::new(self.salt, password);
+        self.digest.iter().zip(other.digest).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+// Falhas ao trocar uma senha
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthError {
+    NoAdmin, // Senha de usuário sem senha de administrador
+}
+
+// Falhas do login
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoginError {
+    Failed, // Senha errada
+    Locked, // Bloqueado por erros demais (a senha nem é conferida)
+}
+
+// Senhas configuradas e o bloqueio do login (compartilhados entre as sessões:
+// os erros de todas as portas contam juntos)
+#[derive(Default)]
+pub struct Auth {
+    hashes: RefCell<[Option<PasswordHash>; 2]>, // user, admin
+    attempts: Cell<u8>,                         // Senhas erradas seguidas
+    locked_until: Cell<u64>,                    // Fim do bloqueio (`Board::uptime_ms`)
+}
+
+impl Auth {
+    pub const fn new() -> Self {
+        Self { hashes: RefCell::new([None; 2]), attempts: Cell::new(0), locked_until: Cell::new(0) }
+    }
+
+    // O login só existe com uma senha de administrador definida
+    pub fn enabled(&self) -> bool {
+        self.hashes.borrow()[1].is_some()
+    }
+
+    // Nível liberado pela senha (o mais alto que confere)
+    pub fn verify(&self, password: &str) -> Option<Level> {
+        let hashes = self.hashes.borrow();
+        Level::USERS
+            .iter()
+            .zip(hashes.iter())
+            .rev()
+            .find(|(_, hash)| hash.is_some_and(|h| h.matches(password)))
+            .map(|(level, _)| *level)
+    }
+
+    // Tentativa de login no instante `now` (ms); MAX_ATTEMPTS erros seguidos,
+    // de qualquer sessão, bloqueiam todas por LOCKOUT_MS
+    pub fn login(&self, password: &str, now: u64) -> Result<Level, LoginError> {
+        if now < self.locked_until.get() {
+            return Err(LoginError::Locked);
+        }
+        if let Some(level) = self.verify(password) {
+            self.attempts.set(0);
+            return Ok(level);
+        }
+        let attempts = self.attempts.get() + 1;
+        if attempts < MAX_ATTEMPTS {
+            self.attempts.set(attempts);
+            return Err(LoginError::Failed);
+        }
+        self.attempts.set(0);
+        self.locked_until.set(now + LOCKOUT_MS);
+        Err(LoginError::Locked)
+    }
+
+    // Define a senha de um nível (vazia remove; remover a de admin desliga o login)
+    pub fn set(&self, level: Level, password: &str, salt: [u8; SALT_LEN]) -> Result<(), AuthError> {
+        let mut hashes = self.hashes.borrow_mut();
+        let hash = (!password.is_empty()).then(|| PasswordHash::new(salt, password));
+        match level {
+            Level::Admin => {
+                hashes[1] = hash;
+                if hash.is_none() {
+                    hashes[0] = None;
+                }
+            },
+            _ if hashes[1].is_none() => return Err(AuthError::NoAdmin),
+            _ => hashes[0] = hash,
+        }
+        Ok(())
+    }
+
+    // Grava as senhas em `buf` (pelo menos CREDENTIALS_BYTES)
+    pub fn save(&self, buf: &mut [u8]) -> usize {
+        let hashes = self.hashes.borrow();
+        buf[..4].copy_from_slice(&MAGIC);
+        let mut present = 0;
+        for (i, hash) in hashes.iter().enumerate() {
+            let entry = &mut buf[5 + i * ENTRY_LEN..5 + (i + 1) * ENTRY_LEN];
+            match hash {
+                Some(hash) => {
+                    present |= 1 << i;
+                    entry[..SALT_LEN].copy_from_slice(&hash.salt);
+                    entry[SALT_LEN..].copy_from_slice(&hash.digest);
+                },
+                None => entry.fill(0),
+            }
+        }
+        buf[4] = present;
+        let crc = crc16(&buf[..CREDENTIALS_BYTES - 2]);
+        buf[CREDENTIALS_BYTES - 2..CREDENTIALS_BYTES].copy_from_slice(&crc.to_le_bytes());
+        CREDENTIALS_BYTES
+    }
+
+    // Restaura o que `save` gravou (ignora conteúdo inválido, ex: flash apagada)
+    pub fn load(&self, buf: &[u8]) {
+        let Some(data) = buf.get(..CREDENTIALS_BYTES) else {
+            return;
+        };
+        let (body, crc) = data.split_at(CREDENTIALS_BYTES - 2);
+        if body[..4] != MAGIC || crc16(body).to_le_bytes() != crc {
+            return;
+        }
+        let mut hashes = self.hashes.borrow_mut();
+        for (i, hash) in hashes.iter_mut().enumerate() {
+            let entry = &body[5 + i * ENTRY_LEN..5 + (i + 1) * ENTRY_LEN];
+            *hash = (body[4] & (1 << i) != 0).then(|| {
+                let mut salt = [0u8; SALT_LEN];
+                let mut digest = [0u8; DIGEST_LEN];
+                salt.copy_from_slice(&entry[..SALT_LEN]);
+                digest.copy_from_slice(&entry[SALT_LEN..]);
+                PasswordHash { salt, digest }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT: [u8; SALT_LEN] = [7; SALT_LEN];
+
+    fn configured() -> Auth {
+        let auth = Auth::new();
+        auth.set(Level::Admin, "root", SALT).unwrap();
+        auth.set(Level::User, "op", [9; SALT_LEN]).unwrap();
+        auth
+    }
+
+    #[test]
+    fn hash_depends_on_salt_and_password() {
+        let hash = PasswordHash::new(SALT, "secreta");
+        assert!(hash.matches("secreta"));
+        assert!(!hash.matches("Secreta"));
+        assert!(!hash.matches(""));
+        assert_ne!(PasswordHash::new([8; SALT_LEN], "secreta").digest, hash.digest);
+    }
+
+    #[test]
+    fn set_verify_and_reject() {
+        let auth = Auth::new();
+        assert!(!auth.enabled());
+        assert_eq!(auth.set(Level::User, "op", SALT), Err(AuthError::NoAdmin));
+
+        let auth = configured();
+        assert!(auth.enabled());
+        assert_eq!(auth.verify("root"), Some(Level::Admin));
+        assert_eq!(auth.verify("op"), Some(Level::User));
+        assert_eq!(auth.verify("nope"), None);
+
+        // Remover a de admin remove também a de usuário
+        auth.set(Level::Admin, "", SALT).unwrap();
+        assert!(!auth.enabled());
+        assert_eq!(auth.verify("op"), None);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut buf = [0u8; CREDENTIALS_BYTES];
+        assert_eq!(configured().save(&mut buf), CREDENTIALS_BYTES);
+        let auth = Auth::new();
+        auth.load(&buf);
+        assert_eq!(auth.verify("root"), Some(Level::Admin));
+        assert_eq!(auth.verify("op"), Some(Level::User));
+    }
+
+    #[test]
+    fn load_rejects_crc_mismatch() {
+        let mut buf = [0u8; CREDENTIALS_BYTES];
+        configured().save(&mut buf);
+        buf[10] ^= 1;
+        let auth = Auth::new();
+        auth.load(&buf);
+        assert!(!auth.enabled());
+        // Flash apagada e dados curtos também
+        auth.load(&[0xFF; CREDENTIALS_BYTES]);
+        auth.load(&buf[..CREDENTIALS_BYTES - 1]);
+        assert!(!auth.enabled());
+    }
+
+    #[test]
+    fn lockout_after_max_attempts() {
+        let auth = configured();
+        for _ in 1..MAX_ATTEMPTS {
+            assert_eq!(auth.login("x", 0), Err(LoginError::Failed));
+        }
+        assert_eq!(auth.login("x", 0), Err(LoginError::Locked));
+        // Nem a senha certa passa até o fim do bloqueio
+        assert_eq!(auth.login("root", LOCKOUT_MS - 1), Err(LoginError::Locked));
+        assert_eq!(auth.login("root", LOCKOUT_MS), Ok(Level::Admin));
+        // Um acerto zera a contagem
+        assert_eq!(auth.login("x", LOCKOUT_MS), Err(LoginError::Failed));
+        assert_eq!(auth.login("op", LOCKOUT_MS), Ok(Level::User));
+        assert_eq!(auth.login("x", LOCKOUT_MS), Err(LoginError::Failed));
+    }
+}
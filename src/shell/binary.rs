@@ -0,0 +1,273 @@
+// Protocolo binário compacto sobre quadros COBS/CRC (ver `frame`), no mesmo
+// link do shell de texto. Um byte 0x00 (que um terminal nunca envia) coloca a
+// sessão neste modo; `OP_TEXT` ou um quadro longo demais voltam ao texto.
+//
+// Requisição: [seq][op][args...]
+// Resposta:   [seq][status][dados...]
+// Amostras:   [seq][STATUS_STREAM][n][(bruto u16, mV u16) x n] (little-endian)
+
+use embassy_futures::select::{select, Either};
+use embedded_io_async::{Read, Write};
+use heapless::Vec;
+
+use super::frame::{encode_frame, FrameDecoder, FrameError, MAX_FRAME, MAX_PAYLOAD};
+use super::{adc_input_mv, App, Board};
+
+// Operações
+pub const OP_PING: u8 = 0x01;   // Ecoa os argumentos
+pub const OP_STATUS: u8 = 0x02; // -> [led]
+pub const OP_LED: u8 = 0x03;    // [0=off, 1=on, 2=toggle] -> [led]
+pub const OP_ADC: u8 = 0x04;    // [limiar mV u16 (opcional)] -> amostras até OP_STOP
+pub const OP_STOP: u8 = 0x05;   // Encerra o fluxo de amostras
+pub const OP_TEXT: u8 = 0x06;   // Volta ao shell de texto
+
+// Status das respostas
+pub const STATUS_OK: u8 = 0x00;
+pub const STATUS_UNKNOWN_OP: u8 = 0x01;
+pub const STATUS_BAD_ARGS: u8 = 0x02;
+pub const STATUS_BAD_FRAME: u8 = 0x03; // Quadro corrompido (seq desconhecido = 0)
+pub const STATUS_BUSY: u8 = 0x04;      // Requisição durante um fluxo (só OP_STOP é aceita)
+pub const STATUS_STREAM: u8 = 0x80;
+
+// Amostras por quadro de fluxo
+const SAMPLES_PER_FRAME: usize = (MAX_PAYLOAD - 3) / 4;
+
+type Payload = Vec<u8, MAX_PAYLOAD>;
+
+// O que o shell deve fazer após um byte
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Stay,     // Continua no modo binário
+    TextMode, // Volta ao shell de texto
+}
+
+// Envia um payload como quadro
+pub async fn send<T: Write>(io: &mut T, payload: &[u8]) -> Result<(), T::Error> {
+    let mut frame = [0u8; MAX_FRAME];
+    match encode_frame(payload, &mut frame) {
+        Some(len) => io.write_all(&frame[..len]).await,
+        None => Ok(()), // Payload maior que o protocolo permite (não acontece)
+    }
+}
+
+// Lado binário de uma sessão
+#[derive(Default)]
+pub struct Link {
+    decoder: FrameDecoder,
+}
+
+impl Link {
+    pub const fn new() -> Self {
+        Self { decoder: FrameDecoder::new() }
+    }
+
+    // Descarta um quadro parcial (ao entrar no modo binário)
+    pub fn reset(&mut self) {
+        self.decoder.reset();
+    }
+
+    // Trata um byte recebido no modo binário
+    // (a entrada só é lida durante um fluxo de amostras, à espera de OP_STOP)
+    pub async fn handle_byte<R: Read, T: Write<Error = R::Error>, B: Board>(
+        &mut self,
+        rx: &mut R,
+        io: &mut T,
+        app: &App<B>,
+        byte: u8,
+    ) -> Result<Action, T::Error> {
+        let board = &app.board;
+        let request = match self.decoder.feed(byte) {
+            None => return Ok(Action::Stay),
+            Some(Ok(payload)) => Payload::from_slice(payload).unwrap_or_default(),
+            // Texto sem delimitadores: o outro lado é um terminal
+            Some(Err(FrameError::TooLong)) => return Ok(Action::TextMode),
+            Some(Err(_)) => {
+                send(io, &[0, STATUS_BAD_FRAME]).await?;
+                return Ok(Action::Stay);
+            },
+        };
+        let (&[seq, op], args) = request.split_at(2.min(request.len())) else {
+            send(io, &[request.first().copied().unwrap_or(0), STATUS_BAD_ARGS]).await?;
+            return Ok(Action::Stay);
+        };
+
+        match (op, args) {
+            (OP_PING, _) => {
+                let mut reply = Payload::new();
+                let _ = reply.extend_from_slice(&[seq, STATUS_OK]);
+                let _ = reply.extend_from_slice(&args[..args.len().min(MAX_PAYLOAD - 2)]);
+                send(io, &reply).await?;
+            },
+            (OP_STATUS, []) => send(io, &[seq, STATUS_OK, board.led_enabled() as u8]).await?,
+            (OP_LED, &[state @ 0..=2]) => {
+                board.set_led_enabled(match state {
+                    0 => false,
+                    1 => true,
+                    _ => !board.led_enabled(),
+                });
+                send(io, &[seq, STATUS_OK, board.led_enabled() as u8]).await?;
+            },
+            (OP_ADC, []) => self.adc_stream(rx, io, app, seq, 0).await?,
+            (OP_ADC, &[lo, hi]) => self.adc_stream(rx, io, app, seq, u16::from_le_bytes([lo, hi]) as u32).await?,
+            (OP_STOP, []) => send(io, &[seq, STATUS_OK]).await?, // Nenhum fluxo ativo
+            (OP_TEXT, []) => {
+                send(io, &[seq, STATUS_OK]).await?;
+                return Ok(Action::TextMode);
+            },
+            (OP_PING..=OP_TEXT, _) => send(io, &[seq, STATUS_BAD_ARGS]).await?,
+            _ => send(io, &[seq, STATUS_UNKNOWN_OP]).await?,
+        }
+        Ok(Action::Stay)
+    }
+
+    // Envia as leituras acima do limiar em lotes até receber OP_STOP
+    async fn adc_stream<R: Read, T: Write<Error = R::Error>, B: Board>(
+        &mut self,
+        rx: &mut R,
+        io: &mut T,
+        app: &App<B>,
+        seq: u8,
+        threshold_mv: u32,
+    ) -> Result<(), T::Error> {
+        let mut count: u32 = 0;
+        let mut byte = [0u8; 1];
+
+        let stop_seq = loop {
+            // Verificação não-bloqueante: leitura com timeout de 50ms
+            match select(rx.read(&mut byte), app.board.delay_ms(50)).await {
+                Either::First(Ok(0)) => break None, // Fim do transporte
+                Either::First(Ok(_)) => match self.decoder.feed(byte[0]) {
+                    Some(Ok(&[stop, OP_STOP])) => break Some(stop),
+                    Some(Ok(request)) => {
+                        let busy = [request.first().copied().unwrap_or(0), STATUS_BUSY];
+                        send(io, &busy).await?;
+                    },
+                    Some(Err(_)) => send(io, &[0, STATUS_BAD_FRAME]).await?,
+                    None => {},
+                },
+                Either::First(Err(e)) => return Err(e),
+                Either::Second(()) => {},
+            }
+
+            let mut batch = Payload::new();
+            let _ = batch.extend_from_slice(&[seq, STATUS_STREAM, 0]);
+            while batch[2] < SAMPLES_PER_FRAME as u8 {
+                let Some(raw) = app.board.try_adc_sample() else {
+                    break;
+                };
+                let mv = adc_input_mv(&app.params, raw);
+                if mv < threshold_mv {
+                    continue;
+                }
+                let _ = batch.extend_from_slice(&raw.to_le_bytes());
+                let _ = batch.extend_from_slice(&(mv.min(u16::MAX as u32) as u16).to_le_bytes());
+                batch[2] += 1;
+            }
+            if batch[2] > 0 {
+                count += batch[2] as u32;
+                send(io, &batch).await?;
+            }
+        };
+
+        // Fim do fluxo: total de amostras na resposta ao OP_ADC
+        let mut done = Payload::new();
+        let _ = done.extend_from_slice(&[seq, STATUS_OK]);
+        let _ = done.extend_from_slice(&count.to_le_bytes());
+        send(io, &done).await?;
+        if let Some(stop) = stop_seq {
+            send(io, &[stop, STATUS_OK]).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::shell::testing::{block_on, Mock, MockBoard};
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut frame = [0u8; MAX_FRAME];
+        let len = encode_frame(payload, &mut frame).unwrap();
+        frame[..len].to_vec()
+    }
+
+    // Entrega `bytes` ao link (com `rx` para a leitura durante um fluxo);
+    // devolve a última ação e os payloads enviados
+    fn exchange(app: &App<MockBoard>, rx: &[u8], bytes: &[u8]) -> (Action, Vec<Vec<u8>>) {
+        let mut link = Link::new();
+        let (mut rx, mut io) = (Mock::new(rx), Mock::default());
+        let mut action = Action::Stay;
+        for &byte in bytes {
+            action = block_on(link.handle_byte(&mut rx, &mut io, app, byte)).unwrap();
+        }
+        let mut decoder = FrameDecoder::new();
+        let replies = io.output.iter().filter_map(|&b| decoder.feed(b).map(|r| r.unwrap().to_vec())).collect();
+        (action, replies)
+    }
+
+    #[test]
+    fn requests() {
+        let app = App::new(MockBoard::default());
+        let mut bytes = frame(&[1, OP_PING, 0xAA, 0x00]);
+        bytes.extend(frame(&[2, OP_LED, 2]));
+        bytes.extend(frame(&[3, OP_STATUS]));
+        bytes.extend(frame(&[4, OP_STOP]));
+        let (action, replies) = exchange(&app, &[], &bytes);
+        assert_eq!(action, Action::Stay);
+        assert_eq!(replies, [&[1, STATUS_OK, 0xAA, 0x00][..], &[2, STATUS_OK, 1], &[3, STATUS_OK, 1], &[4, STATUS_OK]]);
+        assert!(app.board.led.get());
+    }
+
+    #[test]
+    fn bad_requests() {
+        let app = App::new(MockBoard::default());
+        let mut bytes = frame(&[1, OP_LED, 3]);
+        bytes.extend(frame(&[2, OP_STATUS, 0]));
+        bytes.extend(frame(&[3, 0x7F]));
+        bytes.extend(frame(&[4]));
+        let mut corrupt = frame(&[5, OP_STATUS]);
+        corrupt[1] ^= 0x10;
+        bytes.extend(corrupt);
+        let (_, replies) = exchange(&app, &[], &bytes);
+        assert_eq!(
+            replies,
+            [
+                [1, STATUS_BAD_ARGS],
+                [2, STATUS_BAD_ARGS],
+                [3, STATUS_UNKNOWN_OP],
+                [4, STATUS_BAD_ARGS],
+                [0, STATUS_BAD_FRAME],
+            ]
+        );
+    }
+
+    #[test]
+    fn back_to_text() {
+        let app = App::new(MockBoard::default());
+        let (action, replies) = exchange(&app, &[], &frame(&[9, OP_TEXT]));
+        assert_eq!((action, replies), (Action::TextMode, std::vec![std::vec![9, STATUS_OK]]));
+        // Texto digitado sem delimitador estoura o quadro
+        let (action, replies) = exchange(&app, &[], &[b'x'; MAX_FRAME + 1]);
+        assert_eq!(action, Action::TextMode);
+        assert!(replies.is_empty());
+    }
+
+    #[test]
+    fn adc_stream_until_stop() {
+        let app = App::new(MockBoard::default());
+        app.board.samples.borrow_mut().extend([0, 4095, 2048]);
+        // Limiar de 100 mV: a amostra 0 fica de fora
+        let (_, replies) = exchange(&app, &frame(&[8, OP_STOP]), &frame(&[7, OP_ADC, 100, 0]));
+        let samples: Vec<u16> = replies
+            .iter()
+            .filter(|r| r[..2] == [7, STATUS_STREAM])
+            .flat_map(|r| r[3..].chunks(4).map(|s| u16::from_le_bytes([s[0], s[1]])).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(samples, [4095, 2048]);
+        let end = &replies[replies.len() - 2..];
+        assert_eq!(end, [&[7, STATUS_OK, 2, 0, 0, 0][..], &[8, STATUS_OK]]);
+    }
+}
@@ -0,0 +1,106 @@
+// Encadeamento de comandos numa linha: `a ; b` executa os dois, `a && b`
+// executa `b` só se `a` teve sucesso. Separadores dentro de aspas ou
+// escapados com '\' fazem parte do comando
+
+// Como um comando se liga ao anterior
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Then {
+    Always, // Primeiro comando ou depois de ';'
+    IfOk,   // Depois de '&&'
+}
+
+// Comandos de uma linha, na ordem
+pub struct Chain<'a> {
+    rest: Option<&'a str>, // Texto ainda não separado
+    then: Then,            // Ligação do próximo comando
+}
+
+impl<'a> Chain<'a> {
+    pub fn new(line: &'a str) -> Self {
+        Self { rest: Some(line), then: Then::Always }
+    }
+}
+
+impl<'a> Iterator for Chain<'a> {
+    type Item = (Then, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?;
+        let then = self.then;
+        match find_separator(rest) {
+            Some((at, len, next)) => {
+                self.rest = Some(&rest[at + len..]);
+                self.then = next;
+                Some((then, &rest[..at]))
+            },
+            None => {
+                self.rest = None;
+                Some((then, rest))
+            },
+        }
+    }
+}
+
+// Último comando da linha (o que está sendo digitado, para a completação)
+pub fn last(line: &str) -> &str {
+    Chain::new(line).last().map_or(line, |(_, segment)| segment)
+}
+
+// Próximo separador fora de aspas: posição, tamanho e ligação do comando seguinte
+fn find_separator(text: &str) -> Option<(usize, usize, Then)> {
+    let mut quote: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None, // Aspas simples: tudo literal
+            (Some('\''), _) => {},
+            (_, '\\') => {
+                chars.next(); // Caractere escapado
+            },
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(_), '"') => quote = None,
+            (Some(_), _) => {},
+            (None, ';') => return Some((at, 1, Then::Always)),
+            (None, '&') if matches!(chars.peek(), Some((_, '&'))) => return Some((at, 2, Then::IfOk)),
+            (None, _) => {},
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(line: &str) -> std::vec::Vec<(Then, &str)> {
+        Chain::new(line).collect()
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(split("led on"), [(Then::Always, "led on")]);
+        assert_eq!(
+            split("led on; status && led off"),
+            [(Then::Always, "led on"), (Then::Always, " status "), (Then::IfOk, " led off")]
+        );
+        assert_eq!(split("a;;b"), [(Then::Always, "a"), (Then::Always, ""), (Then::Always, "b")]);
+        assert_eq!(split("a &&"), [(Then::Always, "a "), (Then::IfOk, "")]);
+        // Um '&' só não separa (é o segundo plano, tratado antes)
+        assert_eq!(split("a & b"), [(Then::Always, "a & b")]);
+    }
+
+    #[test]
+    fn quoted_and_escaped_separators() {
+        assert_eq!(split(r#"alias x = "a; b" ; c"#), [(Then::Always, r#"alias x = "a; b" "#), (Then::Always, " c")]);
+        assert_eq!(split("echo 'a && \\' ; b"), [(Then::Always, "echo 'a && \\' "), (Then::Always, " b")]);
+        assert_eq!(split(r"echo a\;b \&& c"), [(Then::Always, r"echo a\;b \&& c")]);
+        assert_eq!(split(r#"echo "\"; x""#), [(Then::Always, r#"echo "\"; x""#)]);
+    }
+
+    #[test]
+    fn last_segment() {
+        assert_eq!(last("led on && sta"), " sta");
+        assert_eq!(last("led on;"), "");
+        assert_eq!(last("help"), "help");
+    }
+}
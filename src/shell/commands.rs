@@ -0,0 +1,1384 @@
+// Comandos do shell: cada comando é declarado uma única vez na macro `commands!`,
+// que gera o enum de handlers, o despacho assíncrono e a tabela `COMMANDS`
+
+use core::fmt::Write as _;
+use embedded_io_async::Write;
+
+use crate::boot::{self, BootFlash, BootState, Region};
+
+use super::args::{ArgError, Args, TIME, VOLTAGE};
+use super::auth::{AuthError, Level, LEVELS};
+use super::config::{ConfigStore, MAX_VALUE};
+use super::files::{FileError, FileName, MAX_FILES};
+use super::gpio::{Owner, PinId, PinMode, Pull, PIN_MODES, PULLS};
+use super::i18n::{Lang, Msg, LANGS, LANG_KEY};
+use super::jobs::MAX_JOBS;
+use super::memory::{self, Access, MemoryError, Width, WIDTHS};
+use super::params::{Param, PARAM_NAMES};
+use super::registers::{self, Peripheral, Register};
+use super::startup::{StartupError, MAX_SCRIPT_LINES, STARTUP_BYTES};
+use super::tasks::{self, TaskMonitor};
+use super::settings::{InputEol, OutputEol, OutputMode, INPUT_EOLS, OUTPUT_EOLS, OUTPUT_MODES};
+use super::registry::{self, ArgSpec, Command};
+use super::xmodem::{Protocol, Transfer, PROTOCOLS};
+use super::{adc_input_mv, Board, Context, Error};
+
+// Estados aceitos pelo comando `led`
+const LED_STATES: &[&str] = &["on", "off", "toggle"];
+
+// Estados aceitos pelo comando `echo`
+const ON_OFF: &[&str] = &["on", "off"];
+
+// Limites de `repeat` e `watch` (intervalo em ms)
+pub const REPEAT_MAX: i64 = 10_000;
+pub const WATCH_MIN_MS: i64 = 50;
+pub const WATCH_MAX_MS: i64 = 3_600_000;
+
+// Atualização do `top` (padrão 1 s)
+const TOP_MIN_MS: i64 = 100;
+const TOP_DEFAULT_MS: i64 = 1000;
+
+// Limpa o terminal antes de cada atualização do `top`
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+// Ações do comando `autoexec`
+const AUTOEXEC_ACTIONS: &[&str] = &["list", "add", "del", "clear"];
+
+// Ações do comando `config`
+const CONFIG_ACTIONS: &[&str] = &["save", "load", "reset", "dump"];
+
+// Ações do comando `fw`
+const FW_ACTIONS: &[&str] = &["status", "update"];
+
+// Ações do `gpio` (sem ação: `list`)
+const GPIO_ACTIONS: &[&str] = &["list", "mode", "read", "write", "watch", "free"];
+
+// Intervalo de amostragem do `gpio watch` (pulsos mais curtos passam
+// despercebidos; menos que isso acorda o executor à toa)
+const GPIO_POLL_MS: u64 = 10;
+
+// Limites de `peek` (valores) e `dump` (bytes; 16 por linha)
+const ADDRESS_MAX: i64 = u32::MAX as i64;
+const PEEK_MAX: i64 = 64;
+const DUMP_MAX: i64 = 1024;
+const DUMP_DEFAULT: usize = 64;
+const DUMP_LINE: usize = 16;
+
+// Direções do comando `eol` (os modos de entrada incluem os de saída)
+const EOL_DIRECTIONS: &[&str] = &["in", "out"];
+
+macro_rules! commands {
+    ($($variant:ident => {
+        name: $name:literal,
+        summary: $summary:ident,
+        usage: $usage:ident,
+        args: $args:expr,
+        level: $level:ident
+        $(, run: $run:path)? $(,)?
+    }),* $(,)?) => {
+        // Identifica o handler de cada comando da tabela
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum Handler {
+            $($variant),*
+        }
+
+        impl Handler {
+            // Executa o handler correspondente (None nos comandos sem `run`:
+            // `repeat`/`watch`, repetidos pelo próprio despacho)
+            pub async fn call<T: Write, B: Board>(
+                self,
+                ctx: &mut Context<'_, T, B>,
+                args: &Args<'_>,
+            ) -> Option<Result<(), Error<T::Error>>> {
+                match self {
+                    $($(Handler::$variant => Some($run(ctx, args).await),)?)*
+                    _ => None,
+                }
+            }
+        }
+
+        // Tabela de comandos (na ordem exibida pelo help)
+        pub static COMMANDS: &[Command] = &[
+            $(Command {
+                name: $name,
+                summary: Msg::$summary,
+                usage: Msg::$usage,
+                args: $args,
+                level: Level::$level,
+                handler: Handler::$variant,
+            }),*
+        ];
+    };
+}
+
+commands! {
+    Help => {
+        name: "help",
+        summary: HelpSummary,
+        usage: HelpUsage,
+        args: &[ArgSpec::command(Msg::ArgCommand).opt()],
+        level: User,
+        run: help,
+    },
+    Led => {
+        name: "led",
+        summary: LedSummary,
+        usage: LedUsage,
+        args: &[ArgSpec::choice(Msg::ArgState, LED_STATES)],
+        level: User,
+        run: led,
+    },
+    Status => {
+        name: "status",
+        summary: StatusSummary,
+        usage: StatusUsage,
+        args: &[],
+        level: User,
+        run: status,
+    },
+    History => {
+        name: "history",
+        summary: HistorySummary,
+        usage: HistoryUsage,
+        args: &[],
+        level: User,
+        run: history,
+    },
+    Echo => {
+        name: "echo",
+        summary: EchoSummary,
+        usage: EchoUsage,
+        args: &[ArgSpec::choice(Msg::ArgState, ON_OFF).opt()],
+        level: User,
+        run: echo,
+    },
+    Eol => {
+        name: "eol",
+        summary: EolSummary,
+        usage: EolUsage,
+        args: &[
+            ArgSpec::choice(Msg::ArgDirection, EOL_DIRECTIONS).opt(),
+            ArgSpec::choice(Msg::ArgMode, INPUT_EOLS),
+        ],
+        level: User,
+        run: eol,
+    },
+    Lang => {
+        name: "lang",
+        summary: LangSummary,
+        usage: LangUsage,
+        args: &[ArgSpec::choice(Msg::ArgLang, LANGS).opt()],
+        level: User,
+        run: lang,
+    },
+    Mode => {
+        name: "mode",
+        summary: ModeSummary,
+        usage: ModeUsage,
+        args: &[ArgSpec::choice(Msg::ArgFormat, OUTPUT_MODES).opt()],
+        level: User,
+        run: mode,
+    },
+    Adc => {
+        name: "adc",
+        summary: AdcSummary,
+        usage: AdcUsage,
+        args: &[
+            ArgSpec::choice(Msg::ArgMode, &["cont"]),
+            ArgSpec::fixed(Msg::ArgThreshold, VOLTAGE, 0, 20_000).opt(),
+        ],
+        level: User,
+        run: adc,
+    },
+    Jobs => {
+        name: "jobs",
+        summary: JobsSummary,
+        usage: JobsUsage,
+        args: &[],
+        level: User,
+        run: jobs,
+    },
+    Kill => {
+        name: "kill",
+        summary: KillSummary,
+        usage: KillUsage,
+        args: &[ArgSpec::int(Msg::ArgId, 1, MAX_JOBS as i64)],
+        level: User,
+        run: kill,
+    },
+    Ps => {
+        name: "ps",
+        summary: PsSummary,
+        usage: PsUsage,
+        args: &[],
+        level: User,
+        run: ps,
+    },
+    Top => {
+        name: "top",
+        summary: TopSummary,
+        usage: TopUsage,
+        args: &[ArgSpec::fixed(Msg::ArgInterval, TIME, TOP_MIN_MS, WATCH_MAX_MS).opt()],
+        level: User,
+        run: top,
+    },
+    Repeat => {
+        name: "repeat",
+        summary: RepeatSummary,
+        usage: RepeatUsage,
+        args: &[ArgSpec::int(Msg::ArgCount, 1, REPEAT_MAX), ArgSpec::command_line(Msg::ArgCommand)],
+        level: User, // Sem `run`: ver `run_segment`
+    },
+    Watch => {
+        name: "watch",
+        summary: WatchSummary,
+        usage: WatchUsage,
+        args: &[ArgSpec::fixed(Msg::ArgInterval, TIME, WATCH_MIN_MS, WATCH_MAX_MS), ArgSpec::command_line(Msg::ArgCommand)],
+        level: User, // Sem `run`: ver `run_segment`
+    },
+    Alias => {
+        name: "alias",
+        summary: AliasSummary,
+        usage: AliasUsage,
+        args: &[
+            ArgSpec::text(Msg::ArgName).opt(),
+            ArgSpec::choice(Msg::ArgEquals, &["="]).opt(),
+            ArgSpec::text(Msg::ArgCommand),
+        ],
+        level: Admin,
+        run: alias,
+    },
+    Unalias => {
+        name: "unalias",
+        summary: UnaliasSummary,
+        usage: UnaliasUsage,
+        args: &[ArgSpec::text(Msg::ArgName)],
+        level: Admin,
+        run: unalias,
+    },
+    Autoexec => {
+        name: "autoexec",
+        summary: AutoexecSummary,
+        usage: AutoexecUsage,
+        args: &[ArgSpec::choice(Msg::ArgAction, AUTOEXEC_ACTIONS).opt(), ArgSpec::text(Msg::ArgExtra).opt()],
+        level: Admin,
+        run: autoexec,
+    },
+    Logout => {
+        name: "logout",
+        summary: LogoutSummary,
+        usage: LogoutUsage,
+        args: &[],
+        level: User,
+        run: logout,
+    },
+    Passwd => {
+        name: "passwd",
+        summary: PasswdSummary,
+        usage: PasswdUsage,
+        args: &[ArgSpec::choice(Msg::ArgLevel, LEVELS)],
+        level: Admin,
+        run: passwd,
+    },
+    Params => {
+        name: "params",
+        summary: ParamsSummary,
+        usage: ParamsUsage,
+        args: &[],
+        level: User,
+        run: params,
+    },
+    Get => {
+        name: "get",
+        summary: GetSummary,
+        usage: GetUsage,
+        args: &[ArgSpec::choice(Msg::ArgParam, PARAM_NAMES)],
+        level: User,
+        run: get,
+    },
+    Set => {
+        name: "set",
+        summary: SetSummary,
+        usage: SetUsage,
+        args: &[ArgSpec::choice(Msg::ArgParam, PARAM_NAMES), ArgSpec::text(Msg::ArgValue)],
+        level: Admin,
+        run: set,
+    },
+    Config => {
+        name: "config",
+        summary: ConfigSummary,
+        usage: ConfigUsage,
+        args: &[ArgSpec::choice(Msg::ArgAction, CONFIG_ACTIONS)],
+        level: Admin,
+        run: config,
+    },
+    Rx => {
+        name: "rx",
+        summary: RxSummary,
+        usage: RxUsage,
+        args: &[ArgSpec::choice(Msg::ArgProtocol, PROTOCOLS), ArgSpec::text(Msg::ArgFile).opt()],
+        level: Admin,
+        run: rx,
+    },
+    Sx => {
+        name: "sx",
+        summary: SxSummary,
+        usage: SxUsage,
+        args: &[ArgSpec::choice(Msg::ArgProtocol, PROTOCOLS), ArgSpec::text(Msg::ArgFile)],
+        level: User,
+        run: sx,
+    },
+    Files => {
+        name: "files",
+        summary: FilesSummary,
+        usage: FilesUsage,
+        args: &[],
+        level: User,
+        run: files,
+    },
+    Rm => {
+        name: "rm",
+        summary: RmSummary,
+        usage: RmUsage,
+        args: &[ArgSpec::text(Msg::ArgFile)],
+        level: Admin,
+        run: rm,
+    },
+    Fw => {
+        name: "fw",
+        summary: FwSummary,
+        usage: FwUsage,
+        args: &[ArgSpec::choice(Msg::ArgAction, FW_ACTIONS)],
+        level: Admin,
+        run: fw,
+    },
+    Peek => {
+        name: "peek",
+        summary: PeekSummary,
+        usage: PeekUsage,
+        args: &[
+            ArgSpec::int(Msg::ArgAddress, 0, ADDRESS_MAX),
+            ArgSpec::int(Msg::ArgCount, 1, PEEK_MAX).opt(),
+            ArgSpec::choice(Msg::ArgWidth, WIDTHS).opt(),
+        ],
+        level: Admin,
+        run: peek,
+    },
+    Poke => {
+        name: "poke",
+        summary: PokeSummary,
+        usage: PokeUsage,
+        args: &[
+            ArgSpec::int(Msg::ArgAddress, 0, ADDRESS_MAX),
+            ArgSpec::int(Msg::ArgValue, 0, ADDRESS_MAX),
+            ArgSpec::choice(Msg::ArgWidth, WIDTHS).opt(),
+        ],
+        level: Admin,
+        run: poke,
+    },
+    Dump => {
+        name: "dump",
+        summary: DumpSummary,
+        usage: DumpUsage,
+        args: &[
+            ArgSpec::int(Msg::ArgAddress, 0, ADDRESS_MAX),
+            ArgSpec::int(Msg::ArgBytes, 1, DUMP_MAX).opt(),
+            ArgSpec::choice(Msg::ArgWidth, WIDTHS).opt(),
+        ],
+        level: Admin,
+        run: dump,
+    },
+    Reg => {
+        name: "reg",
+        summary: RegSummary,
+        usage: RegUsage,
+        args: &[ArgSpec::text(Msg::ArgPeripheral).opt(), ArgSpec::text(Msg::ArgRegister).opt()],
+        level: Admin,
+        run: reg,
+    },
+    Gpio => {
+        name: "gpio",
+        summary: GpioSummary,
+        usage: GpioUsage,
+        args: &[
+            ArgSpec::choice(Msg::ArgAction, GPIO_ACTIONS).opt(),
+            ArgSpec::text(Msg::ArgPin).opt(),
+            ArgSpec::text(Msg::ArgExtra).opt(),
+            ArgSpec::text(Msg::ArgExtra).opt(),
+        ],
+        level: Admin,
+        run: gpio,
+    },
+}
+
+// help [comando] - lista gerada a partir da tabela
+async fn help<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    match args.str(0) {
+        None => {
+            ctx.write_msg(Msg::CommandList).await?;
+            ctx.begin_array("commands").await?;
+            for cmd in registry::all() {
+                ctx.write_str("- ").await?;
+                ctx.write_str(cmd.name).await?;
+                ctx.write_str(": ").await?;
+                ctx.write_msg(cmd.summary).await?;
+                ctx.write_str("\n").await?;
+                ctx.begin_item().await?;
+                describe(ctx, cmd).await?;
+                ctx.end_item().await?;
+            }
+            ctx.end_array().await
+        },
+        Some(name) => match registry::find(name) {
+            Some(cmd) => {
+                describe(ctx, cmd).await?;
+                ctx.write_str(cmd.name).await?;
+                ctx.write_str(": ").await?;
+                ctx.write_msg(cmd.summary).await?;
+                ctx.write_str("\n").await?;
+                ctx.write_msg(Msg::Usage).await?;
+                ctx.write_msg(cmd.usage).await?;
+                ctx.write_str("\n").await
+            },
+            None => Err(Error::UnknownCommand),
+        },
+    }
+}
+
+// Campos JSON de um comando (help)
+async fn describe<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, cmd: &Command) -> Result<(), Error<T::Error>> {
+    ctx.field_str("name", cmd.name).await?;
+    ctx.field_str("summary", cmd.summary.text(ctx.settings.lang)).await?;
+    ctx.field_str("usage", cmd.usage.text(ctx.settings.lang)).await
+}
+
+// led on|off|toggle
+async fn led<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    match args.choice(0, Msg::ArgState, LED_STATES)? {
+        0 => ctx.board.set_led_enabled(true),
+        1 => ctx.board.set_led_enabled(false),
+        _ => ctx.board.set_led_enabled(!ctx.board.led_enabled()),
+    }
+    ctx.field_bool("led", ctx.board.led_enabled()).await?;
+    if ctx.board.led_enabled() {
+        ctx.write_msg(Msg::LedOn).await
+    } else {
+        ctx.write_msg(Msg::LedOff).await
+    }
+}
+
+// status
+async fn status<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, _args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    ctx.field_bool("led", ctx.board.led_enabled()).await?;
+    if ctx.board.led_enabled() {
+        ctx.write_msg(Msg::StatusActive).await
+    } else {
+        ctx.write_msg(Msg::StatusInactive).await
+    }
+}
+
+// history - lista numerada do histórico
+async fn history<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, _args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let first = ctx.history.first_number();
+    ctx.begin_array("entries").await?;
+    for index in 0..ctx.history.len() {
+        let number = itoa::Buffer::new().format(first.wrapping_add(index as u32)).len();
+        for _ in number..5 {
+            ctx.write_str(" ").await?; // Alinha os números à direita
+        }
+        ctx.write_str(itoa::Buffer::new().format(first.wrapping_add(index as u32))).await?;
+        ctx.write_str("  ").await?;
+        ctx.write_str(ctx.history.entry(index).unwrap_or("")).await?;
+        ctx.write_str("\n").await?;
+        ctx.begin_item().await?;
+        ctx.field_int("n", first.wrapping_add(index as u32) as i64).await?;
+        ctx.field_str("line", ctx.history.entry(index).unwrap_or("")).await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// echo [on|off] - sem argumento mostra o estado atual
+async fn echo<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    if args.get(0).is_some() {
+        ctx.settings.echo = args.choice(0, Msg::ArgState, ON_OFF)? == 0;
+    }
+    ctx.field_bool("echo", ctx.settings.echo).await?;
+    ctx.write_msg(if ctx.settings.echo { Msg::EchoOn } else { Msg::EchoOff }).await
+}
+
+// eol [in|out <modo>] - sem argumento mostra a configuração atual
+async fn eol<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    if args.get(0).is_some() {
+        if args.choice(0, Msg::ArgDirection, EOL_DIRECTIONS)? == 0 {
+            ctx.settings.input_eol = InputEol::ALL[args.choice(1, Msg::ArgMode, INPUT_EOLS)?];
+        } else {
+            ctx.settings.output_eol = OutputEol::ALL[args.choice(1, Msg::ArgMode, OUTPUT_EOLS)?];
+        }
+    }
+    ctx.field_str("input", ctx.settings.input_eol.name()).await?;
+    ctx.field_str("output", ctx.settings.output_eol.name()).await?;
+    ctx.write_msg(Msg::EolInput).await?;
+    ctx.write_str(ctx.settings.input_eol.name()).await?;
+    ctx.write_msg(Msg::EolOutput).await?;
+    ctx.write_str(ctx.settings.output_eol.name()).await?;
+    ctx.write_str("\n").await
+}
+
+// lang [pt|en] - sem argumento mostra o idioma atual (a escolha é gravada na
+// configuração, quando a placa tem uma, e vale para as próximas sessões)
+async fn lang<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    if args.get(0).is_some() {
+        ctx.settings.lang = Lang::ALL[args.choice(0, Msg::ArgLang, LANGS)?];
+        if let Some(flash) = ctx.board.config_flash() {
+            ConfigStore::open(flash)?.set(LANG_KEY, &[ctx.settings.lang.code()])?;
+        }
+    }
+    ctx.field_str("lang", ctx.settings.lang.name()).await?;
+    ctx.write_msg(Msg::Language).await?;
+    ctx.write_str(ctx.settings.lang.name()).await?;
+    ctx.write_str("\n").await
+}
+
+// mode [human|json] - sem argumento mostra o formato atual
+async fn mode<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    if args.get(0).is_some() {
+        ctx.settings.mode = OutputMode::ALL[args.choice(0, Msg::ArgFormat, OUTPUT_MODES)?];
+    }
+    ctx.field_str("mode", ctx.settings.mode.name()).await?;
+    ctx.write_msg(Msg::Mode).await?;
+    ctx.write_str(ctx.settings.mode.name()).await?;
+    ctx.write_str("\n").await
+}
+
+// adc cont [limiar] - modo contínuo: mostra as leituras (acima do limiar) até o Ctrl-C
+async fn adc<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let threshold_mv = match args.get(1) {
+        Some(_) => args.fixed(1, Msg::ArgThreshold, VOLTAGE, 0, 20_000)? as u32,
+        None => 0,
+    };
+
+    ctx.write_msg(Msg::AdcStart).await?;
+
+    loop {
+        ctx.board.delay_ms(50).await;
+
+        // Processa leituras ADC
+        while let Some(raw_value) = ctx.board.try_adc_sample() {
+            let real_mv = adc_input_mv(ctx.params, raw_value);
+            if real_mv < threshold_mv {
+                continue;
+            }
+
+            ctx.write_str("ADC: ").await?;
+            ctx.write_str(itoa::Buffer::new().format(raw_value)).await?;
+            ctx.write_str(" -> ").await?;
+            ctx.write_str(itoa::Buffer::new().format(real_mv)).await?;
+            ctx.write_str(" mV\n").await?;
+
+            // Modo json: um objeto por amostra
+            ctx.field_int("raw", raw_value as i64).await?;
+            ctx.field_int("mv", real_mv as i64).await?;
+            ctx.end_record().await?;
+        }
+    }
+}
+
+// jobs - lista os jobs em segundo plano
+async fn jobs<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, _args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    ctx.begin_array("jobs").await?;
+    for id in 1..=MAX_JOBS {
+        let Some(job) = ctx.jobs.get(id) else {
+            continue;
+        };
+        ctx.write_str("[").await?;
+        ctx.write_str(itoa::Buffer::new().format(id)).await?;
+        ctx.write_str("] ").await?;
+        ctx.write_msg(job.state.msg()).await?;
+        ctx.write_str("  ").await?;
+        ctx.write_str(&job.line).await?;
+        ctx.write_str("\n").await?;
+        ctx.begin_item().await?;
+        ctx.field_int("id", id as i64).await?;
+        ctx.field_str("line", &job.line).await?;
+        ctx.field_str("state", job.state.name()).await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// kill <id> - encerra um job em segundo plano
+async fn kill<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let id = args.int(0, Msg::ArgId, 1, MAX_JOBS as i64)? as usize;
+    if !ctx.jobs.kill(id) {
+        return Err(Error::Failed { msg: Msg::NoSuchJob, code: "no_such_job" });
+    }
+    // O término é avisado antes do próximo prompt ("[1] Terminado ...")
+    ctx.field_int("id", id as i64).await
+}
+
+// Cópia do monitor das tasks da placa
+fn task_monitor<T: Write, B: Board>(ctx: &Context<'_, T, B>) -> Result<TaskMonitor, Error<T::Error>> {
+    ctx.board.task_monitor().ok_or(Error::Failed { msg: Msg::NoTaskMonitor, code: "no_task_monitor" })
+}
+
+// "12.3%"
+fn percent(permille: u64) -> heapless::String<8> {
+    let mut text = heapless::String::new();
+    let _ = write!(text, "{}.{}%", permille / 10, permille % 10);
+    text
+}
+
+// ps - contadores de cada task desde o boot e a carga média da CPU
+async fn ps<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, _args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let monitor = task_monitor(ctx)?;
+    let boot = TaskMonitor::new(monitor.cycles_hz, monitor.ticks_hz);
+    let load = tasks::load_permille(&boot, &monitor);
+
+    ctx.write_msg(Msg::PsHeader).await?;
+    ctx.begin_array("tasks").await?;
+    for task in &monitor.tasks {
+        let busy_us = monitor.cycles_us(task.busy_cycles);
+        let mut line: heapless::String<48> = heapless::String::new();
+        let _ = write!(line, "{:<10} {:>7} {:>9}", task.name, task.polls, busy_us);
+        ctx.begin_item().await?;
+        ctx.field_str("name", task.name).await?;
+        ctx.field_int("polls", task.polls as i64).await?;
+        ctx.field_int("cpu_us", busy_us as i64).await?;
+        if task.polls > 0 {
+            let last_ms = monitor.ticks_ms(monitor.now - task.last_run);
+            ctx.field_int("last_ms", last_ms as i64).await?;
+            let _ = writeln!(line, " {:>11}", last_ms);
+        } else {
+            let _ = writeln!(line, " {:>11}", "-");
+        }
+        ctx.write_str(&line).await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await?;
+    ctx.field_int("load_permille", load as i64).await?;
+    ctx.field_int("uptime_ms", monitor.ticks_ms(monitor.now) as i64).await?;
+    ctx.write_msg(Msg::CpuLoad).await?;
+    ctx.write_str(&percent(load as u64)).await?;
+    ctx.write_msg(Msg::SinceBoot).await
+}
+
+// top [intervalo] - polls por segundo e fração da CPU de cada task no último
+// intervalo, até o Ctrl-C (no json um objeto por atualização)
+async fn top<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let interval_ms = match args.get(0) {
+        Some(_) => args.fixed(0, Msg::ArgInterval, TIME, TOP_MIN_MS, WATCH_MAX_MS)?,
+        None => TOP_DEFAULT_MS,
+    };
+    let mut before = task_monitor(ctx)?;
+    loop {
+        ctx.board.delay_ms(interval_ms as u64).await;
+        let after = task_monitor(ctx)?;
+        let elapsed_ms = after.ticks_ms(after.now - before.now).max(1);
+        let load = tasks::load_permille(&before, &after);
+
+        ctx.write_str(CLEAR_SCREEN).await?;
+        ctx.write_msg(Msg::CpuLoad).await?;
+        ctx.write_str(&percent(load as u64)).await?;
+        ctx.write_str("\n\n").await?;
+        ctx.write_msg(Msg::TopHeader).await?;
+        ctx.field_int("load_permille", load as i64).await?;
+        ctx.begin_array("tasks").await?;
+        for task in &after.tasks {
+            // Tasks criadas no intervalo partem de zero
+            let (polls, cycles) = before.task(task.id).map_or((0, 0), |t| (t.polls, t.busy_cycles));
+            let polls_s = task.polls.wrapping_sub(polls) as u64 * 1000 / elapsed_ms;
+            let busy = after.cycles_us(task.busy_cycles - cycles) / elapsed_ms; // Permilésimos
+            let mut line: heapless::String<48> = heapless::String::new();
+            let _ = writeln!(line, "{:<10} {:>9} {:>6}", task.name, polls_s, percent(busy));
+            ctx.begin_item().await?;
+            ctx.field_str("name", task.name).await?;
+            ctx.field_int("polls_per_s", polls_s as i64).await?;
+            ctx.field_int("cpu_permille", busy as i64).await?;
+            ctx.write_str(&line).await?;
+            ctx.end_item().await?;
+        }
+        ctx.end_array().await?;
+        ctx.end_record().await?;
+        before = after;
+    }
+}
+
+// alias [nome [= "comando ..."]] - sem argumentos lista; só o nome mostra um alias
+async fn alias<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    match (args.str(0), args.str(2)) {
+        (Some(name), Some(value)) => {
+            ctx.startup.set_alias(name, value)?;
+            persist(ctx)?;
+        },
+        (Some(name), None) if ctx.startup.find(name).is_none() => return Err(StartupError::NotFound.into()),
+        _ => {},
+    }
+
+    ctx.begin_array("aliases").await?;
+    for alias in (0..).map_while(|index| ctx.startup.alias(index)) {
+        if args.str(0).is_some_and(|name| name != alias.name) {
+            continue;
+        }
+        ctx.write_str("alias ").await?;
+        ctx.write_str(&alias.name).await?;
+        ctx.write_str(" = \"").await?;
+        ctx.write_str(&alias.value).await?;
+        ctx.write_str("\"\n").await?;
+        ctx.begin_item().await?;
+        ctx.field_str("name", &alias.name).await?;
+        ctx.field_str("value", &alias.value).await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// unalias <nome>
+async fn unalias<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    ctx.startup.remove_alias(args.token(0, Msg::ArgName)?.text)?;
+    persist(ctx)
+}
+
+// autoexec [list|add|del|clear] - script executado no boot (PA0 pressionado no reset pula)
+async fn autoexec<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let action = match args.get(0) {
+        Some(_) => args.choice(0, Msg::ArgAction, AUTOEXEC_ACTIONS)?,
+        None => 0,
+    };
+    match action {
+        1 => ctx.startup.add_line(args.token(1, Msg::ArgCommand)?.text)?,
+        2 => ctx.startup.remove_line(args.int(1, Msg::ArgCount, 1, MAX_SCRIPT_LINES as i64)? as usize - 1)?,
+        3 => ctx.startup.clear_script(),
+        _ => args.expect_at_most(1)?,
+    }
+    if action != 0 {
+        args.expect_at_most(2)?;
+        persist(ctx)?;
+    }
+
+    ctx.begin_array("lines").await?;
+    for (index, line) in (0..).map_while(|index| ctx.startup.script_line(index)).enumerate() {
+        ctx.write_str(itoa::Buffer::new().format(index + 1)).await?;
+        ctx.write_str("  ").await?;
+        ctx.write_str(&line).await?;
+        ctx.write_str("\n").await?;
+        ctx.item_str(&line).await?;
+    }
+    ctx.end_array().await
+}
+
+// Grava aliases e autoexec na memória persistente da placa
+fn persist<T: Write, B: Board>(ctx: &mut Context<'_, T, B>) -> Result<(), Error<T::Error>> {
+    let mut buf = [0u8; STARTUP_BYTES];
+    let len = ctx.startup.save(&mut buf).ok_or(StartupError::Full)?;
+    if !ctx.board.store_startup(&buf[..len]) {
+        return Err(Error::Failed { msg: Msg::StoreFailed, code: "store_failed" });
+    }
+    Ok(())
+}
+
+// logout - volta à senha e encerra os jobs da sessão (sem login configurado não faz nada)
+async fn logout<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, _args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    ctx.settings.level = Level::Guest;
+    Ok(())
+}
+
+// passwd user|admin - o shell pede a nova senha duas vezes, sem eco (vazia remove;
+// sem a senha de admin o login fica desligado)
+async fn passwd<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let level = Level::USERS[args.choice(0, Msg::ArgLevel, LEVELS)?];
+    if level == Level::User && !ctx.auth.enabled() {
+        return Err(AuthError::NoAdmin.into());
+    }
+    ctx.settings.passwd = Some(level);
+    ctx.field_str("level", level.name()).await
+}
+
+// params - todos os parâmetros com valor, limites, padrão e descrição
+async fn params<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, _args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    ctx.begin_array("params").await?;
+    for param in Param::ALL {
+        let spec = param.spec();
+        ctx.begin_item().await?;
+        show_param(ctx, param).await?;
+        ctx.write_str("  [").await?;
+        ctx.write_str(itoa::Buffer::new().format(spec.min)).await?;
+        ctx.write_str("..").await?;
+        ctx.write_str(itoa::Buffer::new().format(spec.max)).await?;
+        ctx.write_str(", ").await?;
+        ctx.write_msg(Msg::ParamDefault).await?;
+        ctx.write_str(" ").await?;
+        ctx.write_str(itoa::Buffer::new().format(spec.default)).await?;
+        ctx.write_str("]  ").await?;
+        ctx.write_msg(spec.summary).await?;
+        ctx.write_str("\n").await?;
+        ctx.field_int("min", spec.min).await?;
+        ctx.field_int("max", spec.max).await?;
+        ctx.field_int("default", spec.default).await?;
+        ctx.field_str("summary", spec.summary.text(ctx.settings.lang)).await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// get <parametro>
+async fn get<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let param = Param::ALL[args.choice(0, Msg::ArgParam, PARAM_NAMES)?];
+    show_param(ctx, param).await?;
+    ctx.write_str("\n").await
+}
+
+// set <parametro> <valor> - valor na unidade do parâmetro (ex: `200ms`, `3.3V`)
+async fn set<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let param = Param::ALL[args.choice(0, Msg::ArgParam, PARAM_NAMES)?];
+    let spec = param.spec();
+    let value = match spec.units {
+        [] => args.int(1, Msg::ArgValue, spec.min, spec.max)?,
+        units => args.fixed(1, Msg::ArgValue, units, spec.min, spec.max)?,
+    };
+    ctx.params.set(param, value)?;
+    show_param(ctx, param).await?;
+    ctx.write_str("\n").await
+}
+
+// "nome = valor unidade" (campos name, value e unit no json)
+async fn show_param<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, param: Param) -> Result<(), Error<T::Error>> {
+    let spec = param.spec();
+    let value = ctx.params.get(param);
+    ctx.field_str("name", spec.name).await?;
+    ctx.field_int("value", value).await?;
+    ctx.field_str("unit", spec.unit()).await?;
+    ctx.write_str(spec.name).await?;
+    ctx.write_str(" = ").await?;
+    ctx.write_str(itoa::Buffer::new().format(value)).await?;
+    if !spec.unit().is_empty() {
+        ctx.write_str(" ").await?;
+        ctx.write_str(spec.unit()).await?;
+    }
+    Ok(())
+}
+
+// config save|load|reset|dump - parâmetros na flash (carregados também no boot)
+async fn config<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let action = args.choice(0, Msg::ArgAction, CONFIG_ACTIONS)?;
+    let board = ctx.board;
+    let Some(flash) = board.config_flash() else {
+        return Err(Error::Failed { msg: Msg::NoConfigFlash, code: "no_config_flash" });
+    };
+    match action {
+        0 => {
+            ctx.params.save(&mut ConfigStore::open(flash)?)?;
+            ctx.write_msg(Msg::ConfigSaved).await
+        },
+        1 => {
+            let loaded = ctx.params.load(&ConfigStore::open(flash)?)?;
+            ctx.field_int("loaded", loaded as i64).await?;
+            ctx.write_msg(Msg::ConfigLoaded).await?;
+            ctx.write_str(itoa::Buffer::new().format(loaded)).await?;
+            ctx.write_str("\n").await
+        },
+        2 => {
+            ConfigStore::format(flash)?;
+            ctx.params.reset();
+            ctx.write_msg(Msg::ConfigCleared).await
+        },
+        _ => dump_config(ctx, &ConfigStore::open(flash)?).await,
+    }
+}
+
+// config dump - situação do setor ativo e os valores gravados
+async fn dump_config<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, store: &ConfigStore<'_>) -> Result<(), Error<T::Error>> {
+    let Some(stats) = store.stats() else {
+        return ctx.write_msg(Msg::ConfigEmpty).await;
+    };
+    ctx.field_int("sector", stats.sector as i64).await?;
+    ctx.field_int("generation", stats.generation as i64).await?;
+    ctx.field_int("schema", stats.schema as i64).await?;
+    ctx.field_int("used", stats.used as i64).await?;
+    ctx.field_int("size", stats.size as i64).await?;
+    ctx.write_msg(Msg::ConfigSector).await?;
+    ctx.write_str(itoa::Buffer::new().format(stats.sector)).await?;
+    ctx.write_msg(Msg::ConfigGeneration).await?;
+    ctx.write_str(itoa::Buffer::new().format(stats.generation)).await?;
+    ctx.write_msg(Msg::ConfigSchema).await?;
+    ctx.write_str(itoa::Buffer::new().format(stats.schema)).await?;
+    ctx.write_msg(Msg::ConfigUsed).await?;
+    ctx.write_str(itoa::Buffer::new().format(stats.used)).await?;
+    ctx.write_str("/").await?;
+    ctx.write_str(itoa::Buffer::new().format(stats.size)).await?;
+    ctx.write_str(" bytes\n").await?;
+
+    ctx.begin_array("entries").await?;
+    for key in store.keys()? {
+        let mut value = [0u8; MAX_VALUE];
+        let Some(len) = store.get(&key, &mut value)? else {
+            continue;
+        };
+        ctx.begin_item().await?;
+        ctx.field_str("key", &key).await?;
+        ctx.write_str(&key).await?;
+        ctx.write_str(" =").await?;
+        match value[..len] {
+            // Inteiros (os parâmetros) em decimal; o resto em hexadecimal
+            [a, b, c, d, e, f, g, h] => {
+                let number = i64::from_le_bytes([a, b, c, d, e, f, g, h]);
+                ctx.field_int("value", number).await?;
+                ctx.write_str(" ").await?;
+                ctx.write_str(itoa::Buffer::new().format(number)).await?;
+            },
+            ref bytes => {
+                let mut hex: heapless::String<{ 2 * MAX_VALUE }> = heapless::String::new();
+                for byte in bytes {
+                    let _ = write!(hex, "{:02x}", byte);
+                }
+                ctx.field_str("hex", &hex).await?;
+                ctx.write_str(" 0x").await?;
+                ctx.write_str(&hex).await?;
+            },
+        }
+        ctx.write_str("\n").await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// rx xmodem <arquivo> | rx ymodem - o shell recebe depois da resposta (o YMODEM
+// traz os nomes; um arquivo existente é substituído)
+async fn rx<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let protocol = Protocol::ALL[args.choice(0, Msg::ArgProtocol, PROTOCOLS)?];
+    let name = match protocol {
+        Protocol::Xmodem => Some(file_name(args.token(1, Msg::ArgFile)?.text)?),
+        Protocol::Ymodem => {
+            args.expect_at_most(1)?;
+            None
+        },
+    };
+    start_transfer(ctx, Transfer { send: false, protocol, name, firmware: false }).await
+}
+
+// sx xmodem|ymodem <arquivo>
+async fn sx<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let protocol = Protocol::ALL[args.choice(0, Msg::ArgProtocol, PROTOCOLS)?];
+    let name = file_name(args.token(1, Msg::ArgFile)?.text)?;
+    ctx.files.len(&name).ok_or(FileError::NotFound)?;
+    start_transfer(ctx, Transfer { send: true, protocol, name: Some(name), firmware: false }).await
+}
+
+// Deixa a transferência pendente para o shell (que tem a entrada)
+async fn start_transfer<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, transfer: Transfer) -> Result<(), Error<T::Error>> {
+    if ctx.job.is_some() {
+        return Err(Error::Failed { msg: Msg::ForegroundOnly, code: "foreground_only" });
+    }
+    ctx.settings.transfer = Some(transfer);
+    ctx.field_str("protocol", transfer.protocol.name()).await?;
+    if let Some(name) = &transfer.name {
+        ctx.field_str("name", name.as_str()).await?;
+    }
+    ctx.write_msg(Msg::TransferReady).await
+}
+
+fn file_name<E>(text: &str) -> Result<FileName, Error<E>> {
+    FileName::new(text).ok_or(FileError::BadName.into())
+}
+
+// files - nome e tamanho de cada arquivo
+async fn files<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, _args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let mut empty = true;
+    ctx.begin_array("files").await?;
+    for (name, len) in (0..MAX_FILES).filter_map(|index| ctx.files.entry(index)) {
+        empty = false;
+        ctx.begin_item().await?;
+        ctx.field_str("name", name.as_str()).await?;
+        ctx.field_int("bytes", len as i64).await?;
+        ctx.write_str(name.as_str()).await?;
+        ctx.write_str("  ").await?;
+        ctx.write_str(itoa::Buffer::new().format(len)).await?;
+        ctx.write_str(" bytes\n").await?;
+        ctx.end_item().await?;
+    }
+    if empty {
+        ctx.write_msg(Msg::NoFiles).await?;
+    }
+    ctx.end_array().await
+}
+
+// rm <arquivo>
+async fn rm<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let name = file_name(args.token(0, Msg::ArgFile)?.text)?;
+    ctx.files.remove(&name)?;
+    Ok(())
+}
+
+// fw status|update - estado da troca e as imagens dos slots; `update` recebe
+// a imagem (com o descritor de `tools/fwpack.py`) por YMODEM direto no DFU e
+// reinicia para o bootloader trocar
+async fn fw<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let action = args.choice(0, Msg::ArgAction, FW_ACTIONS)?;
+    let board = ctx.board;
+    let Some(flash) = board.boot_flash() else {
+        return Err(Error::Failed { msg: Msg::NoBootFlash, code: "no_boot_flash" });
+    };
+    let state = boot::state(flash)?;
+    if action == 1 {
+        // Em teste o DFU guarda a imagem anterior (a do rollback)
+        if state == BootState::Testing {
+            return Err(Error::Failed { msg: Msg::FirmwareTesting, code: "firmware_testing" });
+        }
+        let transfer = Transfer { send: false, protocol: Protocol::Ymodem, name: None, firmware: true };
+        return start_transfer(ctx, transfer).await;
+    }
+    ctx.field_str("state", state.name()).await?;
+    ctx.write_msg(Msg::FwState).await?;
+    ctx.write_str(state.name()).await?;
+    ctx.write_str("\n").await?;
+    ctx.write_msg(Msg::FwActive).await?;
+    show_image(ctx, flash, Region::Active, ["active_bytes", "active_sha256"]).await?;
+    ctx.write_msg(Msg::FwDfu).await?;
+    show_image(ctx, flash, Region::Dfu, ["dfu_bytes", "dfu_sha256"]).await
+}
+
+// "N bytes, sha256 xxxxxxxx..." (tamanho e hash completo nos campos `keys` do
+// json; a imagem gravada pelo probe não tem descritor)
+async fn show_image<T: Write, B: Board>(
+    ctx: &mut Context<'_, T, B>,
+    flash: &dyn BootFlash,
+    region: Region,
+    keys: [&str; 2],
+) -> Result<(), Error<T::Error>> {
+    let Some(info) = boot::image_info(flash, region)? else {
+        return ctx.write_msg(Msg::FwNoImage).await;
+    };
+    let mut hex: heapless::String<64> = heapless::String::new();
+    for byte in info.digest {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    ctx.field_int(keys[0], info.len as i64).await?;
+    ctx.field_str(keys[1], &hex).await?;
+    ctx.write_str(itoa::Buffer::new().format(info.len)).await?;
+    ctx.write_str(" bytes, sha256 ").await?;
+    ctx.write_str(&hex[..16]).await?;
+    ctx.write_str("...\n").await
+}
+
+// Largura opcional na posição `index` (`default` se omitida)
+fn width_arg(args: &Args<'_>, index: usize, default: Width) -> Result<Width, ArgError> {
+    match args.get(index) {
+        Some(_) => Ok(Width::ALL[args.choice(index, Msg::ArgWidth, WIDTHS)?]),
+        None => Ok(default),
+    }
+}
+
+// peek <endereco> [n] [8|16|32] - n valores a partir do endereço (32 bits se
+// a largura for omitida)
+async fn peek<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let address = args.int(0, Msg::ArgAddress, 0, ADDRESS_MAX)? as u32;
+    let count = match args.get(1) {
+        Some(_) => args.int(1, Msg::ArgCount, 1, PEEK_MAX)? as usize,
+        None => 1,
+    };
+    let width = width_arg(args, 2, Width::Word)?;
+    let board = ctx.board;
+    memory::check(board.memory_map(), address, count * width.bytes(), width, Access::Read)?;
+
+    ctx.begin_array("values").await?;
+    for index in 0..count {
+        let at = address + (index * width.bytes()) as u32;
+        let value = board.read_memory(at, width);
+        ctx.begin_item().await?;
+        ctx.field_int("address", at as i64).await?;
+        ctx.field_int("value", value as i64).await?;
+        show_value(ctx, at, value, width).await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// poke <endereco> <valor> [8|16|32] - grava e mostra o valor lido de volta
+// (registradores podem ignorar bits ou mudar sozinhos). Onde a leitura altera
+// o registrador mostra o valor gravado
+async fn poke<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let address = args.int(0, Msg::ArgAddress, 0, ADDRESS_MAX)? as u32;
+    let width = width_arg(args, 2, Width::Word)?;
+    let mut value = args.int(1, Msg::ArgValue, 0, width.max() as i64)? as u32;
+    let board = ctx.board;
+    let map = board.memory_map();
+    memory::check(map, address, width.bytes(), width, Access::Write)?;
+    board.write_memory(address, width, value);
+    if memory::check(map, address, width.bytes(), width, Access::Read).is_ok() {
+        value = board.read_memory(address, width);
+    }
+    ctx.field_int("address", address as i64).await?;
+    ctx.field_int("value", value as i64).await?;
+    show_value(ctx, address, value, width).await
+}
+
+// "0x40023830: 0x00100000" (com os dígitos da largura)
+async fn show_value<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, address: u32, value: u32, width: Width) -> Result<(), Error<T::Error>> {
+    let mut line: heapless::String<32> = heapless::String::new();
+    let _ = writeln!(line, "0x{:08x}: 0x{:0digits$x}", address, value, digits = 2 * width.bytes());
+    ctx.write_str(&line).await
+}
+
+// dump <endereco> [bytes] [8|16|32] - 16 bytes por linha em hexadecimal (em
+// grupos da largura, como valores) e em ASCII; no json os bytes em ordem
+async fn dump<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let address = args.int(0, Msg::ArgAddress, 0, ADDRESS_MAX)? as u32;
+    let len = match args.get(1) {
+        Some(_) => args.int(1, Msg::ArgBytes, 1, DUMP_MAX)? as usize,
+        None => DUMP_DEFAULT,
+    };
+    let width = width_arg(args, 2, Width::Byte)?;
+    let len = len.next_multiple_of(width.bytes());
+    let board = ctx.board;
+    memory::check(board.memory_map(), address, len, width, Access::Read)?;
+
+    ctx.begin_array("lines").await?;
+    for start in (0..len).step_by(DUMP_LINE) {
+        let at = address + start as u32;
+        let mut bytes = [0u8; DUMP_LINE];
+        let count = (len - start).min(DUMP_LINE);
+        let mut text: heapless::String<80> = heapless::String::new();
+        let _ = write!(text, "{:08x} ", at);
+        for offset in (0..count).step_by(width.bytes()) {
+            let value = board.read_memory(at + offset as u32, width);
+            bytes[offset..offset + width.bytes()].copy_from_slice(&value.to_le_bytes()[..width.bytes()]);
+            let _ = write!(text, " {:0digits$x}", value, digits = 2 * width.bytes());
+        }
+        let mut hex: heapless::String<{ 2 * DUMP_LINE }> = heapless::String::new();
+        for byte in &bytes[..count] {
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        // Alinha a coluna ASCII na última linha incompleta
+        let missing = DUMP_LINE - count;
+        let pad = missing / width.bytes() * (2 * width.bytes() + 1);
+        let _ = write!(text, "{:pad$}  |", "", pad = pad);
+        for &byte in &bytes[..count] {
+            let _ = text.push(if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' });
+        }
+        let _ = text.push_str("|\n");
+
+        ctx.begin_item().await?;
+        ctx.field_int("address", at as i64).await?;
+        ctx.field_str("data", &hex).await?;
+        ctx.write_str(&text).await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// reg [periferico] [registrador] - sem argumentos lista os periféricos; com o
+// periférico mostra cada registrador; com o registrador, os campos
+async fn reg<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let board = ctx.board;
+    let Some(name) = args.str(0) else {
+        return list_peripherals(ctx).await;
+    };
+    let Some(peripheral) = registers::find(board.peripherals(), name) else {
+        return Err(Error::Failed { msg: Msg::UnknownPeripheral, code: "unknown_peripheral" });
+    };
+    if let Some(name) = args.str(1) {
+        let Some(register) = peripheral.register(name) else {
+            return Err(Error::Failed { msg: Msg::UnknownRegister, code: "unknown_register" });
+        };
+        return show_register(ctx, peripheral, register).await;
+    }
+
+    ctx.begin_array("registers").await?;
+    for register in peripheral.registers {
+        let address = peripheral.base + register.offset;
+        let mut line: heapless::String<40> = heapless::String::new();
+        let _ = write!(line, "{:<8} 0x{:08x}", register.name, address);
+        ctx.begin_item().await?;
+        ctx.field_str("name", register.name).await?;
+        ctx.field_int("address", address as i64).await?;
+        ctx.write_str(&line).await?;
+        match read_register(board, address) {
+            Ok(value) => {
+                ctx.field_int("value", value as i64).await?;
+                line.clear();
+                let _ = writeln!(line, " = 0x{:08x}", value);
+                ctx.write_str(&line).await?;
+            },
+            // Os que a leitura altera aparecem, sem valor
+            Err(MemoryError::ReadClears) => ctx.write_msg(Msg::RegisterNotRead).await?,
+            Err(e) => return Err(e.into()),
+        }
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// Periféricos conhecidos e os endereços
+async fn list_peripherals<T: Write, B: Board>(ctx: &mut Context<'_, T, B>) -> Result<(), Error<T::Error>> {
+    ctx.begin_array("peripherals").await?;
+    for peripheral in ctx.board.peripherals() {
+        let mut line: heapless::String<40> = heapless::String::new();
+        let _ = writeln!(line, "{:<8} 0x{:08x}", peripheral.name, peripheral.base);
+        ctx.begin_item().await?;
+        ctx.field_str("name", peripheral.name).await?;
+        ctx.field_int("address", peripheral.base as i64).await?;
+        ctx.write_str(&line).await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// "RCC.CFGR 0x40023808 = 0x0000940a" e um campo por linha (decimal)
+async fn show_register<T: Write, B: Board>(
+    ctx: &mut Context<'_, T, B>,
+    peripheral: &Peripheral,
+    register: &Register,
+) -> Result<(), Error<T::Error>> {
+    let board = ctx.board;
+    let address = peripheral.base + register.offset;
+    let value = read_register(board, address)?;
+    ctx.field_str("peripheral", peripheral.name).await?;
+    ctx.field_str("register", register.name).await?;
+    ctx.field_int("address", address as i64).await?;
+    ctx.field_int("value", value as i64).await?;
+    let mut line: heapless::String<72> = heapless::String::new();
+    let _ = writeln!(line, "{}.{} 0x{:08x} = 0x{:08x}", peripheral.name, register.name, address, value);
+    ctx.write_str(&line).await?;
+
+    ctx.begin_array("fields").await?;
+    for field in register.fields {
+        for index in 0..field.count {
+            let mut name: heapless::String<24> = heapless::String::new();
+            let _ = name.push_str(field.name);
+            if field.count > 1 {
+                let _ = write!(name, "{}", index);
+            }
+            let field_value = field.value(value, index);
+            ctx.begin_item().await?;
+            ctx.field_str("name", &name).await?;
+            ctx.field_int("value", field_value as i64).await?;
+            line.clear();
+            let _ = writeln!(line, "  {:<14} = {}", name, field_value);
+            ctx.write_str(&line).await?;
+            ctx.end_item().await?;
+        }
+    }
+    ctx.end_array().await
+}
+
+// Lê um registrador de 32 bits (se estiver no mapa de memória da placa e a
+// leitura não o alterar, como no `peek`)
+fn read_register<B: Board>(board: &B, address: u32) -> Result<u32, MemoryError> {
+    memory::check(board.memory_map(), address, Width::Word.bytes(), Width::Word, Access::Read)?;
+    Ok(board.read_memory(address, Width::Word))
+}
+
+// gpio [list | mode | read | write | watch | free] - pinos livres; os dos
+// periféricos (registrados pela aplicação) só podem ser lidos
+async fn gpio<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, args: &Args<'_>) -> Result<(), Error<T::Error>> {
+    let action = match args.get(0) {
+        Some(_) => args.choice(0, Msg::ArgAction, GPIO_ACTIONS)?,
+        None => 0,
+    };
+    if action == 0 {
+        args.expect_at_most(1)?;
+        return list_pins(ctx).await;
+    }
+    let board = ctx.board;
+    let text = args.token(1, Msg::ArgPin)?.text;
+    let Some(pin) = PinId::parse(text).filter(|&pin| board.has_pin(pin)) else {
+        return Err(Error::Failed { msg: Msg::BadPin, code: "bad_pin" });
+    };
+    match action {
+        1 => {
+            let mode = PinMode::ALL[args.choice(2, Msg::ArgMode, PIN_MODES)?];
+            let pull = match args.get(3) {
+                Some(_) => Pull::ALL[args.choice(3, Msg::ArgPull, PULLS)?],
+                None => Pull::None,
+            };
+            ctx.pins.configure(pin, mode, pull)?;
+            board.configure_pin(pin, mode, pull);
+            Ok(())
+        },
+        2 => {
+            args.expect_at_most(2)?;
+            show_level(ctx, pin, board.read_pin(pin)).await
+        },
+        3 => {
+            let high = args.int(2, Msg::ArgLevel, 0, 1)? == 1;
+            args.expect_at_most(3)?;
+            ctx.pins.check_output(pin)?;
+            board.write_pin(pin, high);
+            Ok(())
+        },
+        4 => {
+            args.expect_at_most(2)?;
+            watch_pin(ctx, pin).await
+        },
+        _ => {
+            args.expect_at_most(2)?;
+            ctx.pins.release(pin)?;
+            board.release_pin(pin);
+            Ok(())
+        },
+    }
+}
+
+// Pinos registrados: dono (periférico) ou modo e pull (shell)
+async fn list_pins<T: Write, B: Board>(ctx: &mut Context<'_, T, B>) -> Result<(), Error<T::Error>> {
+    ctx.begin_array("pins").await?;
+    for (pin, owner) in (0..).map_while(|index| ctx.pins.entry(index)) {
+        let mut name: heapless::String<8> = heapless::String::new();
+        let _ = write!(name, "{}", pin);
+        let mut line: heapless::String<32> = heapless::String::new();
+        let _ = write!(line, "{:<5} ", name);
+        ctx.begin_item().await?;
+        ctx.field_str("pin", &name).await?;
+        match owner {
+            Owner::Peripheral(owner) => {
+                let _ = writeln!(line, "{}", owner);
+                ctx.field_str("owner", owner).await?;
+            },
+            Owner::Shell(mode, pull) => {
+                let _ = writeln!(line, "gpio {} {}", mode.name(), pull.name());
+                ctx.field_str("owner", "gpio").await?;
+                ctx.field_str("mode", mode.name()).await?;
+                ctx.field_str("pull", pull.name()).await?;
+            },
+        }
+        ctx.write_str(&line).await?;
+        ctx.end_item().await?;
+    }
+    ctx.end_array().await
+}
+
+// "PA5 1"
+async fn show_level<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, pin: PinId, high: bool) -> Result<(), Error<T::Error>> {
+    let mut name: heapless::String<8> = heapless::String::new();
+    let _ = write!(name, "{}", pin);
+    ctx.field_str("pin", &name).await?;
+    ctx.field_int("level", high as i64).await?;
+    ctx.write_str(&name).await?;
+    ctx.write_str(if high { " 1\n" } else { " 0\n" }).await
+}
+
+// Uma linha por borda até o Ctrl-C (tempo do relógio da placa desde o início:
+// a espera entre as amostras pode passar de GPIO_POLL_MS; a primeira linha é o
+// nível inicial)
+async fn watch_pin<T: Write, B: Board>(ctx: &mut Context<'_, T, B>, pin: PinId) -> Result<(), Error<T::Error>> {
+    let board = ctx.board;
+    ctx.write_msg(Msg::GpioWatch).await?;
+    let start = board.uptime_ms();
+    let mut level = board.read_pin(pin);
+    let mut elapsed_ms: u64 = 0;
+    loop {
+        ctx.write_str("+").await?;
+        ctx.write_str(itoa::Buffer::new().format(elapsed_ms)).await?;
+        ctx.write_str(" ").await?;
+        ctx.field_int("ms", elapsed_ms as i64).await?;
+        show_level(ctx, pin, level).await?;
+        ctx.end_record().await?;
+
+        // Espera a próxima borda
+        while board.read_pin(pin) == level {
+            board.delay_ms(GPIO_POLL_MS).await;
+        }
+        elapsed_ms = board.uptime_ms().saturating_sub(start);
+        level = !level;
+    }
+}
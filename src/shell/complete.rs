@@ -0,0 +1,144 @@
+// Completação com Tab: nomes de comando e argumentos de valores fixos,
+// a partir dos mesmos metadados da tabela de comandos
+
+use super::chain;
+use super::registry::{self, ArgKind};
+
+// Resultado da completação da palavra sob o cursor
+pub struct Completion {
+    pub count: usize,        // Quantidade de candidatos
+    pub first: &'static str, // Primeiro candidato
+    pub common: usize,       // Prefixo comum a todos os candidatos (bytes)
+    pub typed: usize,        // Parte da palavra já digitada (bytes)
+}
+
+impl Completion {
+    // Texto a inserir: o resto do candidato único, ou o prefixo comum
+    pub fn insertion(&self) -> &'static str {
+        match self.count {
+            0 => "",
+            1 => &self.first[self.typed..],
+            _ => &self.first[self.typed..self.common],
+        }
+    }
+}
+
+// Chama `f` para cada candidato que começa com a palavra sob o cursor
+pub fn candidates(before_cursor: &str, mut f: impl FnMut(&'static str)) {
+    let (index, partial, command) = split(before_cursor);
+    let mut offer = |value: &'static str| {
+        if value.starts_with(partial) {
+            f(value);
+        }
+    };
+
+    if index == 0 {
+        registry::all().iter().for_each(|c| offer(c.name));
+        return;
+    }
+
+    // Argumento posicional `index - 1` do comando digitado
+    let Some(spec) = registry::find(command).and_then(|c| c.args.get(index - 1)) else {
+        return;
+    };
+    match spec.kind {
+        ArgKind::Choice(choices) => choices.iter().for_each(|c| offer(c)),
+        ArgKind::Command | ArgKind::CommandLine => registry::all().iter().for_each(|c| offer(c.name)),
+        _ => {},
+    }
+}
+
+// Calcula a completação para o texto antes do cursor
+pub fn complete(before_cursor: &str) -> Completion {
+    let typed = split(before_cursor).1.len();
+    let mut result = Completion { count: 0, first: "", common: 0, typed };
+    candidates(before_cursor, |candidate| {
+        if result.count == 0 {
+            result.first = candidate;
+            result.common = candidate.len();
+        } else {
+            result.common = common_prefix(result.first, candidate).min(result.common);
+        }
+        result.count += 1;
+    });
+    result
+}
+
+// Separa: índice da palavra sob o cursor, a parte já digitada e o nome do comando
+// (do último comando da cadeia; em `repeat 3 led o`, do comando repetido)
+fn split(before_cursor: &str) -> (usize, &str, &str) {
+    let mut line = chain::last(before_cursor);
+    loop {
+        let partial = line.rsplit(' ').next().unwrap_or("");
+        let head = &line[..line.len() - partial.len()];
+        let command = head.split_whitespace().next().unwrap_or("");
+        let index = head.split_whitespace().count();
+
+        let inner = registry::find(command)
+            .and_then(|c| c.command_line())
+            .and_then(|position| head.split_whitespace().nth(position + 1));
+        match inner {
+            // Recomeça a partir do nome do comando interno
+            Some(word) => line = &line[word.as_ptr() as usize - line.as_ptr() as usize..],
+            None => return (index, partial, command),
+        }
+    }
+}
+
+// Tamanho (bytes) do prefixo comum entre dois textos
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, ca), cb)| ca != cb)
+        .map_or(a.len().min(b.len()), |((i, _), _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all(before_cursor: &str) -> std::vec::Vec<&'static str> {
+        let mut out = std::vec::Vec::new();
+        candidates(before_cursor, |c| out.push(c));
+        out
+    }
+
+    #[test]
+    fn command_names() {
+        let completion = complete("sta");
+        assert_eq!((completion.count, completion.insertion()), (1, "tus"));
+        // Vários candidatos: só o prefixo comum
+        assert_eq!(all("p"), ["ps", "passwd", "params", "peek", "poke"]);
+        let completion = complete("pa");
+        assert_eq!((completion.count, completion.insertion()), (2, ""));
+        assert_eq!(complete("po").insertion(), "ke");
+        assert_eq!(complete("xyz").count, 0);
+    }
+
+    #[test]
+    fn choice_arguments() {
+        assert_eq!(all("led "), ["on", "off", "toggle"]);
+        assert_eq!(complete("led o").insertion(), "");
+        assert_eq!(complete("led t").insertion(), "oggle");
+        // Texto livre e argumentos além da especificação não completam
+        assert!(all("led on ").is_empty());
+        assert!(all("peek ").is_empty());
+        assert!(all("nada ").is_empty());
+    }
+
+    #[test]
+    fn chained_and_repeated_commands() {
+        assert_eq!(complete("status; le").insertion(), "d");
+        assert_eq!(complete("status && led of").insertion(), "f");
+        assert_eq!(complete("help rep").insertion(), "eat");
+        assert_eq!(complete("repeat 3 led to").insertion(), "ggle");
+        assert_eq!(complete("watch 1s repeat 2 led of").insertion(), "f");
+    }
+
+    #[test]
+    fn common_prefix_is_char_aligned() {
+        assert_eq!(common_prefix("ação", "açúcar"), 3); // "aç"
+        assert_eq!(common_prefix("ab", "abc"), 2);
+        assert_eq!(common_prefix("", "x"), 0);
+    }
+}
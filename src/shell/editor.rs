@@ -0,0 +1,541 @@
+// Editor de linha: decodificação de teclas VT100/ANSI, cursor, inserção no meio
+// da linha e atalhos estilo readline (Ctrl-A/E/K/U/W)
+
+use heapless::{String, Vec};
+
+use super::history::Recall;
+use super::utf8::{is_extend, width};
+
+// Capacidade padrão da linha (bytes UTF-8)
+pub const LINE_LEN: usize = 128;
+
+// Linha completa em UTF-8
+pub type Line<const N: usize = LINE_LEN> = String<N>;
+
+// Sequências de escape de um redesenho, além do texto
+const ESCAPES_LEN: usize = 64;
+
+// Bytes a enviar ao terminal após uma tecla ('\n' = fim de linha configurado).
+// Cabe duas vezes a linha de `N` bytes (texto + espaços para apagar, ou as duas
+// inserções da completação) mais as sequências de escape; guardado em partes
+// porque `2 * N` não pode ser o tamanho de um `Vec`
+pub struct Echo<const N: usize = LINE_LEN> {
+    parts: [Vec<u8, N>; 2],
+    escapes: Vec<u8, ESCAPES_LEN>,
+}
+
+impl<const N: usize> Default for Echo<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Echo<N> {
+    pub const fn new() -> Self {
+        Self { parts: [Vec::new(), Vec::new()], escapes: Vec::new() }
+    }
+
+    // Acrescenta no fim (as partes enchem em ordem)
+    fn push(&mut self, mut bytes: &[u8]) {
+        for part in &mut self.parts {
+            let n = bytes.len().min(N - part.len());
+            let _ = part.extend_from_slice(&bytes[..n]);
+            bytes = &bytes[n..];
+        }
+        let n = bytes.len().min(ESCAPES_LEN - self.escapes.len());
+        let _ = self.escapes.extend_from_slice(&bytes[..n]);
+    }
+
+    // Conteúdo em ordem, para escrever parte a parte
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        let [first, second] = &self.parts;
+        [first.as_slice(), second.as_slice(), self.escapes.as_slice()].into_iter()
+    }
+}
+
+// Teclas reconhecidas pelo editor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char), // Caractere imprimível
+    Enter,      // \r ou \n
+    Backspace,  // \x08 ou \x7f
+    Delete,     // ESC [ 3 ~
+    Left,       // ESC [ D
+    Right,      // ESC [ C
+    Up,         // ESC [ A
+    Down,       // ESC [ B
+    Home,       // ESC [ H, ESC [ 1 ~, ESC [ 7 ~, Ctrl-A
+    End,        // ESC [ F, ESC [ 4 ~, ESC [ 8 ~, Ctrl-E
+    KillToEnd,  // Ctrl-K
+    KillToStart, // Ctrl-U
+    KillWord,   // Ctrl-W
+    Interrupt,  // Ctrl-C
+    Tab,        // \t
+}
+
+// Estado do decodificador de sequências de escape
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EscState {
+    Normal,
+    Esc,          // Recebeu ESC
+    Csi(u8),      // Recebeu ESC [ (parâmetro numérico acumulado)
+    Ss3,          // Recebeu ESC O
+}
+
+// Converte caracteres recebidos em teclas (sequências ANSI de vários bytes)
+pub struct KeyDecoder {
+    state: EscState,
+}
+
+impl Default for KeyDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyDecoder {
+    pub const fn new() -> Self {
+        Self { state: EscState::Normal }
+    }
+
+    // Processa um caractere; retorna a tecla quando a sequência termina
+    pub fn feed(&mut self, c: char) -> Option<Key> {
+        match self.state {
+            EscState::Normal => match c {
+                '\x1b' => {
+                    self.state = EscState::Esc;
+                    None
+                },
+                '\r' | '\n' => Some(Key::Enter),
+                '\x08' | '\x7f' => Some(Key::Backspace),
+                '\t' => Some(Key::Tab),
+                '\x01' => Some(Key::Home),
+                '\x03' => Some(Key::Interrupt),
+                '\x05' => Some(Key::End),
+                '\x0b' => Some(Key::KillToEnd),
+                '\x15' => Some(Key::KillToStart),
+                '\x17' => Some(Key::KillWord),
+                c if !c.is_control() => Some(Key::Char(c)),
+                _ => None, // Outros caracteres de controle são ignorados
+            },
+            EscState::Esc => {
+                self.state = match c {
+                    '[' => EscState::Csi(0),
+                    'O' => EscState::Ss3,
+                    _ => EscState::Normal, // Sequência desconhecida
+                };
+                None
+            },
+            EscState::Csi(param) => {
+                if let Some(digit) = c.to_digit(10) {
+                    self.state = EscState::Csi(param.saturating_mul(10).saturating_add(digit as u8));
+                    return None;
+                }
+                self.state = EscState::Normal;
+                match (c, param) {
+                    ('A', _) => Some(Key::Up),
+                    ('B', _) => Some(Key::Down),
+                    ('C', _) => Some(Key::Right),
+                    ('D', _) => Some(Key::Left),
+                    ('H', _) => Some(Key::Home),
+                    ('F', _) => Some(Key::End),
+                    ('~', 1) | ('~', 7) => Some(Key::Home),
+                    ('~', 4) | ('~', 8) => Some(Key::End),
+                    ('~', 3) => Some(Key::Delete),
+                    (';', _) => {
+                        // Modificadores (ex: ESC [ 1 ; 5 C) - descarta o parâmetro
+                        self.state = EscState::Csi(0);
+                        None
+                    },
+                    _ => None,
+                }
+            },
+            EscState::Ss3 => {
+                self.state = EscState::Normal;
+                match c {
+                    'A' => Some(Key::Up),
+                    'B' => Some(Key::Down),
+                    'C' => Some(Key::Right),
+                    'D' => Some(Key::Left),
+                    'H' => Some(Key::Home),
+                    'F' => Some(Key::End),
+                    _ => None,
+                }
+            },
+        }
+    }
+}
+
+// Resultado de uma tecla processada pelo editor
+// (a linha vai por valor: sem alocação dinâmica não há como usar Box)
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event<const N: usize = LINE_LEN> {
+    Line(Line<N>), // Enter: linha completa
+    Interrupt,     // Ctrl-C: linha descartada
+    Complete,      // Tab: pedido de completação
+    TooLong,       // Caractere recusado: linha cheia
+}
+
+// Linha em edição com cursor; capacidade de `N` bytes UTF-8.
+// O cursor fica sempre no início de um grafema (caractere base + combinantes)
+pub struct LineEditor<const N: usize = LINE_LEN> {
+    chars: Vec<char, N>, // Conteúdo da linha
+    bytes: usize,        // Tamanho da linha em UTF-8
+    cursor: usize,       // Posição do cursor (em caracteres)
+    nav: Option<usize>,  // Entrada do histórico exibida (setas)
+    stash: Vec<char, N>, // Linha digitada antes de navegar no histórico
+}
+
+impl<const N: usize> Default for LineEditor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> LineEditor<N> {
+    pub const fn new() -> Self {
+        Self { chars: Vec::new(), bytes: 0, cursor: 0, nav: None, stash: Vec::new() }
+    }
+
+    // Capacidade da linha em bytes
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    // Conteúdo atual e posição do cursor
+    pub fn chars(&self) -> &[char] {
+        &self.chars
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    // Aplica uma tecla; o redesenho do terminal é acumulado em `echo`
+    pub fn handle(&mut self, key: Key, echo: &mut Echo<N>, history: &dyn Recall) -> Option<Event<N>> {
+        match key {
+            Key::Char(c) => {
+                if !self.insert(c, echo) {
+                    return Some(Event::TooLong);
+                }
+            },
+            Key::Enter => {
+                push_str(echo, "\n");
+                let line = self.chars.iter().collect();
+                self.clear();
+                return Some(Event::Line(line));
+            },
+            Key::Interrupt => {
+                self.end(echo);
+                push_str(echo, "^C\n");
+                self.clear();
+                return Some(Event::Interrupt);
+            },
+            Key::Backspace => {
+                if self.cursor > 0 {
+                    // Apaga o grafema inteiro (base + acentos combinantes)
+                    let start = self.prev_boundary(self.cursor);
+                    move_left(echo, self.columns(start, self.cursor));
+                    let count = self.cursor - start;
+                    self.cursor = start;
+                    self.remove(start, count, echo);
+                }
+            },
+            Key::Delete => {
+                if self.cursor < self.chars.len() {
+                    let count = self.next_boundary(self.cursor) - self.cursor;
+                    self.remove(self.cursor, count, echo);
+                }
+            },
+            Key::Left => {
+                if self.cursor > 0 {
+                    let start = self.prev_boundary(self.cursor);
+                    move_left(echo, self.columns(start, self.cursor));
+                    self.cursor = start;
+                }
+            },
+            Key::Right => {
+                if self.cursor < self.chars.len() {
+                    let end = self.next_boundary(self.cursor);
+                    for &c in &self.chars[self.cursor..end] {
+                        push_char(echo, c);
+                    }
+                    self.cursor = end;
+                }
+            },
+            Key::Home => self.home(echo),
+            Key::End => self.end(echo),
+            Key::KillToEnd => {
+                let count = self.chars.len() - self.cursor;
+                self.remove(self.cursor, count, echo);
+            },
+            Key::KillToStart => {
+                let count = self.cursor;
+                self.home(echo);
+                self.remove(0, count, echo);
+            },
+            Key::KillWord => {
+                // Apaga espaços e a palavra anteriores ao cursor
+                let mut start = self.cursor;
+                while start > 0 && self.chars[start - 1] == ' ' {
+                    start -= 1;
+                }
+                while start > 0 && self.chars[start - 1] != ' ' {
+                    start -= 1;
+                }
+                let count = self.cursor - start;
+                move_left(echo, self.columns(start, self.cursor));
+                self.cursor = start;
+                self.remove(start, count, echo);
+            },
+            Key::Up => {
+                // Entrada anterior do histórico (guarda a linha atual na primeira vez)
+                let index = match self.nav {
+                    None if history.is_empty() => return None,
+                    None => {
+                        self.stash = self.chars.clone();
+                        history.len() - 1
+                    },
+                    Some(index) => index.saturating_sub(1),
+                };
+                self.nav = Some(index);
+                self.replace(history.entry(index).unwrap_or("").chars(), echo);
+            },
+            Key::Down => match self.nav {
+                Some(index) if index + 1 < history.len() => {
+                    self.nav = Some(index + 1);
+                    self.replace(history.entry(index + 1).unwrap_or("").chars(), echo);
+                },
+                Some(_) => {
+                    // Passou da última entrada: volta à linha que estava sendo digitada
+                    self.nav = None;
+                    let stash = core::mem::take(&mut self.stash);
+                    self.replace(stash.into_iter(), echo);
+                },
+                None => {},
+            },
+            Key::Tab => return Some(Event::Complete),
+        }
+        None
+    }
+
+    // Texto antes do cursor (palavra a completar)
+    pub fn before_cursor(&self) -> Line<N> {
+        self.chars[..self.cursor].iter().collect()
+    }
+
+    // Insere um texto na posição do cursor; false se a linha encher
+    pub fn insert_str(&mut self, s: &str, echo: &mut Echo<N>) -> bool {
+        // Um só redesenho do resto da linha (não um por caractere)
+        let start = self.cursor;
+        let fits = s.chars().all(|c| self.insert_char(c));
+        for &c in &self.chars[start..self.cursor] {
+            push_char(echo, c);
+        }
+        self.redraw_tail(echo, 0);
+        fits
+    }
+
+    // Reescreve a linha inteira (após o prompt) e reposiciona o cursor
+    pub fn redraw(&self, echo: &mut Echo<N>) {
+        for &c in self.chars.iter() {
+            push_char(echo, c);
+        }
+        move_left(echo, self.columns(self.cursor, self.chars.len()));
+    }
+
+    // Descarta o conteúdo sem redesenhar
+    pub fn clear(&mut self) {
+        self.chars.clear();
+        self.bytes = 0;
+        self.cursor = 0;
+        self.nav = None;
+        self.stash.clear();
+    }
+
+    // Início do grafema que termina em `index`
+    fn prev_boundary(&self, index: usize) -> usize {
+        let mut start = index.saturating_sub(1);
+        while start > 0 && (is_extend(self.chars[start]) || self.chars[start - 1] == '\u{200D}') {
+            start -= 1;
+        }
+        start
+    }
+
+    // Fim do grafema que começa em `index`
+    fn next_boundary(&self, index: usize) -> usize {
+        let mut end = index + 1;
+        while end < self.chars.len() && (is_extend(self.chars[end]) || self.chars[end - 1] == '\u{200D}') {
+            end += 1;
+        }
+        end
+    }
+
+    // Colunas do terminal ocupadas por chars[from..to]
+    fn columns(&self, from: usize, to: usize) -> usize {
+        self.chars[from..to].iter().map(|&c| width(c)).sum()
+    }
+
+    // Substitui a linha inteira e deixa o cursor no fim
+    fn replace(&mut self, new: impl Iterator<Item = char>, echo: &mut Echo<N>) {
+        move_left(echo, self.columns(0, self.cursor));
+        let old_columns = self.columns(0, self.chars.len());
+        self.chars.clear();
+        self.bytes = 0;
+        for c in new {
+            if self.bytes + c.len_utf8() > N || self.chars.push(c).is_err() {
+                break;
+            }
+            self.bytes += c.len_utf8();
+            push_char(echo, c);
+        }
+        self.cursor = self.chars.len();
+
+        // Apaga o que sobrou da linha anterior
+        let erase = old_columns.saturating_sub(self.columns(0, self.chars.len()));
+        for _ in 0..erase {
+            push_str(echo, " ");
+        }
+        move_left(echo, erase);
+    }
+
+    // Insere um caractere na posição do cursor; false se não couber
+    fn insert(&mut self, c: char, echo: &mut Echo<N>) -> bool {
+        if !self.insert_char(c) {
+            return false;
+        }
+        push_char(echo, c);
+        self.redraw_tail(echo, 0);
+        true
+    }
+
+    // Insere sem redesenhar
+    fn insert_char(&mut self, c: char) -> bool {
+        if self.bytes + c.len_utf8() > N || self.chars.insert(self.cursor, c).is_err() {
+            return false;
+        }
+        self.bytes += c.len_utf8();
+        self.cursor += 1;
+        true
+    }
+
+    // Remove `count` caracteres a partir de `at` (o cursor já está em `at`)
+    fn remove(&mut self, at: usize, count: usize, echo: &mut Echo<N>) {
+        if count == 0 {
+            return;
+        }
+        let erase = self.columns(at, at + count);
+        for _ in 0..count {
+            self.bytes -= self.chars.remove(at).len_utf8();
+        }
+        self.redraw_tail(echo, erase);
+    }
+
+    // Reescreve o trecho após o cursor, apaga `erase` colunas que sobraram e volta o cursor
+    fn redraw_tail(&self, echo: &mut Echo<N>, erase: usize) {
+        let tail = &self.chars[self.cursor..];
+        for &c in tail {
+            push_char(echo, c);
+        }
+        for _ in 0..erase {
+            push_str(echo, " ");
+        }
+        move_left(echo, self.columns(self.cursor, self.chars.len()) + erase);
+    }
+
+    fn home(&mut self, echo: &mut Echo<N>) {
+        move_left(echo, self.columns(0, self.cursor));
+        self.cursor = 0;
+    }
+
+    fn end(&mut self, echo: &mut Echo<N>) {
+        move_right(echo, self.columns(self.cursor, self.chars.len()));
+        self.cursor = self.chars.len();
+    }
+}
+
+fn push_str<const N: usize>(echo: &mut Echo<N>, s: &str) {
+    echo.push(s.as_bytes());
+}
+
+fn push_char<const N: usize>(echo: &mut Echo<N>, c: char) {
+    let mut utf8 = [0u8; 4];
+    push_str(echo, c.encode_utf8(&mut utf8));
+}
+
+// Move o cursor do terminal `n` colunas (ESC [ n D / ESC [ n C)
+fn move_cursor<const N: usize>(echo: &mut Echo<N>, n: usize, code: &str) {
+    if n > 0 {
+        push_str(echo, "\x1b[");
+        push_str(echo, itoa::Buffer::new().format(n));
+        push_str(echo, code);
+    }
+}
+
+fn move_left<const N: usize>(echo: &mut Echo<N>, n: usize) {
+    move_cursor(echo, n, "D");
+}
+
+fn move_right<const N: usize>(echo: &mut Echo<N>, n: usize) {
+    move_cursor(echo, n, "C");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::history::NoHistory;
+
+    fn output<const N: usize>(echo: &Echo<N>) -> std::vec::Vec<u8> {
+        echo.chunks().flatten().copied().collect()
+    }
+
+    #[test]
+    fn echo_fits_long_lines() {
+        // Linha maior que a padrão: o redesenho cresce com `N`
+        let mut editor: LineEditor<512> = LineEditor::new();
+        let mut echo = Echo::new();
+        assert!(editor.insert_str(&"a".repeat(512), &mut echo));
+        assert_eq!(output(&echo), "a".repeat(512).as_bytes());
+
+        editor.handle(Key::Home, &mut Echo::new(), &NoHistory);
+        let mut echo = Echo::new();
+        editor.handle(Key::Delete, &mut echo, &NoHistory);
+        let expected = format!("{} \x1b[512D", "a".repeat(511));
+        assert_eq!(output(&echo), expected.as_bytes());
+    }
+
+    #[test]
+    fn completion_inserts_with_one_redraw() {
+        let mut editor: LineEditor = LineEditor::new();
+        let mut echo = Echo::new();
+        editor.insert_str("ab", &mut echo);
+        editor.handle(Key::Left, &mut echo, &NoHistory);
+        let mut echo = Echo::new();
+        editor.insert_str("xyz", &mut echo);
+        assert_eq!(output(&echo), b"xyzb\x1b[1D");
+        assert_eq!(editor.chars(), ['a', 'x', 'y', 'z', 'b']);
+    }
+
+    #[test]
+    fn clear_drops_stashed_line() {
+        struct One;
+        impl Recall for One {
+            fn first_number(&self) -> u32 {
+                1
+            }
+            fn len(&self) -> usize {
+                1
+            }
+            fn entry(&self, _index: usize) -> Option<&str> {
+                Some("help")
+            }
+        }
+        let mut editor: LineEditor = LineEditor::new();
+        let mut echo = Echo::new();
+        editor.insert_str("senha", &mut echo);
+        editor.handle(Key::Up, &mut echo, &One);
+        assert_eq!(editor.handle(Key::Interrupt, &mut echo, &One), Some(Event::Interrupt));
+        assert!(editor.stash.is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+// Arquivos em RAM recebidos/enviados por `rx`/`sx` (scripts, tabelas de
+// calibração, capturas): poucos arquivos de tamanho fixo, perdidos no reset
+
+use core::cell::RefCell;
+use core::fmt;
+use heapless::Vec;
+
+use super::xmodem::Sink;
+
+// Limites do armazenamento
+pub const MAX_FILES: usize = 4;      // Arquivos ao mesmo tempo
+pub const FILE_BYTES: usize = 2048;  // Tamanho máximo de cada arquivo
+pub const FILE_NAME_LEN: usize = 16; // Bytes do nome
+
+// Nome de arquivo (copiável, para caber nas configurações da sessão)
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FileName {
+    bytes: [u8; FILE_NAME_LEN],
+    len: u8,
+}
+
+impl FileName {
+    // Letras, números, '.', '_' e '-' (None se inválido ou longo demais)
+    pub fn new(name: &str) -> Option<Self> {
+        let valid = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-');
+        if name.is_empty() || name.len() > FILE_NAME_LEN || !name.bytes().all(valid) {
+            return None;
+        }
+        let mut bytes = [0u8; FILE_NAME_LEN];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        Some(Self { bytes, len: name.len() as u8 })
+    }
+
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for FileName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Falhas do armazenamento
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileError {
+    Full,     // Arquivo maior que FILE_BYTES
+    TooMany,  // MAX_FILES arquivos já existem
+    BadName,  // Nome inválido
+    NotFound, // Arquivo inexistente
+    Flash,    // Gravação na flash falhou (slot de firmware)
+}
+
+struct File {
+    name: FileName,
+    data: Vec<u8, FILE_BYTES>,
+}
+
+// Tabela de arquivos (compartilhada entre as sessões)
+pub struct Files {
+    table: RefCell<[Option<File>; MAX_FILES]>,
+}
+
+impl Default for Files {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Files {
+    pub const fn new() -> Self {
+        Self { table: RefCell::new([const { None }; MAX_FILES]) }
+    }
+
+    // Cria um arquivo vazio (substitui o de mesmo nome)
+    pub fn create(&self, name: FileName) -> Result<(), FileError> {
+        let mut table = self.table.borrow_mut();
+        let slot = match table.iter().position(|f| f.as_ref().is_some_and(|f| f.name == name)) {
+            Some(index) => index,
+            None => table.iter().position(Option::is_none).ok_or(FileError::TooMany)?,
+        };
+        table[slot] = Some(File { name, data: Vec::new() });
+        Ok(())
+    }
+
+    // Acrescenta dados no fim
+    pub fn append(&self, name: &FileName, data: &[u8]) -> Result<(), FileError> {
+        self.with_file(name, |file| file.data.extend_from_slice(data).map_err(|_| FileError::Full))
+    }
+
+    // Encurta o arquivo para `len` bytes
+    pub fn truncate(&self, name: &FileName, len: usize) -> Result<(), FileError> {
+        self.with_file(name, |file| {
+            file.data.truncate(len);
+            Ok(())
+        })
+    }
+
+    pub fn remove(&self, name: &FileName) -> Result<(), FileError> {
+        let mut table = self.table.borrow_mut();
+        let slot = table.iter_mut().find(|f| f.as_ref().is_some_and(|f| f.name == *name));
+        slot.ok_or(FileError::NotFound)?.take();
+        Ok(())
+    }
+
+    // Tamanho do arquivo (None se não existe)
+    pub fn len(&self, name: &FileName) -> Option<usize> {
+        self.with_file(name, |file| Ok(file.data.len())).ok()
+    }
+
+    // Copia até `buf.len()` bytes a partir de `offset`; retorna quantos
+    pub fn read(&self, name: &FileName, offset: usize, buf: &mut [u8]) -> Result<usize, FileError> {
+        self.with_file(name, |file| {
+            let data = file.data.get(offset..).unwrap_or_default();
+            let n = data.len().min(buf.len());
+            buf[..n].copy_from_slice(&data[..n]);
+            Ok(n)
+        })
+    }
+
+    // Nome e tamanho do arquivo na posição `index` da tabela (para `files`)
+    pub fn entry(&self, index: usize) -> Option<(FileName, usize)> {
+        let table = self.table.borrow();
+        table.get(index)?.as_ref().map(|file| (file.name, file.data.len()))
+    }
+
+    fn with_file<T>(&self, name: &FileName, f: impl FnOnce(&mut File) -> Result<T, FileError>) -> Result<T, FileError> {
+        let mut table = self.table.borrow_mut();
+        let file = table.iter_mut().flatten().find(|file| file.name == *name).ok_or(FileError::NotFound)?;
+        f(file)
+    }
+}
+
+// Recepção (`rx`) para a tabela de arquivos
+pub struct FileSink<'a> {
+    files: &'a Files,
+    current: Option<FileName>, // Arquivo em recepção
+    len: usize,
+}
+
+impl<'a> FileSink<'a> {
+    pub fn new(files: &'a Files) -> Self {
+        Self { files, current: None, len: 0 }
+    }
+
+    fn current(&self) -> Result<FileName, FileError> {
+        self.current.ok_or(FileError::NotFound)
+    }
+}
+
+// Preenchimento do último bloco XMODEM
+const SUB: u8 = 0x1A;
+
+impl Sink for FileSink<'_> {
+    fn create(&mut self, name: &str, size: Option<usize>) -> Result<(), FileError> {
+        let name = FileName::new(name).ok_or(FileError::BadName)?;
+        if size.is_some_and(|size| size > FILE_BYTES) {
+            return Err(FileError::Full);
+        }
+        self.files.create(name)?;
+        self.current = Some(name);
+        self.len = 0;
+        Ok(())
+    }
+
+    fn append(&mut self, data: &[u8]) -> Result<(), FileError> {
+        self.files.append(&self.current()?, data)?;
+        self.len += data.len();
+        Ok(())
+    }
+
+    // Sem tamanho anunciado (XMODEM) descarta os SUB do fim do último bloco
+    fn finish(&mut self, padded: bool) -> Result<usize, FileError> {
+        let name = self.current.take().ok_or(FileError::NotFound)?;
+        if !padded {
+            return Ok(self.len);
+        }
+        let mut tail = [0u8; 128];
+        let start = self.len.saturating_sub(tail.len());
+        let n = self.files.read(&name, start, &mut tail)?;
+        let kept = start + tail[..n].iter().rposition(|&b| b != SUB).map_or(0, |i| i + 1);
+        self.files.truncate(&name, kept)?;
+        Ok(kept)
+    }
+
+    fn discard(&mut self) {
+        if let Some(name) = self.current.take() {
+            let _ = self.files.remove(&name);
+        }
+    }
+}
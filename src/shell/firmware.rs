@@ -0,0 +1,63 @@
+// Recepção do `fw update` direto no slot DFU da flash (ver `boot`): a imagem
+// não cabe na RAM, então cada bloco do YMODEM é gravado ao chegar
+
+use crate::boot::{BootFlash, Region, DESCRIPTOR_LEN, WORD};
+
+use super::files::FileError;
+use super::xmodem::Sink;
+
+pub struct DfuSink<'a> {
+    flash: &'a dyn BootFlash,
+    len: usize, // Bytes gravados
+}
+
+impl<'a> DfuSink<'a> {
+    pub fn new(flash: &'a dyn BootFlash) -> Self {
+        Self { flash, len: 0 }
+    }
+
+    // Espaço para imagem + descritor (o fim do slot guarda a cópia do descritor)
+    fn capacity(&self) -> usize {
+        self.flash.region_size(Region::Dfu) - DESCRIPTOR_LEN
+    }
+}
+
+impl Sink for DfuSink<'_> {
+    // O nome do arquivo não importa; apaga o slot (bloqueia por ~1 s)
+    fn create(&mut self, _name: &str, size: Option<usize>) -> Result<(), FileError> {
+        if size.is_some_and(|size| size > self.capacity()) {
+            return Err(FileError::Full);
+        }
+        if !self.flash.erase(Region::Dfu) {
+            return Err(FileError::Flash);
+        }
+        self.len = 0;
+        Ok(())
+    }
+
+    // Os blocos chegam com 128 ou 1024 bytes; só o último pode ter um tamanho
+    // qualquer e é completado com 0xFF até a palavra
+    fn append(&mut self, data: &[u8]) -> Result<(), FileError> {
+        if self.len + data.len() > self.capacity() || !self.len.is_multiple_of(WORD) {
+            return Err(FileError::Full);
+        }
+        let whole = data.len() - data.len() % WORD;
+        let mut tail = [0xFF; WORD];
+        tail[..data.len() - whole].copy_from_slice(&data[whole..]);
+        let ok = self.flash.write(Region::Dfu, self.len, &data[..whole])
+            && (whole == data.len() || self.flash.write(Region::Dfu, self.len + whole, &tail));
+        if !ok {
+            return Err(FileError::Flash);
+        }
+        self.len += data.len();
+        Ok(())
+    }
+
+    // A imagem vem com o tamanho no cabeçalho YMODEM (sem SUB a descartar)
+    fn finish(&mut self, _padded: bool) -> Result<usize, FileError> {
+        Ok(self.len)
+    }
+
+    // Uma imagem incompleta não tem descritor válido: nada a apagar
+    fn discard(&mut self) {}
+}
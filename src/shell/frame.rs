@@ -0,0 +1,269 @@
+// Enquadramento binário: COBS + CRC-16 (CCITT-FALSE) com 0x00 como delimitador
+// Quadro no fio: COBS(payload + CRC16 little-endian) 0x00
+
+// Maior payload aceito (bytes, sem CRC)
+pub const MAX_PAYLOAD: usize = 64;
+
+// Maior quadro codificado: payload + CRC + overhead do COBS (1 a cada 254) + delimitador
+pub const MAX_FRAME: usize = MAX_PAYLOAD + 2 + (MAX_PAYLOAD + 2) / 254 + 1 + 1;
+
+// Erros de um quadro recebido
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    TooLong, // Passou de MAX_FRAME antes do delimitador
+    Cobs,    // Codificação COBS inválida
+    Short,   // Menor que o CRC
+    Crc,     // CRC não confere
+}
+
+// CRC-16/CCITT-FALSE (polinômio 0x1021, valor inicial 0xFFFF)
+pub fn crc16(data: &[u8]) -> u16 {
+    crc16_from(0xFFFF, data)
+}
+
+// Mesmo polinômio com outro valor inicial (0 = CRC-16/XMODEM)
+pub fn crc16_from(init: u16, data: &[u8]) -> u16 {
+    let mut crc = init;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+// Codifica `src` em COBS (sem o delimitador); retorna o tamanho ou None se `dst` não couber
+pub fn cobs_encode(src: &[u8], dst: &mut [u8]) -> Option<usize> {
+    let mut code_at = 0; // Posição do byte de código do bloco atual
+    let mut len = 1;
+    let mut code = 1u8;
+    for &byte in src {
+        if byte != 0 {
+            *dst.get_mut(len)? = byte;
+            len += 1;
+            code += 1;
+        }
+        if byte == 0 || code == 0xFF {
+            *dst.get_mut(code_at)? = code;
+            code_at = len;
+            len += 1;
+            code = 1;
+        }
+    }
+    *dst.get_mut(code_at)? = code;
+    Some(len)
+}
+
+// Decodifica um bloco COBS (sem o delimitador) em `dst`
+pub fn cobs_decode(src: &[u8], dst: &mut [u8]) -> Result<usize, FrameError> {
+    let mut read = 0;
+    let mut len = 0;
+    while read < src.len() {
+        let code = src[read] as usize;
+        if code == 0 || read + code > src.len() {
+            return Err(FrameError::Cobs);
+        }
+        read += 1;
+        for &byte in &src[read..read + code - 1] {
+            if byte == 0 {
+                return Err(FrameError::Cobs);
+            }
+            *dst.get_mut(len).ok_or(FrameError::TooLong)? = byte;
+            len += 1;
+        }
+        read += code - 1;
+        // O zero implícito não existe após um bloco cheio nem no fim
+        if code < 0xFF && read < src.len() {
+            *dst.get_mut(len).ok_or(FrameError::TooLong)? = 0;
+            len += 1;
+        }
+    }
+    Ok(len)
+}
+
+// Monta o quadro completo (com CRC e delimitador) em `dst`
+pub fn encode_frame(payload: &[u8], dst: &mut [u8]) -> Option<usize> {
+    if payload.len() > MAX_PAYLOAD {
+        return None;
+    }
+    let mut raw = [0u8; MAX_PAYLOAD + 2];
+    raw[..payload.len()].copy_from_slice(payload);
+    raw[payload.len()..payload.len() + 2].copy_from_slice(&crc16(payload).to_le_bytes());
+    let len = cobs_encode(&raw[..payload.len() + 2], dst)?;
+    *dst.get_mut(len)? = 0;
+    Some(len + 1)
+}
+
+// Remonta quadros a partir dos bytes recebidos
+pub struct FrameDecoder {
+    raw: [u8; MAX_FRAME],           // Bytes codificados até o delimitador
+    len: usize,
+    overflow: bool,                 // Descarta até o próximo delimitador
+    payload: [u8; MAX_PAYLOAD + 2], // Último quadro decodificado (com CRC)
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameDecoder {
+    pub const fn new() -> Self {
+        Self { raw: [0; MAX_FRAME], len: 0, overflow: false, payload: [0; MAX_PAYLOAD + 2] }
+    }
+
+    // Processa um byte; no delimitador retorna o payload validado (delimitadores
+    // repetidos, usados para sincronizar, são ignorados). Um quadro longo demais
+    // é reportado assim que estoura e o resto é descartado até o delimitador
+    pub fn feed(&mut self, byte: u8) -> Option<Result<&[u8], FrameError>> {
+        if byte != 0 {
+            if self.len < self.raw.len() {
+                self.raw[self.len] = byte;
+                self.len += 1;
+            } else if !self.overflow {
+                self.overflow = true;
+                return Some(Err(FrameError::TooLong));
+            }
+            return None;
+        }
+
+        let len = core::mem::take(&mut self.len);
+        if core::mem::take(&mut self.overflow) || len == 0 {
+            return None;
+        }
+        Some(self.decode(len))
+    }
+
+    fn decode(&mut self, len: usize) -> Result<&[u8], FrameError> {
+        let n = cobs_decode(&self.raw[..len], &mut self.payload)?;
+        if n < 2 {
+            return Err(FrameError::Short);
+        }
+        let (data, crc) = self.payload[..n].split_at(n - 2);
+        if crc16(data).to_le_bytes() != [crc[0], crc[1]] {
+            return Err(FrameError::Crc);
+        }
+        Ok(data)
+    }
+
+    // Descarta o quadro parcial
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.overflow = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(payload: &[u8]) -> ([u8; MAX_FRAME], usize) {
+        let mut frame = [0u8; MAX_FRAME];
+        let len = encode_frame(payload, &mut frame).unwrap();
+        (frame, len)
+    }
+
+    // Entrega os bytes e retorna a última resposta do decodificador
+    fn feed(decoder: &mut FrameDecoder, bytes: &[u8]) -> Option<Result<std::vec::Vec<u8>, FrameError>> {
+        let mut last = None;
+        for &byte in bytes {
+            if let Some(result) = decoder.feed(byte) {
+                last = Some(result.map(<[u8]>::to_vec));
+            }
+        }
+        last
+    }
+
+    // Quadro COBS com `raw` já contendo payload e CRC
+    fn raw_frame(raw: &[u8]) -> std::vec::Vec<u8> {
+        let mut frame = [0u8; 2 * MAX_FRAME];
+        let len = cobs_encode(raw, &mut frame).unwrap();
+        let mut frame = frame[..len].to_vec();
+        frame.push(0);
+        frame
+    }
+
+    #[test]
+    fn crc_check_values() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+        assert_eq!(crc16_from(0, b"123456789"), 0x31C3); // XMODEM
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut zeros_every_other = [0u8; MAX_PAYLOAD];
+        zeros_every_other.iter_mut().step_by(2).for_each(|b| *b = 0x5A);
+        let max: std::vec::Vec<u8> = (1..=MAX_PAYLOAD as u8).collect();
+        let payloads: [&[u8]; 6] = [&[], &[0], &[0; MAX_PAYLOAD], &zeros_every_other, &max, b"led on"];
+        let mut decoder = FrameDecoder::new();
+        for payload in payloads {
+            let (frame, len) = encode(payload);
+            assert_eq!(frame[len - 1], 0);
+            assert!(!frame[..len - 1].contains(&0), "delimitador dentro do quadro");
+            assert_eq!(feed(&mut decoder, &frame[..len]), Some(Ok(payload.to_vec())));
+        }
+    }
+
+    #[test]
+    fn repeated_delimiters_are_ignored() {
+        let (frame, len) = encode(b"x");
+        let mut decoder = FrameDecoder::new();
+        assert_eq!(feed(&mut decoder, &[0, 0]), None);
+        assert_eq!(feed(&mut decoder, &frame[..len]), Some(Ok(b"x".to_vec())));
+        assert_eq!(feed(&mut decoder, &[0]), None);
+    }
+
+    #[test]
+    fn rejects_flipped_bits() {
+        let payload = b"\x01\x00adc 10\x00";
+        let crc = crc16(payload).to_le_bytes();
+        let mut decoder = FrameDecoder::new();
+        for bit in 0..(payload.len() + 2) * 8 {
+            let mut raw = payload.to_vec();
+            raw.extend_from_slice(&crc);
+            raw[bit / 8] ^= 1 << (bit % 8);
+            assert_eq!(feed(&mut decoder, &raw_frame(&raw)), Some(Err(FrameError::Crc)), "bit {bit}");
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_frames() {
+        let mut decoder = FrameDecoder::new();
+        let (frame, len) = encode(b"\x00status\x00\x00");
+        for cut in 1..len - 1 {
+            let mut truncated = frame[..len - 1 - cut].to_vec();
+            truncated.push(0);
+            let result = feed(&mut decoder, &truncated);
+            assert!(matches!(result, Some(Err(_))), "corte {cut}: {result:?}");
+        }
+        // Só um byte de dados: menor que o CRC
+        assert_eq!(feed(&mut decoder, &raw_frame(&[0x42])), Some(Err(FrameError::Short)));
+        // O decodificador segue sincronizado
+        assert_eq!(feed(&mut decoder, &frame[..len]), Some(Ok(b"\x00status\x00\x00".to_vec())));
+    }
+
+    #[test]
+    fn rejects_oversize_frames() {
+        let mut dst = [0u8; MAX_FRAME];
+        assert_eq!(encode_frame(&[1; MAX_PAYLOAD + 1], &mut dst), None);
+        assert_eq!(encode_frame(b"abc", &mut dst[..4]), None);
+
+        let mut decoder = FrameDecoder::new();
+        // Estoura o buffer: erro uma vez e o resto é descartado até o delimitador
+        let long = [0x11u8; MAX_FRAME + 10];
+        let errors: std::vec::Vec<_> = long.iter().filter_map(|&b| decoder.feed(b).map(|r| r.err())).collect();
+        assert_eq!(errors, [Some(FrameError::TooLong)]);
+        assert_eq!(decoder.feed(0), None);
+
+        // Cabe no buffer mas decodifica em mais que MAX_PAYLOAD + CRC
+        let mut raw = [0x22u8; MAX_PAYLOAD + 3];
+        let crc = crc16(&raw[..MAX_PAYLOAD + 1]).to_le_bytes();
+        raw[MAX_PAYLOAD + 1..].copy_from_slice(&crc);
+        assert_eq!(feed(&mut decoder, &raw_frame(&raw)), Some(Err(FrameError::TooLong)));
+
+        let (frame, len) = encode(b"ok");
+        assert_eq!(feed(&mut decoder, &frame[..len]), Some(Ok(b"ok".to_vec())));
+    }
+}
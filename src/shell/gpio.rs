@@ -0,0 +1,246 @@
+// Pinos para o `gpio`: nomes (PA0..PI15), modos e o registro de quem usa cada
+// pino. A aplicação registra os pinos dos periféricos na partida; o shell só
+// configura pinos livres (e só escreve nos que ele mesmo configurou)
+
+use core::cell::RefCell;
+use core::fmt;
+use heapless::Vec;
+
+// Portas do STM32F407 (A a I)
+pub const PORTS: u8 = 9;
+
+// Pinos registrados ao mesmo tempo (periféricos + configurados pelo shell)
+pub const MAX_PINS: usize = 32;
+
+// Pino: porta (0 = A) e número (0 a 15)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PinId {
+    pub port: u8,
+    pub number: u8,
+}
+
+impl PinId {
+    pub const fn new(port: u8, number: u8) -> Self {
+        Self { port, number }
+    }
+
+    // "PA5", "pd12" (None se a porta ou o número não existem; só dígitos, sem
+    // sinal nem zero à esquerda). Os pinos que o encapsulamento não tem são
+    // recusados pela placa (ver `Board::has_pin`)
+    pub fn parse(text: &str) -> Option<Self> {
+        let [p, port, digits @ ..] = text.as_bytes() else {
+            return None;
+        };
+        let plain = match digits {
+            [d] => d.is_ascii_digit(),
+            [b'1'..=b'9', d] => d.is_ascii_digit(),
+            _ => false,
+        };
+        if !p.eq_ignore_ascii_case(&b'P') || !plain {
+            return None;
+        }
+        let port = port.to_ascii_uppercase().checked_sub(b'A').filter(|&p| p < PORTS)?;
+        let number = digits.iter().fold(0, |n, d| n * 10 + (d - b'0'));
+        (number < 16).then_some(Self { port, number })
+    }
+}
+
+impl fmt::Display for PinId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "P{}{}", (b'A' + self.port) as char, self.number)
+    }
+}
+
+// Modo de um pino configurado pelo shell
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinMode {
+    Input,
+    Output,    // Push-pull
+    OpenDrain,
+}
+
+// Nomes aceitos pelo `gpio mode` (mesma ordem de `PinMode::ALL`)
+pub const PIN_MODES: &[&str] = &["in", "out", "od"];
+
+impl PinMode {
+    pub const ALL: [PinMode; 3] = [PinMode::Input, PinMode::Output, PinMode::OpenDrain];
+
+    pub fn name(self) -> &'static str {
+        PIN_MODES[self as usize]
+    }
+}
+
+// Resistor interno
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pull {
+    None,
+    Up,
+    Down,
+}
+
+// Nomes aceitos pelo `gpio mode` (mesma ordem de `Pull::ALL`)
+pub const PULLS: &[&str] = &["none", "up", "down"];
+
+impl Pull {
+    pub const ALL: [Pull; 3] = [Pull::None, Pull::Up, Pull::Down];
+
+    pub fn name(self) -> &'static str {
+        PULLS[self as usize]
+    }
+}
+
+// Quem usa o pino
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Owner {
+    Peripheral(&'static str), // Registrado pela aplicação (ex: "usart1")
+    Shell(PinMode, Pull),     // Configurado pelo `gpio`
+}
+
+// Falhas do registro
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinError {
+    InUse,         // Pino de um periférico
+    NotConfigured, // `write`/`free` num pino que o shell não configurou
+    NotOutput,     // `write` num pino de entrada
+    Full,          // MAX_PINS pinos já registrados
+}
+
+// Registro de pinos (compartilhado entre as sessões)
+pub struct Pins {
+    table: RefCell<Vec<(PinId, Owner), MAX_PINS>>,
+}
+
+impl Default for Pins {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pins {
+    pub const fn new() -> Self {
+        Self { table: RefCell::new(Vec::new()) }
+    }
+
+    // Pino usado por um periférico da aplicação (na partida)
+    pub fn claim(&self, pin: PinId, owner: &'static str) -> Result<(), PinError> {
+        if self.owner(pin).is_some() {
+            return Err(PinError::InUse);
+        }
+        self.table.borrow_mut().push((pin, Owner::Peripheral(owner))).map_err(|_| PinError::Full)
+    }
+
+    pub fn owner(&self, pin: PinId) -> Option<Owner> {
+        self.table.borrow().iter().find(|(p, _)| *p == pin).map(|&(_, owner)| owner)
+    }
+
+    // Registra (ou muda) a configuração de um pino livre
+    pub fn configure(&self, pin: PinId, mode: PinMode, pull: Pull) -> Result<(), PinError> {
+        let mut table = self.table.borrow_mut();
+        match table.iter_mut().find(|(p, _)| *p == pin) {
+            Some((_, Owner::Peripheral(_))) => Err(PinError::InUse),
+            Some((_, owner)) => {
+                *owner = Owner::Shell(mode, pull);
+                Ok(())
+            },
+            None => table.push((pin, Owner::Shell(mode, pull))).map_err(|_| PinError::Full),
+        }
+    }
+
+    // Pino configurado pelo shell como saída (para o `write`)
+    pub fn check_output(&self, pin: PinId) -> Result<(), PinError> {
+        match self.owner(pin) {
+            Some(Owner::Peripheral(_)) => Err(PinError::InUse),
+            Some(Owner::Shell(PinMode::Input, _)) => Err(PinError::NotOutput),
+            Some(Owner::Shell(..)) => Ok(()),
+            None => Err(PinError::NotConfigured),
+        }
+    }
+
+    // Devolve um pino configurado pelo shell
+    pub fn release(&self, pin: PinId) -> Result<(), PinError> {
+        let mut table = self.table.borrow_mut();
+        match table.iter().position(|(p, _)| *p == pin) {
+            Some(index) if matches!(table[index].1, Owner::Shell(..)) => {
+                table.swap_remove(index);
+                Ok(())
+            },
+            Some(_) => Err(PinError::InUse),
+            None => Err(PinError::NotConfigured),
+        }
+    }
+
+    // Entrada `index` da tabela, em ordem de porta e número (para a listagem)
+    pub fn entry(&self, index: usize) -> Option<(PinId, Owner)> {
+        let mut entries = self.table.borrow().clone();
+        entries.sort_unstable_by_key(|(pin, _)| (pin.port, pin.number));
+        entries.get(index).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pin_names() {
+        assert_eq!(PinId::parse("PA5"), Some(PinId::new(0, 5)));
+        assert_eq!(PinId::parse("pd12"), Some(PinId::new(3, 12)));
+        assert_eq!(PinId::parse("PI15"), Some(PinId::new(8, 15)));
+        assert_eq!(PinId::parse("PB0"), Some(PinId::new(1, 0)));
+        for bad in ["", "P", "PA", "PJ0", "PA16", "PA+5", "PA05", "PA-1", "PA 5", "XA5", "PA5x", "PA١", "PA123"] {
+            assert_eq!(PinId::parse(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let mut text = heapless::String::<8>::new();
+        core::fmt::write(&mut text, format_args!("{}", PinId::new(2, 13))).unwrap();
+        assert_eq!(text, "PC13");
+        assert_eq!(PinId::parse(&text), Some(PinId::new(2, 13)));
+    }
+
+    #[test]
+    fn peripheral_pins_are_not_configurable() {
+        let pins = Pins::new();
+        let tx = PinId::new(0, 9);
+        pins.claim(tx, "usart1").unwrap();
+        assert_eq!(pins.claim(tx, "spi1"), Err(PinError::InUse));
+        assert_eq!(pins.configure(tx, PinMode::Output, Pull::None), Err(PinError::InUse));
+        assert_eq!(pins.check_output(tx), Err(PinError::InUse));
+        assert_eq!(pins.release(tx), Err(PinError::InUse));
+        assert_eq!(pins.owner(tx), Some(Owner::Peripheral("usart1")));
+    }
+
+    #[test]
+    fn configure_and_release() {
+        let pins = Pins::new();
+        let pin = PinId::new(4, 3);
+        assert_eq!(pins.check_output(pin), Err(PinError::NotConfigured));
+        assert_eq!(pins.release(pin), Err(PinError::NotConfigured));
+
+        pins.configure(pin, PinMode::Input, Pull::Up).unwrap();
+        assert_eq!(pins.check_output(pin), Err(PinError::NotOutput));
+        // Reconfigurar troca o modo sem ocupar outra entrada
+        pins.configure(pin, PinMode::OpenDrain, Pull::None).unwrap();
+        assert_eq!(pins.check_output(pin), Ok(()));
+        assert_eq!(pins.entry(1), None);
+
+        pins.release(pin).unwrap();
+        assert_eq!(pins.owner(pin), None);
+        // Liberado, o pino pode ir para um periférico
+        pins.claim(pin, "spi1").unwrap();
+    }
+
+    #[test]
+    fn table_is_bounded_and_listed_in_order() {
+        let pins = Pins::new();
+        for i in 0..MAX_PINS as u8 {
+            pins.configure(PinId::new(4 - i / 16, i % 16), PinMode::Input, Pull::None).unwrap();
+        }
+        assert_eq!(pins.claim(PinId::new(0, 0), "usart1"), Err(PinError::Full));
+        assert_eq!(pins.configure(PinId::new(0, 0), PinMode::Input, Pull::None), Err(PinError::Full));
+        assert_eq!(pins.entry(0).map(|(pin, _)| pin), Some(PinId::new(3, 0)));
+        assert_eq!(pins.entry(MAX_PINS - 1).map(|(pin, _)| pin), Some(PinId::new(4, 15)));
+    }
+}
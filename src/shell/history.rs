@@ -0,0 +1,242 @@
+// Histórico de comandos: anel de capacidade fixa com numeração contínua
+// (navegação com setas, `history`, `!n`/`!!` e persistência opcional)
+
+use heapless::Deque;
+
+use super::editor::{Line, LINE_LEN};
+
+// Capacidade padrão do histórico (linhas)
+pub const HISTORY_LEN: usize = 16;
+
+// Cabeçalho da cópia persistente: "HIST" + tamanho (u16) + soma de verificação (u16)
+const MAGIC: [u8; 4] = *b"HIST";
+const HEADER_LEN: usize = 8;
+
+// Acesso somente leitura ao histórico (usado pelo comando `history`)
+pub trait Recall {
+    // Número da primeira entrada guardada
+    fn first_number(&self) -> u32;
+    // Quantidade de entradas guardadas
+    fn len(&self) -> usize;
+    // Entrada pela posição (0 = mais antiga)
+    fn entry(&self, index: usize) -> Option<&str>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Histórico vazio (jobs e digitação de senhas)
+pub struct NoHistory;
+
+impl Recall for NoHistory {
+    fn first_number(&self) -> u32 {
+        1
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+
+    fn entry(&self, _index: usize) -> Option<&str> {
+        None
+    }
+}
+
+// Anel de histórico com `N` linhas de até `L` bytes
+pub struct History<const N: usize, const L: usize = LINE_LEN> {
+    entries: Deque<Line<L>, N>, // Linhas mais antigas na frente
+    next: u32,                  // Número da próxima linha registrada
+}
+
+impl<const N: usize, const L: usize> Default for History<N, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const L: usize> History<N, L> {
+    pub const fn new() -> Self {
+        Self { entries: Deque::new(), next: 1 }
+    }
+
+    // Registra uma linha (ignora linhas vazias e repetição da última)
+    pub fn push(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.entries.back().map(|l| l.as_str()) == Some(line) {
+            return;
+        }
+        let mut entry = Line::new();
+        if entry.push_str(line).is_err() {
+            return;
+        }
+        if self.entries.is_full() {
+            self.entries.pop_front();
+        }
+        let _ = self.entries.push_back(entry);
+        self.next = self.next.wrapping_add(1);
+    }
+
+    // Linha pelo número exibido em `history`
+    pub fn by_number(&self, number: u32) -> Option<&str> {
+        let index = number.checked_sub(self.first_number())? as usize;
+        self.entry(index)
+    }
+
+    // Última linha registrada (`!!`)
+    pub fn last(&self) -> Option<&str> {
+        self.entries.back().map(|l| l.as_str())
+    }
+
+    // Expande `!!` e `!n`; retorna None se a linha não for uma referência
+    pub fn expand(&self, line: &str) -> Option<Result<&str, ()>> {
+        let reference = line.trim().strip_prefix('!')?;
+        Some(match reference {
+            "!" => self.last().ok_or(()),
+            n => n.parse().ok().and_then(|n| self.by_number(n)).ok_or(()),
+        })
+    }
+
+    // Grava as linhas em `buf` (formato com cabeçalho e soma de verificação)
+    pub fn save(&self, buf: &mut [u8]) {
+        if buf.len() < HEADER_LEN {
+            return;
+        }
+        let (header, data) = buf.split_at_mut(HEADER_LEN);
+        let mut len = 0;
+        // Guarda as linhas mais recentes que couberem
+        let skip = self
+            .entries
+            .iter()
+            .rev()
+            .scan(0, |total, l| {
+                *total += l.len() + 1;
+                Some(*total)
+            })
+            .filter(|total| *total > data.len())
+            .count();
+        for line in self.entries.iter().skip(skip) {
+            data[len..len + line.len()].copy_from_slice(line.as_bytes());
+            data[len + line.len()] = b'\n';
+            len += line.len() + 1;
+        }
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..6].copy_from_slice(&(len as u16).to_le_bytes());
+        header[6..8].copy_from_slice(&checksum(&data[..len]).to_le_bytes());
+    }
+
+    // Restaura as linhas gravadas por `save` (ignora conteúdo inválido)
+    pub fn load(&mut self, buf: &[u8]) {
+        if buf.len() < HEADER_LEN || buf[..4] != MAGIC {
+            return;
+        }
+        let len = u16::from_le_bytes([buf[4], buf[5]]) as usize;
+        let sum = u16::from_le_bytes([buf[6], buf[7]]);
+        let Some(data) = buf.get(HEADER_LEN..HEADER_LEN + len) else {
+            return;
+        };
+        if checksum(data) != sum {
+            return;
+        }
+        let Ok(text) = core::str::from_utf8(data) else {
+            return;
+        };
+        for line in text.lines() {
+            self.push(line);
+        }
+    }
+}
+
+impl<const N: usize, const L: usize> Recall for History<N, L> {
+    fn first_number(&self) -> u32 {
+        self.next.wrapping_sub(self.entries.len() as u32)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn entry(&self, index: usize) -> Option<&str> {
+        self.entries.iter().nth(index).map(|l| l.as_str())
+    }
+}
+
+// Soma de verificação Fletcher-16
+fn checksum(data: &[u8]) -> u16 {
+    let (mut a, mut b) = (0u16, 0u16);
+    for &byte in data {
+        a = (a + byte as u16) % 255;
+        b = (b + a) % 255;
+    }
+    (b << 8) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines<const N: usize>(history: &History<N>) -> std::vec::Vec<&str> {
+        (0..history.len()).filter_map(|i| history.entry(i)).collect()
+    }
+
+    #[test]
+    fn ring_keeps_numbering() {
+        let mut history: History<3> = History::new();
+        for line in ["a", "b", "  ", "b", "c", " d "] {
+            history.push(line);
+        }
+        // Vazias e repetições seguidas não entram; a mais antiga sai
+        assert_eq!(lines(&history), ["b", "c", "d"]);
+        assert_eq!(history.first_number(), 2);
+        assert_eq!(history.by_number(1), None);
+        assert_eq!(history.by_number(3), Some("c"));
+        assert_eq!(history.by_number(5), None);
+    }
+
+    #[test]
+    fn bang_references() {
+        let mut history: History<4> = History::new();
+        assert_eq!(history.expand("!!"), Some(Err(())));
+        history.push("led on");
+        history.push("status");
+        assert_eq!(history.expand("!!"), Some(Ok("status")));
+        assert_eq!(history.expand(" !1 "), Some(Ok("led on")));
+        assert_eq!(history.expand("!9"), Some(Err(())));
+        assert_eq!(history.expand("!x"), Some(Err(())));
+        assert_eq!(history.expand("led !1"), None);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut history: History<4> = History::new();
+        for line in ["led on", "status", "adc"] {
+            history.push(line);
+        }
+        let mut buf = [0u8; 64];
+        history.save(&mut buf);
+        let mut restored: History<4> = History::new();
+        restored.load(&buf);
+        assert_eq!(lines(&restored), ["led on", "status", "adc"]);
+
+        // Área pequena: só as mais recentes que couberem
+        let mut small = [0u8; HEADER_LEN + 12];
+        history.save(&mut small);
+        let mut restored: History<4> = History::new();
+        restored.load(&small);
+        assert_eq!(lines(&restored), ["status", "adc"]);
+    }
+
+    #[test]
+    fn load_rejects_damaged_copies() {
+        let mut history: History<4> = History::new();
+        history.push("led on");
+        let mut buf = [0u8; 32];
+        history.save(&mut buf);
+        buf[HEADER_LEN] ^= 0x20;
+        let mut restored: History<4> = History::new();
+        restored.load(&buf);
+        restored.load(&[0xFF; 32]);
+        restored.load(&buf[..4]);
+        assert!(restored.is_empty());
+    }
+}
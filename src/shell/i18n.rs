@@ -0,0 +1,440 @@
+// Catálogo de mensagens do shell (idioma escolhido em tempo de execução)
+// Cada mensagem é declarada uma única vez na macro `messages!` com o texto em
+// todos os idiomas: faltar um idioma em qualquer entrada não compila
+
+// Idiomas disponíveis
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lang {
+    Pt,
+    En,
+}
+
+// Nomes aceitos pelo comando `lang` (mesma ordem do enum)
+pub const LANGS: &[&str] = &["pt", "en"];
+
+// Chave do idioma na configuração persistente (gravada pelo `lang`)
+pub const LANG_KEY: &str = "lang";
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::Pt, Lang::En];
+
+    pub fn name(self) -> &'static str {
+        LANGS[self as usize]
+    }
+
+    // Código gravado na configuração persistente
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_code(code: u8) -> Option<Lang> {
+        Lang::ALL.get(code as usize).copied()
+    }
+}
+
+macro_rules! messages {
+    ($($id:ident => { pt: $pt:literal, en: $en:literal $(,)? }),* $(,)?) => {
+        // Identificador de cada mensagem do catálogo
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Msg {
+            $($id),*
+        }
+
+        impl Msg {
+            // Texto da mensagem no idioma pedido
+            pub fn text(self, lang: Lang) -> &'static str {
+                match lang {
+                    Lang::Pt => match self {
+                        $(Msg::$id => $pt),*
+                    },
+                    Lang::En => match self {
+                        $(Msg::$id => $en),*
+                    },
+                }
+            }
+        }
+    };
+}
+
+messages! {
+    // Shell
+    Banner => {
+        pt: "Digite 'help' para ver os comandos disponíveis.\n",
+        en: "Type 'help' to list the available commands.\n",
+    },
+    UnknownCommand => {
+        pt: "Comando não reconhecido. Digite 'help' para ajuda.\n",
+        en: "Unknown command. Type 'help' for help.\n",
+    },
+    Usage => { pt: "Uso: ", en: "Usage: " },
+    LineDiscarded => {
+        pt: "Linha muito longa: comando descartado\n",
+        en: "Line too long: command discarded\n",
+    },
+    LineTooLong => { pt: "Linha muito longa (máximo", en: "Line too long (max" },
+    EventNotFound => {
+        pt: "Evento não encontrado no histórico\n",
+        en: "Event not found in history\n",
+    },
+    TooManyJobs => {
+        pt: "Jobs demais em execução (use 'jobs' e 'kill')\n",
+        en: "Too many running jobs (use 'jobs' and 'kill')\n",
+    },
+    Interrupted => { pt: "Comando interrompido\n", en: "Command interrupted\n" },
+
+    // Erros de argumento
+    UnterminatedQuote => { pt: "aspas sem fechamento", en: "unterminated quote" },
+    BadEscape => { pt: "escape inválido", en: "invalid escape" },
+    TooManyTokens => { pt: "argumentos demais para o buffer", en: "too many arguments for the buffer" },
+    MissingArg => { pt: "falta o argumento", en: "missing argument" },
+    UnexpectedArg => { pt: "argumento inesperado", en: "unexpected argument" },
+    InvalidInt => { pt: "inteiro inválido (use decimal ou 0x..)", en: "invalid integer (use decimal or 0x..)" },
+    InvalidNumber => { pt: "número inválido", en: "invalid number" },
+    OutOfRange => { pt: "fora do intervalo", en: "out of range" },
+    BadUnit => { pt: "unidade inválida (esperado", en: "invalid unit (expected" },
+    Precision => { pt: "casas decimais demais", en: "too many decimal places" },
+    NotInChoices => { pt: "valor inválido (esperado", en: "invalid value (expected" },
+
+    // Resumos da ajuda
+    HelpSummary => { pt: "Mostra esta ajuda", en: "Show this help" },
+    LedSummary => { pt: "Controla o LED piscante", en: "Control the blinking LED" },
+    StatusSummary => { pt: "Mostra o estado do sistema", en: "Show the system status" },
+    HistorySummary => {
+        pt: "Lista os comandos anteriores (!n ou !! para repetir)",
+        en: "List previous commands (!n or !! to repeat)",
+    },
+    EchoSummary => { pt: "Liga/desliga o eco do que é digitado", en: "Turn typed character echo on/off" },
+    EolSummary => {
+        pt: "Fim de linha da entrada (in) e da saída (out)",
+        en: "Input (in) and output (out) line endings",
+    },
+    LangSummary => { pt: "Idioma das mensagens", en: "Message language" },
+    ModeSummary => { pt: "Formato das respostas (texto ou JSON Lines)", en: "Response format (text or JSON Lines)" },
+    AdcSummary => { pt: "Mostra leituras ADC (Ctrl-C para sair)", en: "Show ADC readings (Ctrl-C to quit)" },
+    JobsSummary => { pt: "Lista os jobs em segundo plano ('cmd &')", en: "List background jobs ('cmd &')" },
+    KillSummary => { pt: "Encerra um job em segundo plano", en: "Stop a background job" },
+    RepeatSummary => { pt: "Executa um comando n vezes", en: "Run a command n times" },
+    AliasSummary => { pt: "Define ou lista aliases (persistidos)", en: "Define or list aliases (persisted)" },
+    UnaliasSummary => { pt: "Remove um alias", en: "Remove an alias" },
+    AutoexecSummary => {
+        pt: "Script executado no boot (PA0 no reset pula)",
+        en: "Script run at boot (hold PA0 at reset to skip)",
+    },
+    LogoutSummary => { pt: "Encerra a sessão (volta à senha)", en: "End the session (back to the password)" },
+    PasswdSummary => {
+        pt: "Troca a senha de um nível (vazia remove)",
+        en: "Change the password of a level (empty removes)",
+    },
+    ParamsSummary => {
+        pt: "Lista os parâmetros com valor, limites e padrão",
+        en: "List the parameters with value, limits and default",
+    },
+    GetSummary => { pt: "Mostra o valor de um parâmetro", en: "Show the value of a parameter" },
+    SetSummary => {
+        pt: "Altera um parâmetro (vale na hora)",
+        en: "Change a parameter (takes effect immediately)",
+    },
+    ConfigSummary => {
+        pt: "Grava, carrega, apaga ou mostra a configuração na flash",
+        en: "Save, load, erase or show the configuration in flash",
+    },
+    RxSummary => {
+        pt: "Recebe arquivos por XMODEM-CRC ou YMODEM",
+        en: "Receive files over XMODEM-CRC or YMODEM",
+    },
+    SxSummary => {
+        pt: "Envia um arquivo por XMODEM-CRC ou YMODEM",
+        en: "Send a file over XMODEM-CRC or YMODEM",
+    },
+    FilesSummary => { pt: "Lista os arquivos em RAM", en: "List the files in RAM" },
+    RmSummary => { pt: "Apaga um arquivo", en: "Delete a file" },
+    PeekSummary => {
+        pt: "Lê valores da memória (8, 16 ou 32 bits)",
+        en: "Read values from memory (8, 16 or 32 bits)",
+    },
+    PokeSummary => {
+        pt: "Grava um valor na memória ou num registrador",
+        en: "Write a value to memory or to a register",
+    },
+    DumpSummary => {
+        pt: "Mostra um trecho da memória em hexadecimal e ASCII",
+        en: "Show a memory range in hexadecimal and ASCII",
+    },
+    RegSummary => {
+        pt: "Mostra registradores de periféricos campo a campo",
+        en: "Show peripheral registers field by field",
+    },
+    PsSummary => {
+        pt: "Lista as tasks com polls, tempo de CPU e carga",
+        en: "List the tasks with polls, CPU time and load",
+    },
+    TopSummary => {
+        pt: "Carga da CPU por task, atualizada (Ctrl-C para sair)",
+        en: "Per-task CPU load, refreshed (Ctrl-C to quit)",
+    },
+    GpioSummary => {
+        pt: "Configura, lê, escreve e observa pinos livres",
+        en: "Configure, read, write and watch free pins",
+    },
+    FwSummary => {
+        pt: "Mostra a imagem em execução ou recebe uma nova por YMODEM",
+        en: "Show the running image or receive a new one over YMODEM",
+    },
+    WatchSummary => {
+        pt: "Executa um comando periodicamente (Ctrl-C para sair)",
+        en: "Run a command periodically (Ctrl-C to quit)",
+    },
+
+    // Uso dos comandos (help <comando> e erros de argumento)
+    HelpUsage => { pt: "help [comando]", en: "help [command]" },
+    LedUsage => { pt: "led on|off|toggle", en: "led on|off|toggle" },
+    StatusUsage => { pt: "status", en: "status" },
+    HistoryUsage => { pt: "history", en: "history" },
+    EchoUsage => { pt: "echo [on|off]", en: "echo [on|off]" },
+    EolUsage => { pt: "eol [in cr|lf|crlf|auto | out crlf|lf|cr]", en: "eol [in cr|lf|crlf|auto | out crlf|lf|cr]" },
+    LangUsage => { pt: "lang [pt|en]", en: "lang [pt|en]" },
+    ModeUsage => { pt: "mode [human|json]", en: "mode [human|json]" },
+    AdcUsage => { pt: "adc cont [limiar, ex: 1.2V]", en: "adc cont [threshold, e.g. 1.2V]" },
+    JobsUsage => { pt: "jobs", en: "jobs" },
+    KillUsage => { pt: "kill <id>", en: "kill <id>" },
+    PsUsage => { pt: "ps", en: "ps" },
+    TopUsage => { pt: "top [intervalo, ex: 2s]", en: "top [interval, e.g. 2s]" },
+    RepeatUsage => { pt: "repeat <n> <comando> [args...]", en: "repeat <n> <command> [args...]" },
+    WatchUsage => {
+        pt: "watch <intervalo, ex: 1s> <comando> [args...]",
+        en: "watch <interval, e.g. 1s> <command> [args...]",
+    },
+    AliasUsage => { pt: "alias [nome [= \"comando ...\"]]", en: "alias [name [= \"command ...\"]]" },
+    UnaliasUsage => { pt: "unalias <nome>", en: "unalias <name>" },
+    AutoexecUsage => {
+        pt: "autoexec [list | add \"comando ...\" | del <n> | clear]",
+        en: "autoexec [list | add \"command ...\" | del <n> | clear]",
+    },
+    LogoutUsage => { pt: "logout", en: "logout" },
+    PasswdUsage => { pt: "passwd user|admin", en: "passwd user|admin" },
+    ParamsUsage => { pt: "params", en: "params" },
+    GetUsage => { pt: "get <parametro>", en: "get <parameter>" },
+    SetUsage => { pt: "set <parametro> <valor, ex: 200ms>", en: "set <parameter> <value, e.g. 200ms>" },
+    ConfigUsage => { pt: "config save|load|reset|dump", en: "config save|load|reset|dump" },
+    RxUsage => { pt: "rx xmodem <arquivo> | rx ymodem", en: "rx xmodem <file> | rx ymodem" },
+    SxUsage => { pt: "sx xmodem|ymodem <arquivo>", en: "sx xmodem|ymodem <file>" },
+    FilesUsage => { pt: "files", en: "files" },
+    RmUsage => { pt: "rm <arquivo>", en: "rm <file>" },
+    FwUsage => { pt: "fw status|update", en: "fw status|update" },
+    PeekUsage => { pt: "peek <endereco> [n] [8|16|32]", en: "peek <address> [n] [8|16|32]" },
+    PokeUsage => { pt: "poke <endereco> <valor> [8|16|32]", en: "poke <address> <value> [8|16|32]" },
+    DumpUsage => { pt: "dump <endereco> [bytes] [8|16|32]", en: "dump <address> [bytes] [8|16|32]" },
+    RegUsage => { pt: "reg [periferico] [registrador]", en: "reg [peripheral] [register]" },
+    GpioUsage => {
+        pt: "gpio [list | mode <pino> in|out|od [none|up|down] | read <pino> | write <pino> 0|1 | watch <pino> | free <pino>]",
+        en: "gpio [list | mode <pin> in|out|od [none|up|down] | read <pin> | write <pin> 0|1 | watch <pin> | free <pin>]",
+    },
+
+    // Nomes dos argumentos ("falta o argumento <nome>")
+    ArgAction => { pt: "acao", en: "action" },
+    ArgFile => { pt: "arquivo", en: "file" },
+    ArgBytes => { pt: "bytes", en: "bytes" },
+    ArgCommand => { pt: "comando", en: "command" },
+    ArgDirection => { pt: "direcao", en: "direction" },
+    ArgAddress => { pt: "endereco", en: "address" },
+    ArgState => { pt: "estado", en: "state" },
+    ArgFormat => { pt: "formato", en: "format" },
+    ArgId => { pt: "id", en: "id" },
+    ArgLang => { pt: "idioma", en: "language" },
+    ArgInterval => { pt: "intervalo", en: "interval" },
+    ArgWidth => { pt: "largura", en: "width" },
+    ArgThreshold => { pt: "limiar", en: "threshold" },
+    ArgMode => { pt: "modo", en: "mode" },
+    ArgCount => { pt: "n", en: "n" },
+    ArgLevel => { pt: "nivel", en: "level" },
+    ArgName => { pt: "nome", en: "name" },
+    ArgParam => { pt: "parametro", en: "parameter" },
+    ArgPin => { pt: "pino", en: "pin" },
+    ArgProtocol => { pt: "protocolo", en: "protocol" },
+    ArgPull => { pt: "pull", en: "pull" },
+    ArgValue => { pt: "valor", en: "value" },
+    ArgEquals => { pt: "=", en: "=" },
+    ArgExtra => { pt: "arg", en: "arg" },
+    ArgPeripheral => { pt: "periferico", en: "peripheral" },
+    ArgRegister => { pt: "registrador", en: "register" },
+
+    // Respostas dos comandos
+    CommandList => { pt: "Comandos disponíveis:\n", en: "Available commands:\n" },
+    LedOn => { pt: "LED ligado\n", en: "LED on\n" },
+    LedOff => { pt: "LED desligado\n", en: "LED off\n" },
+    StatusActive => { pt: "Sistema OK - LED ativo\n", en: "System OK - LED active\n" },
+    StatusInactive => { pt: "Sistema OK - LED inativo\n", en: "System OK - LED inactive\n" },
+    EchoOn => { pt: "Eco: ligado\n", en: "Echo: on\n" },
+    EchoOff => { pt: "Eco: desligado\n", en: "Echo: off\n" },
+    EolInput => { pt: "Entrada: ", en: "Input: " },
+    EolOutput => { pt: ", saida: ", en: ", output: " },
+    Language => { pt: "Idioma: ", en: "Language: " },
+    Mode => { pt: "Formato: ", en: "Format: " },
+    AdcStart => {
+        pt: "Modo continuo (Ctrl-C para sair):\nFormato: [valor bruto] -> [tensao] mV\n",
+        en: "Continuous mode (Ctrl-C to quit):\nFormat: [raw value] -> [voltage] mV\n",
+    },
+    JobRunning => { pt: "Executando", en: "Running" },
+    JobDone => { pt: "Concluído", en: "Done" },
+    JobFailed => { pt: "Falhou", en: "Failed" },
+    JobKilled => { pt: "Terminado", en: "Killed" },
+    NoSuchJob => { pt: "Job não encontrado\n", en: "No such job\n" },
+    StartupFull => {
+        pt: "Sem espaço para aliases/autoexec\n",
+        en: "No room left for aliases/autoexec\n",
+    },
+    BadAliasName => {
+        pt: "Nome de alias inválido (letras, números, '_' e '-')\n",
+        en: "Invalid alias name (letters, digits, '_' and '-')\n",
+    },
+    NotFound => { pt: "Não encontrado\n", en: "Not found\n" },
+    StoreFailed => { pt: "Falha ao gravar na flash\n", en: "Flash write failed\n" },
+    PermissionDenied => { pt: "Permissão negada (requer admin)\n", en: "Permission denied (admin required)\n" },
+    NoAdminPassword => {
+        pt: "Defina antes a senha de admin ('passwd admin')\n",
+        en: "Set the admin password first ('passwd admin')\n",
+    },
+    PasswordPrompt => { pt: "Senha: ", en: "Password: " },
+    NewPasswordPrompt => { pt: "Nova senha: ", en: "New password: " },
+    RepeatPasswordPrompt => { pt: "Repita a senha: ", en: "Repeat the password: " },
+    PasswordMismatch => { pt: "As senhas não conferem\n", en: "Passwords do not match\n" },
+    PasswordChanged => { pt: "Senha alterada\n", en: "Password changed\n" },
+    LoginFailed => { pt: "Senha incorreta\n", en: "Wrong password\n" },
+    LoginLocked => {
+        pt: "Tentativas demais: login bloqueado por 30 s\n",
+        en: "Too many attempts: login locked for 30 s\n",
+    },
+    LoggedIn => { pt: "Nível: ", en: "Level: " },
+    SessionTimeout => { pt: "Sessão encerrada por inatividade\n", en: "Session closed after inactivity\n" },
+    NestedLoop => {
+        pt: "repeat/watch não podem repetir outro repeat/watch\n",
+        en: "repeat/watch cannot repeat another repeat/watch\n",
+    },
+    ParamOutOfRange => {
+        pt: "Valor fora dos limites do parâmetro\n",
+        en: "Value outside the parameter limits\n",
+    },
+    ParamDefault => { pt: "padrão", en: "default" },
+    ConfigFull => { pt: "Configuração não cabe na flash\n", en: "Configuration does not fit in flash\n" },
+    ConfigNewerSchema => {
+        pt: "Configuração gravada por um firmware mais novo (use 'config reset')\n",
+        en: "Configuration written by newer firmware (use 'config reset')\n",
+    },
+    NoConfigFlash => { pt: "Placa sem flash de configuração\n", en: "Board has no configuration flash\n" },
+    ConfigLoaded => { pt: "Parâmetros carregados: ", en: "Parameters loaded: " },
+    ConfigSaved => { pt: "Configuração gravada\n", en: "Configuration saved\n" },
+    ConfigCleared => {
+        pt: "Configuração apagada (parâmetros no padrão)\n",
+        en: "Configuration erased (parameters at default)\n",
+    },
+    ConfigEmpty => { pt: "Configuração vazia\n", en: "Configuration empty\n" },
+    ConfigSector => { pt: "Setor ", en: "Sector " },
+    ConfigGeneration => { pt: ", geração ", en: ", generation " },
+    ConfigSchema => { pt: ", versão ", en: ", version " },
+    ConfigUsed => { pt: ", usados ", en: ", used " },
+    FileFull => { pt: "Arquivo grande demais\n", en: "File too large\n" },
+    TooManyFiles => { pt: "Arquivos demais (apague um com 'rm')\n", en: "Too many files (delete one with 'rm')\n" },
+    BadFileName => {
+        pt: "Nome de arquivo inválido (letras, números, '.', '_' e '-')\n",
+        en: "Invalid file name (letters, digits, '.', '_' and '-')\n",
+    },
+    FileNotFound => { pt: "Arquivo não encontrado\n", en: "File not found\n" },
+    NoFiles => { pt: "Nenhum arquivo\n", en: "No files\n" },
+    ForegroundOnly => {
+        pt: "Transferências só em primeiro plano\n",
+        en: "Transfers only run in the foreground\n",
+    },
+    TransferReady => {
+        pt: "Inicie a transferência no terminal (Ctrl-X Ctrl-X cancela)\n",
+        en: "Start the transfer in the terminal (Ctrl-X Ctrl-X cancels)\n",
+    },
+    TransferDone => { pt: "Transferência concluída: ", en: "Transfer complete: " },
+    TransferFiles => { pt: " arquivo(s), ", en: " file(s), " },
+    TransferCancelled => { pt: "Transferência cancelada\n", en: "Transfer cancelled\n" },
+    TransferTimeout => { pt: "Transferência sem resposta\n", en: "Transfer timed out\n" },
+    TransferFailed => {
+        pt: "Transferência falhou (erros demais)\n",
+        en: "Transfer failed (too many errors)\n",
+    },
+
+    // Acesso à memória (`peek`, `poke`, `dump`)
+    BadAddress => {
+        pt: "Endereço fora das regiões de memória conhecidas\n",
+        en: "Address outside the known memory regions\n",
+    },
+    ReadOnlyAddress => { pt: "Região só de leitura\n", en: "Read-only region\n" },
+    UnalignedAddress => {
+        pt: "Endereço não alinhado à largura do acesso\n",
+        en: "Address not aligned to the access width\n",
+    },
+    ReadClearsAddress => {
+        pt: "Registrador que a leitura altera: não é lido\n",
+        en: "Reading would change this register: not read\n",
+    },
+
+    UnknownPeripheral => {
+        pt: "Periférico desconhecido ('reg' lista os disponíveis)\n",
+        en: "Unknown peripheral ('reg' lists the available ones)\n",
+    },
+    UnknownRegister => { pt: "Registrador desconhecido\n", en: "Unknown register\n" },
+    RegisterNotRead => {
+        pt: "  (não lido: a leitura altera o estado)\n",
+        en: "  (not read: reading changes its state)\n",
+    },
+
+    // Tasks (`ps`, `top`)
+    NoTaskMonitor => { pt: "Placa sem monitor de tasks\n", en: "Board has no task monitor\n" },
+    PsHeader => {
+        pt: "TASK         POLLS   CPU(us)  ULTIMO(ms)\n",
+        en: "TASK         POLLS   CPU(us)    LAST(ms)\n",
+    },
+    TopHeader => {
+        pt: "TASK         POLLS/s   CPU%\n",
+        en: "TASK         POLLS/s   CPU%\n",
+    },
+    CpuLoad => { pt: "Carga da CPU: ", en: "CPU load: " },
+    SinceBoot => { pt: " desde o boot\n", en: " since boot\n" },
+
+    // Pinos (`gpio`)
+    BadPin => { pt: "Pino inválido (ex: PA5, PD12)\n", en: "Invalid pin (e.g. PA5, PD12)\n" },
+    PinInUse => { pt: "Pino usado por um periférico\n", en: "Pin used by a peripheral\n" },
+    PinNotConfigured => {
+        pt: "Pino não configurado ('gpio mode' antes)\n",
+        en: "Pin not configured ('gpio mode' first)\n",
+    },
+    PinNotOutput => { pt: "Pino configurado como entrada\n", en: "Pin configured as input\n" },
+    PinsFull => { pt: "Pinos demais configurados\n", en: "Too many pins configured\n" },
+    GpioWatch => {
+        pt: "Observando bordas (Ctrl-C para sair):\nFormato: +[ms] [pino] [nível]\n",
+        en: "Watching edges (Ctrl-C to quit):\nFormat: +[ms] [pin] [level]\n",
+    },
+
+    // Atualização de firmware (`fw`)
+    NoBootFlash => { pt: "Placa sem bootloader\n", en: "Board has no bootloader\n" },
+    FwState => { pt: "Estado: ", en: "State: " },
+    FwActive => { pt: "Imagem em execução: ", en: "Running image: " },
+    FwDfu => { pt: "Slot DFU: ", en: "DFU slot: " },
+    FwNoImage => { pt: "sem descritor\n", en: "no descriptor\n" },
+    FirmwareInvalid => {
+        pt: "Imagem inválida (descritor, SHA-256 ou endereço)\n",
+        en: "Invalid image (descriptor, SHA-256 or address)\n",
+    },
+    FirmwareTesting => {
+        pt: "Imagem nova em teste; aguarde a confirmação\n",
+        en: "New image under test; wait for it to be confirmed\n",
+    },
+    FirmwareInstalled => {
+        pt: "Imagem verificada; reiniciando para a troca\n",
+        en: "Image verified; restarting to swap\n",
+    },
+
+    // Parâmetros (`params`)
+    AdcPeriodParam => { pt: "Intervalo entre leituras do ADC", en: "Interval between ADC readings" },
+    BlinkPeriodParam => { pt: "Tempo aceso/apagado do LED piscante", en: "On/off time of the blinking LED" },
+    VrefParam => { pt: "Tensão de referência do ADC", en: "ADC reference voltage" },
+    CorrectionParam => {
+        pt: "Fator do divisor de tensão (x10000)",
+        en: "Voltage divider factor (x10000)",
+    },
+    BaudParam => { pt: "Velocidade das UARTs do shell", en: "Shell UART baud rate" },
+}
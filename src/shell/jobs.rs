@@ -0,0 +1,186 @@
+// Jobs em segundo plano (`cmd &`, `jobs`, `kill <id>`): cada job é o despacho
+// normal de uma linha rodando em paralelo com o shell, na mesma task.
+// Os futures ficam em slots fixos no `Shell::run`; esta tabela guarda só o
+// que os comandos enxergam (linha, estado e pedido de término)
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+use core::future::{poll_fn, Future};
+use core::pin::Pin;
+use core::task::Poll;
+use embedded_io_async::Write;
+
+use super::editor::Line;
+use super::history::NoHistory;
+use super::i18n::Msg;
+use super::json::Reply;
+use super::settings::Settings;
+use super::{process_command, Board, Context, Shared};
+
+// Jobs simultâneos (ids de 1 a MAX_JOBS)
+pub const MAX_JOBS: usize = 4;
+
+// Situação de um job
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Done,   // Comando terminou
+    Failed, // Erro de transporte
+    Killed, // Encerrado por `kill`
+}
+
+impl JobState {
+    // Nome estável (modo json)
+    pub fn name(self) -> &'static str {
+        match self {
+            JobState::Running => "running",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+            JobState::Killed => "killed",
+        }
+    }
+
+    // Nome no idioma da sessão (modo texto)
+    pub fn msg(self) -> Msg {
+        match self {
+            JobState::Running => Msg::JobRunning,
+            JobState::Done => Msg::JobDone,
+            JobState::Failed => Msg::JobFailed,
+            JobState::Killed => Msg::JobKilled,
+        }
+    }
+}
+
+// Entrada da tabela de jobs
+#[derive(Clone)]
+pub struct JobInfo {
+    pub line: Line,       // Linha executada (sem o '&')
+    pub state: JobState,
+    kill: bool,           // `kill` pedido, future ainda não descartado
+}
+
+// Tabela de jobs compartilhada entre o shell e os comandos
+#[derive(Default)]
+pub struct Jobs {
+    table: RefCell<[Option<JobInfo>; MAX_JOBS]>,
+}
+
+impl Jobs {
+    pub const fn new() -> Self {
+        Self { table: RefCell::new([const { None }; MAX_JOBS]) }
+    }
+
+    // Cópia da entrada `id`
+    pub fn get(&self, id: usize) -> Option<JobInfo> {
+        self.table.borrow().get(id.checked_sub(1)?)?.clone()
+    }
+
+    // Pede o término de um job em execução; false se não existir
+    pub fn kill(&self, id: usize) -> bool {
+        let mut table = self.table.borrow_mut();
+        match id.checked_sub(1).and_then(|i| table.get_mut(i)).and_then(Option::as_mut) {
+            Some(job) if job.state == JobState::Running => {
+                job.kill = true;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    // Pede o término de todos os jobs (fim da sessão)
+    pub fn kill_all(&self) {
+        for id in 1..=MAX_JOBS {
+            self.kill(id);
+        }
+    }
+
+    // Reserva um id livre para a linha
+    pub(super) fn start(&self, line: &str) -> Option<usize> {
+        let mut table = self.table.borrow_mut();
+        let index = table.iter().position(Option::is_none)?;
+        let mut copy = Line::new();
+        for c in line.chars() {
+            if copy.push(c).is_err() {
+                break; // Só para exibição: trunca linhas maiores
+            }
+        }
+        table[index] = Some(JobInfo { line: copy, state: JobState::Running, kill: false });
+        Some(index + 1)
+    }
+
+    // Remove e retorna um job encerrado (para avisar no próximo prompt)
+    pub(super) fn take_finished(&self) -> Option<(usize, JobInfo)> {
+        let mut table = self.table.borrow_mut();
+        let index = table.iter().position(|j| j.as_ref().is_some_and(|j| j.state != JobState::Running))?;
+        table[index].take().map(|job| (index + 1, job))
+    }
+
+    fn finish(&self, index: usize, state: JobState) {
+        if let Some(job) = self.table.borrow_mut()[index].as_mut() {
+            job.state = state;
+        }
+    }
+
+    fn kill_requested(&self, index: usize) -> bool {
+        self.table.borrow()[index].as_ref().is_some_and(|j| j.kill)
+    }
+}
+
+// Corpo de um job: despacha a linha com uma cópia das configurações da sessão
+pub(super) async fn run<W: Write, B: Board, const L: usize>(
+    shared: &Shared<'_, W, B>,
+    id: usize,
+    line: Line<L>,
+    mut settings: Settings,
+) -> Result<(), W::Error> {
+    let mut tx = shared.out.tx();
+    let mut ctx = Context {
+        io: &mut tx,
+        board: &shared.app.board,
+        history: &NoHistory, // Jobs não têm acesso ao histórico da sessão
+        settings: &mut settings,
+        reply: Reply::default(),
+        jobs: &shared.jobs,
+        job: Some(id),
+        startup: &shared.app.startup,
+        auth: &shared.app.auth,
+        params: &shared.app.params,
+        files: &shared.app.files,
+        pins: &shared.app.pins,
+    };
+    process_command(&line, &mut ctx).await
+}
+
+// Descarta os jobs com `kill` pendente
+pub(super) fn reap<F>(jobs: &Jobs, slots: &mut [Option<F>; MAX_JOBS]) {
+    for (index, slot) in slots.iter_mut().enumerate() {
+        if slot.is_some() && jobs.kill_requested(index) {
+            *slot = None; // Destrói o future no lugar
+            jobs.finish(index, JobState::Killed);
+        }
+    }
+}
+
+// Executa os jobs enquanto o shell espera outra coisa (nunca termina)
+pub(super) async fn poll<F, E>(jobs: &Jobs, slots: &mut [Option<F>; MAX_JOBS]) -> Infallible
+where
+    F: Future<Output = Result<(), E>>,
+{
+    poll_fn(|cx| {
+        reap(jobs, slots);
+        for (index, slot) in slots.iter_mut().enumerate() {
+            let Some(future) = slot.as_mut() else {
+                continue;
+            };
+            // SAFETY: os slots vivem no estado do `Shell::run` (já fixado na
+            // memória) e nunca são movidos; o future é destruído no lugar
+            let future = unsafe { Pin::new_unchecked(future) };
+            if let Poll::Ready(result) = future.poll(cx) {
+                *slot = None;
+                jobs.finish(index, if result.is_ok() { JobState::Done } else { JobState::Failed });
+            }
+        }
+        Poll::Pending
+    })
+    .await
+}
@@ -0,0 +1,217 @@
+// Saída estruturada (JSON Lines) para automação: cada resposta é um objeto
+// por linha, escrito aos poucos direto no transporte (sem buffer do objeto)
+
+use embedded_io_async::Write;
+
+// Objeto de resposta em construção; abre na primeira escrita com o nome do comando
+pub struct Reply {
+    cmd: Option<&'static str>, // Comando que gerou a resposta (None = erro do shell)
+    job: Option<usize>,        // Job em segundo plano que gerou a resposta
+    open: bool,                // '{' já enviado
+    comma: bool,               // Próximo valor precisa de ','
+}
+
+impl Default for Reply {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl Reply {
+    pub const fn new(cmd: Option<&'static str>) -> Self {
+        Self { cmd, job: None, open: false, comma: false }
+    }
+
+    // Identifica as respostas de um job
+    pub fn with_job(self, job: Option<usize>) -> Self {
+        Self { job, ..self }
+    }
+
+    // Abre o objeto com o comando e o job que o geraram
+    async fn open<T: Write>(&mut self, io: &mut T) -> Result<(), T::Error> {
+        io.write_all(b"{").await?;
+        self.open = true;
+        self.comma = false;
+        if let Some(cmd) = self.cmd {
+            io.write_all(b"\"cmd\":").await?;
+            write_string(io, cmd).await?;
+            self.comma = true;
+        }
+        if let Some(job) = self.job {
+            if self.comma {
+                io.write_all(b",").await?;
+            }
+            io.write_all(b"\"job\":").await?;
+            io.write_all(itoa::Buffer::new().format(job).as_bytes()).await?;
+            self.comma = true;
+        }
+        Ok(())
+    }
+
+    // Abre o objeto (se preciso) e escreve a chave de um campo
+    async fn key<T: Write>(&mut self, io: &mut T, key: &str) -> Result<(), T::Error> {
+        if !self.open {
+            self.open(io).await?;
+        }
+        if self.comma {
+            io.write_all(b",").await?;
+        }
+        write_string(io, key).await?;
+        io.write_all(b":").await?;
+        self.comma = true;
+        Ok(())
+    }
+
+    pub async fn str<T: Write>(&mut self, io: &mut T, key: &str, value: &str) -> Result<(), T::Error> {
+        self.key(io, key).await?;
+        write_string(io, value).await
+    }
+
+    pub async fn int<T: Write>(&mut self, io: &mut T, key: &str, value: i64) -> Result<(), T::Error> {
+        self.key(io, key).await?;
+        io.write_all(itoa::Buffer::new().format(value).as_bytes()).await
+    }
+
+    pub async fn bool<T: Write>(&mut self, io: &mut T, key: &str, value: bool) -> Result<(), T::Error> {
+        self.key(io, key).await?;
+        io.write_all(if value { b"true" } else { b"false" }).await
+    }
+
+    // Listas: `begin_array`, itens (`item_str` ou objetos), `end_array`
+    pub async fn begin_array<T: Write>(&mut self, io: &mut T, key: &str) -> Result<(), T::Error> {
+        self.key(io, key).await?;
+        io.write_all(b"[").await?;
+        self.comma = false;
+        Ok(())
+    }
+
+    pub async fn end_array<T: Write>(&mut self, io: &mut T) -> Result<(), T::Error> {
+        self.comma = true;
+        io.write_all(b"]").await
+    }
+
+    pub async fn item_str<T: Write>(&mut self, io: &mut T, value: &str) -> Result<(), T::Error> {
+        if self.comma {
+            io.write_all(b",").await?;
+        }
+        self.comma = true;
+        write_string(io, value).await
+    }
+
+    // Objeto dentro de uma lista (os campos seguintes vão para ele)
+    pub async fn begin_item<T: Write>(&mut self, io: &mut T) -> Result<(), T::Error> {
+        if self.comma {
+            io.write_all(b",").await?;
+        }
+        self.comma = false;
+        io.write_all(b"{").await
+    }
+
+    pub async fn end_item<T: Write>(&mut self, io: &mut T) -> Result<(), T::Error> {
+        self.comma = true;
+        io.write_all(b"}").await
+    }
+
+    // Fecha o objeto e termina a linha; a próxima escrita abre outro objeto
+    pub async fn end<T: Write>(&mut self, io: &mut T, eol: &[u8]) -> Result<(), T::Error> {
+        if !self.open {
+            self.open(io).await?;
+        }
+        self.open = false;
+        io.write_all(b"}").await?;
+        io.write_all(eol).await
+    }
+}
+
+// Escreve `s` como string JSON (aspas e escapes)
+pub async fn write_string<T: Write>(io: &mut T, s: &str) -> Result<(), T::Error> {
+    io.write_all(b"\"").await?;
+    let bytes = s.as_bytes();
+    let mut start = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let mut hex = *b"\\u0000";
+        let escape: &[u8] = match byte {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x00..=0x1F => {
+                const DIGITS: &[u8; 16] = b"0123456789abcdef";
+                hex[4] = DIGITS[(byte >> 4) as usize];
+                hex[5] = DIGITS[(byte & 0x0F) as usize];
+                &hex
+            },
+            _ => continue,
+        };
+        io.write_all(&bytes[start..i]).await?;
+        io.write_all(escape).await?;
+        start = i + 1;
+    }
+    io.write_all(&bytes[start..]).await?;
+    io.write_all(b"\"").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::testing::{block_on, Mock};
+
+    fn text(mock: Mock) -> std::string::String {
+        std::string::String::from_utf8(mock.output).unwrap()
+    }
+
+    #[test]
+    fn string_escapes() {
+        let mut mock = Mock::default();
+        block_on(write_string(&mut mock, "a\"b\\c\n\r\t\u{1}\u{1f}é")).unwrap();
+        assert_eq!(text(mock), r#""a\"b\\c\n\r\t\u0001\u001fé""#);
+    }
+
+    #[test]
+    fn fields_and_arrays() {
+        let mut mock = Mock::default();
+        let mut reply = Reply::new(Some("gpio")).with_job(Some(2));
+        block_on(async {
+            reply.int(&mut mock, "ms", -5).await?;
+            reply.bool(&mut mock, "ok", true).await?;
+            reply.begin_array(&mut mock, "names").await?;
+            reply.item_str(&mut mock, "a").await?;
+            reply.item_str(&mut mock, "b").await?;
+            reply.end_array(&mut mock).await?;
+            reply.begin_array(&mut mock, "pins").await?;
+            for pin in ["PA5", "PD12"] {
+                reply.begin_item(&mut mock).await?;
+                reply.str(&mut mock, "pin", pin).await?;
+                reply.end_item(&mut mock).await?;
+            }
+            reply.end_array(&mut mock).await?;
+            reply.end(&mut mock, b"\n").await?;
+            // Depois de `end` a próxima escrita abre outro objeto
+            reply.str(&mut mock, "x", "y").await?;
+            reply.end(&mut mock, b"\n").await
+        })
+        .unwrap();
+        assert_eq!(
+            text(mock),
+            concat!(
+                r#"{"cmd":"gpio","job":2,"ms":-5,"ok":true,"names":["a","b"],"pins":[{"pin":"PA5"},{"pin":"PD12"}]}"#,
+                "\n",
+                r#"{"cmd":"gpio","job":2,"x":"y"}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn empty_replies() {
+        let mut mock = Mock::default();
+        block_on(async {
+            Reply::new(Some("led")).end(&mut mock, b"\r\n").await?;
+            Reply::default().with_job(Some(1)).end(&mut mock, b"\n").await?;
+            Reply::default().end(&mut mock, b"\n").await
+        })
+        .unwrap();
+        assert_eq!(text(mock), "{\"cmd\":\"led\"}\r\n{\"job\":1}\n{}\n");
+    }
+}
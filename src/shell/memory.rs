@@ -0,0 +1,137 @@
+// Acesso à memória pelo shell (`peek`, `poke`, `dump`): só dentro das regiões
+// conhecidas da placa (ver `Board::memory_map`). Um endereço fora delas daria
+// hard fault em vez de erro
+
+// Largura de cada acesso (registradores de periféricos exigem a largura certa)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Width {
+    Byte,
+    Half,
+    Word,
+}
+
+// Nomes aceitos pelos comandos (mesma ordem de `Width::ALL`)
+pub const WIDTHS: &[&str] = &["8", "16", "32"];
+
+impl Width {
+    pub const ALL: [Width; 3] = [Width::Byte, Width::Half, Width::Word];
+
+    pub fn bytes(self) -> usize {
+        match self {
+            Width::Byte => 1,
+            Width::Half => 2,
+            Width::Word => 4,
+        }
+    }
+
+    // Maior valor que cabe na largura
+    pub fn max(self) -> u32 {
+        match self {
+            Width::Byte => 0xFF,
+            Width::Half => 0xFFFF,
+            Width::Word => 0xFFFF_FFFF,
+        }
+    }
+}
+
+// Região acessível: [start, end)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub name: &'static str,
+    pub start: u32,
+    pub end: u32,
+    pub writable: bool, // Flash só para leitura (gravar nela exige o controlador)
+    pub readable: bool, // Ler altera o estado (ex: SR/DR da USART): `peek`/`dump`/`reg` recusam
+}
+
+// Acesso recusado
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryError {
+    Unmapped,    // Algum byte fora das regiões
+    ReadOnly,    // `poke` numa região só de leitura
+    Unaligned,   // Endereço não múltiplo da largura
+    ReadClears,  // `peek`/`dump`/`reg` num registrador que a leitura altera
+}
+
+// Tipo de acesso conferido
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,  // `peek`/`dump`/`reg`
+    Write, // `poke`
+}
+
+// Confere `len` bytes a partir de `address` (podem atravessar regiões vizinhas,
+// como SRAM e SRAM2). Regiões sem leitura ficam dentro de outras maiores e
+// vêm antes delas no mapa
+pub fn check(map: &[MemoryRegion], address: u32, len: usize, width: Width, access: Access) -> Result<(), MemoryError> {
+    if !address.is_multiple_of(width.bytes() as u32) {
+        return Err(MemoryError::Unaligned);
+    }
+    let end = address as u64 + len as u64;
+    if access == Access::Read {
+        let overlaps = |r: &MemoryRegion| (r.start as u64) < end && (address as u64) < r.end as u64;
+        if map.iter().any(|r| !r.readable && overlaps(r)) {
+            return Err(MemoryError::ReadClears);
+        }
+    }
+    let mut next = address as u64;
+    while next < end {
+        let region = map
+            .iter()
+            .find(|r| (r.start as u64..r.end as u64).contains(&next))
+            .ok_or(MemoryError::Unmapped)?;
+        if access == Access::Write && !region.writable {
+            return Err(MemoryError::ReadOnly);
+        }
+        next = region.end as u64;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: [MemoryRegion; 4] = [
+        MemoryRegion { name: "usart", start: 0x100, end: 0x108, writable: true, readable: false },
+        MemoryRegion { name: "apb", start: 0x000, end: 0x400, writable: true, readable: true },
+        MemoryRegion { name: "sram", start: 0x1000, end: 0x1800, writable: true, readable: true },
+        MemoryRegion { name: "sram2", start: 0x1800, end: 0x1C00, writable: true, readable: true },
+    ];
+
+    #[test]
+    fn spans_neighbouring_regions() {
+        assert_eq!(check(&MAP, 0x17F0, 0x20, Width::Word, Access::Read), Ok(()));
+        assert_eq!(check(&MAP, 0x1BF0, 0x20, Width::Word, Access::Read), Err(MemoryError::Unmapped));
+        assert_eq!(check(&MAP, 0x1002, 2, Width::Word, Access::Read), Err(MemoryError::Unaligned));
+    }
+
+    #[test]
+    fn read_clearing_registers_are_not_read() {
+        assert_eq!(check(&MAP, 0x104, 4, Width::Word, Access::Read), Err(MemoryError::ReadClears));
+        // Um `dump` que só passa por cima também é recusado
+        assert_eq!(check(&MAP, 0x0F0, 0x40, Width::Byte, Access::Read), Err(MemoryError::ReadClears));
+        assert_eq!(check(&MAP, 0x108, 4, Width::Word, Access::Read), Ok(()));
+        assert_eq!(check(&MAP, 0x104, 4, Width::Word, Access::Write), Ok(()));
+    }
+
+    #[test]
+    fn dump_over_a_read_clearing_register_is_refused() {
+        // Como no F407: FIFO do SDIO no APB2 e DR da DCMI no AHB2
+        let map = [
+            MemoryRegion { name: "sdio_fifo", start: 0x4001_2C80, end: 0x4001_2D00, writable: true, readable: false },
+            MemoryRegion { name: "dcmi_dr", start: 0x5005_0028, end: 0x5005_002C, writable: true, readable: false },
+            MemoryRegion { name: "apb2", start: 0x4001_0000, end: 0x4001_4C00, writable: true, readable: true },
+            MemoryRegion { name: "dcmi", start: 0x5005_0000, end: 0x5005_0400, writable: true, readable: true },
+        ];
+        let dump = |address, len| check(&map, address, len, Width::Byte, Access::Read);
+        assert_eq!(dump(0x4001_2C00, 0x100), Err(MemoryError::ReadClears)); // Cobre a FIFO
+        assert_eq!(dump(0x4001_2CF0, 0x20), Err(MemoryError::ReadClears)); // Começa no fim dela
+        assert_eq!(dump(0x5005_0020, 9), Err(MemoryError::ReadClears)); // Um byte do DR
+        // Encostar nas bordas sem entrar é permitido
+        assert_eq!(dump(0x4001_2C00, 0x80), Ok(()));
+        assert_eq!(dump(0x4001_2D00, 0x40), Ok(()));
+        assert_eq!(dump(0x5005_0000, 0x28), Ok(()));
+        assert_eq!(dump(0x5005_002C, 4), Ok(()));
+    }
+}
@@ -0,0 +1,1348 @@
+// Shell/terminal genérico sobre os traits `Read`/`Write` do embedded-io-async
+// (não depende da UART concreta, nem do embassy-stm32)
+
+use embassy_futures::select::{select, select3, Either, Either3}; // Comando x Ctrl-C x jobs
+use embedded_io_async::{Read, Write}; // Traits de transporte assíncrono
+use core::fmt::Write as _; // Formatação em buffers de tamanho fixo
+use core::future::Future;
+use heapless::{Deque, String}; // Coleções de tamanho fixo (sem alocação dinâmica)
+
+use crate::boot::{self, BootFlash, FlashError};
+use args::{ArgError, Tokens, TIME};
+use auth::{Auth, AuthError, Level, LoginError, CREDENTIALS_BYTES, SALT_LEN, SESSION_TIMEOUT_MS};
+use binary::{Action, Link};
+use chain::{Chain, Then};
+use config::{ConfigError, ConfigFlash, ConfigStore};
+use commands::{Handler, REPEAT_MAX, WATCH_MAX_MS, WATCH_MIN_MS};
+use editor::{Echo, Event, KeyDecoder, Line, LineEditor, LINE_LEN};
+use files::{FileError, FileName, FileSink, Files};
+use firmware::DfuSink;
+use gpio::{PinError, PinId, PinMode, Pins, Pull};
+use history::{History, NoHistory, Recall, HISTORY_LEN};
+use i18n::{Lang, Msg, LANG_KEY};
+use jobs::{JobState, Jobs, MAX_JOBS};
+use memory::{MemoryError, MemoryRegion, Width};
+use json::Reply;
+use output::{Output, Tx};
+use params::{Param, ParamError, Params};
+use registers::Peripheral;
+use registry::Command;
+use settings::{write_text, EolFilter, OutputMode, Settings};
+use startup::{Startup, StartupError, MAX_SCRIPT_LINES, STARTUP_BYTES};
+use tasks::TaskMonitor;
+use utf8::Utf8Decoder;
+use xmodem::{Protocol, Summary, Transfer, TransferError};
+
+pub mod args;     // Tokenizador e argumentos tipados
+pub mod auth;     // Login, senhas e níveis de privilégio
+pub mod binary;   // Protocolo binário (quadros COBS/CRC)
+pub mod chain;    // Encadeamento com ';' e '&&'
+pub mod commands; // Comandos e tabela de despacho
+pub mod config;   // Configuração persistente na flash (`config`)
+pub mod complete; // Completação com Tab
+pub mod editor;   // Editor de linha e decodificação de teclas ANSI
+pub mod files;    // Arquivos em RAM (`rx`, `sx`, `files`)
+pub mod firmware; // Recepção do `fw update` no slot DFU
+pub mod frame;    // Codec COBS + CRC-16
+pub mod gpio;     // Pinos livres e registro de uso (`gpio`)
+pub mod history;  // Histórico de comandos
+pub mod i18n;     // Catálogo de mensagens (pt/en)
+pub mod jobs;     // Jobs em segundo plano
+pub mod json;     // Respostas em JSON Lines
+pub mod memory;   // Regiões liberadas para `peek`/`poke`/`dump`
+pub mod output;   // Saída compartilhada com os jobs
+pub mod params;   // Parâmetros de execução (`get`/`set`)
+pub mod registers; // Campos dos registradores de periféricos (`reg`)
+pub mod registry; // Registro de comandos e geração da ajuda
+pub mod settings; // Fim de linha, echo, idioma e formato da saída
+pub mod sha256;   // Hash das senhas
+pub mod startup;  // Aliases e script de inicialização
+pub mod tasks;    // Monitor das tasks do executor (`ps`, `top`)
+pub mod utf8;     // Decodificação UTF-8 e largura de caracteres
+pub mod xmodem;   // Transferência XMODEM/YMODEM (`rx`, `sx`)
+
+#[cfg(test)]
+pub(crate) mod testing; // Transporte e placa simulados
+#[cfg(test)]
+mod tests;
+
+// Prompt exibido antes de cada comando
+pub const PROMPT: &str = "stm32> ";
+
+// Caracteres digitados durante um comando (processados quando ele termina)
+const TYPEAHEAD_LEN: usize = 64;
+
+// Ctrl-C: interrompe o comando em primeiro plano
+const INTERRUPT: u8 = 0x03;
+
+// Espera entre a resposta do `fw update` e o reset (a mensagem sai inteira)
+const RESET_DELAY_MS: u64 = 100;
+
+// Acesso ao hardware usado pelos comandos do shell
+// (implementado pela aplicação na placa e por mocks no host). Os métodos usam
+// `&self` porque o hardware é compartilhado entre o shell e os jobs
+#[allow(async_fn_in_trait)]
+pub trait Board {
+    // Estado do LED piscante
+    fn led_enabled(&self) -> bool;
+    fn set_led_enabled(&self, enabled: bool);
+
+    // Próxima leitura ADC disponível (não bloqueante)
+    fn try_adc_sample(&self) -> Option<u16>;
+
+    // Espera assíncrona em milissegundos
+    async fn delay_ms(&self, ms: u64);
+
+    // Milissegundos desde a partida, do relógio da placa (`gpio watch` e o fim
+    // do bloqueio do login)
+    fn uptime_ms(&self) -> u64 {
+        0
+    }
+
+    // Memória que sobrevive ao reset para guardar o histórico (opcional)
+    fn with_history_store(&self, _f: &mut dyn FnMut(&mut [u8])) {}
+
+    // Aliases e autoexec (ver `startup`); sem persistência valem até o reset
+    fn load_startup(&self, _buf: &mut [u8]) {}
+    fn store_startup(&self, _data: &[u8]) -> bool {
+        true
+    }
+
+    // Hashes das senhas (ver `auth`); sem senha de admin gravada não há login
+    fn load_credentials(&self, _buf: &mut [u8]) {}
+    fn store_credentials(&self, _data: &[u8]) -> bool {
+        true
+    }
+
+    // Sal para uma nova senha (deve variar entre placas e entre trocas)
+    fn salt(&self, _salt: &mut [u8]) {}
+
+    // Setores da configuração persistente (ver `config`); sem eles `config` falha
+    fn config_flash(&self) -> Option<&dyn ConfigFlash> {
+        None
+    }
+
+    // Slots do bootloader (ver `boot`); sem eles `fw` falha
+    fn boot_flash(&self) -> Option<&dyn BootFlash> {
+        None
+    }
+
+    // Reinicia a placa (o bootloader faz a troca pedida pelo `fw update`)
+    fn reset(&self) {}
+
+    // Regiões liberadas para `peek`/`poke`/`dump` (vazio: recusam tudo)
+    fn memory_map(&self) -> &[MemoryRegion] {
+        &[]
+    }
+
+    // Um acesso à memória (endereço já conferido com `memory_map`)
+    fn read_memory(&self, _address: u32, _width: Width) -> u32 {
+        0
+    }
+    fn write_memory(&self, _address: u32, _width: Width, _value: u32) {}
+
+    // Periféricos com nome e endereço para o `reg` (ver `registers`)
+    fn peripherals(&self) -> &[Peripheral] {
+        &[]
+    }
+
+    // Pinos que existem no encapsulamento do chip (o `gpio` recusa os outros)
+    fn has_pin(&self, _pin: PinId) -> bool {
+        true
+    }
+
+    // Pinos do `gpio` (só os livres no registro `App::pins` chegam aqui)
+    fn configure_pin(&self, _pin: PinId, _mode: PinMode, _pull: Pull) {}
+    fn release_pin(&self, _pin: PinId) {} // Volta ao estado do reset
+    fn read_pin(&self, _pin: PinId) -> bool {
+        false
+    }
+    fn write_pin(&self, _pin: PinId, _high: bool) {}
+
+    // Cópia atualizada do monitor das tasks (ver `tasks`); sem ele `ps` falha
+    fn task_monitor(&self) -> Option<TaskMonitor> {
+        None
+    }
+}
+
+pub fn adc_to_voltage(adc_value: u16, vref_mv: u32) -> u32 {
+    (adc_value as u32 * vref_mv) / 4095
+}
+
+// Tensão real na entrada do divisor (mV) a partir da leitura bruta
+// (referência e fator do divisor vêm dos parâmetros `vref` e `correction`)
+pub fn adc_input_mv(params: &Params, adc_value: u16) -> u32 {
+    let vref_mv = params.get(Param::Vref) as u32;
+    let correction = params.get(Param::Correction) as u32; // 1/0.27 ≈ 3.7037 (escalado x10000)
+
+    adc_to_voltage(adc_value, vref_mv) * correction / 10000
+}
+
+// Erros retornados pelos handlers de comando
+#[derive(Debug)]
+pub enum Error<E> {
+    Io(E),          // Falha no transporte (propagada para o shell)
+    UnknownCommand, // Nome de comando inexistente
+    Arg(ArgError),  // Argumento inválido (aponta para o token)
+    Failed { msg: Msg, code: &'static str }, // Falha do comando (mensagem e código json)
+}
+
+impl<E> From<ArgError> for Error<E> {
+    fn from(e: ArgError) -> Self {
+        Error::Arg(e)
+    }
+}
+
+impl<E> From<AuthError> for Error<E> {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::NoAdmin => Error::Failed { msg: Msg::NoAdminPassword, code: "no_admin_password" },
+        }
+    }
+}
+
+impl<E> From<ConfigError> for Error<E> {
+    fn from(e: ConfigError) -> Self {
+        let (msg, code) = match e {
+            ConfigError::Flash => (Msg::StoreFailed, "store_failed"),
+            ConfigError::Full => (Msg::ConfigFull, "config_full"),
+            ConfigError::TooLong => (Msg::ConfigFull, "config_full"),
+            ConfigError::NewerSchema => (Msg::ConfigNewerSchema, "config_newer_schema"),
+        };
+        Error::Failed { msg, code }
+    }
+}
+
+impl<E> From<FileError> for Error<E> {
+    fn from(e: FileError) -> Self {
+        let (msg, code) = file_error(e);
+        Error::Failed { msg, code }
+    }
+}
+
+// Mensagem e código json de uma falha dos arquivos (comandos e transferências)
+fn file_error(e: FileError) -> (Msg, &'static str) {
+    match e {
+        FileError::Full => (Msg::FileFull, "file_full"),
+        FileError::TooMany => (Msg::TooManyFiles, "too_many_files"),
+        FileError::BadName => (Msg::BadFileName, "bad_file_name"),
+        FileError::NotFound => (Msg::FileNotFound, "file_not_found"),
+        FileError::Flash => (Msg::StoreFailed, "store_failed"),
+    }
+}
+
+impl<E> From<MemoryError> for Error<E> {
+    fn from(e: MemoryError) -> Self {
+        let (msg, code) = match e {
+            MemoryError::Unmapped => (Msg::BadAddress, "bad_address"),
+            MemoryError::ReadOnly => (Msg::ReadOnlyAddress, "read_only"),
+            MemoryError::Unaligned => (Msg::UnalignedAddress, "unaligned"),
+            MemoryError::ReadClears => (Msg::ReadClearsAddress, "read_clears"),
+        };
+        Error::Failed { msg, code }
+    }
+}
+
+impl<E> From<PinError> for Error<E> {
+    fn from(e: PinError) -> Self {
+        let (msg, code) = match e {
+            PinError::InUse => (Msg::PinInUse, "pin_in_use"),
+            PinError::NotConfigured => (Msg::PinNotConfigured, "pin_not_configured"),
+            PinError::NotOutput => (Msg::PinNotOutput, "pin_not_output"),
+            PinError::Full => (Msg::PinsFull, "pins_full"),
+        };
+        Error::Failed { msg, code }
+    }
+}
+
+impl<E> From<FlashError> for Error<E> {
+    fn from(_: FlashError) -> Self {
+        Error::Failed { msg: Msg::StoreFailed, code: "store_failed" }
+    }
+}
+
+impl<E> From<ParamError> for Error<E> {
+    fn from(e: ParamError) -> Self {
+        match e {
+            ParamError::OutOfRange { .. } => Error::Failed { msg: Msg::ParamOutOfRange, code: "out_of_range" },
+        }
+    }
+}
+
+impl<E> From<StartupError> for Error<E> {
+    fn from(e: StartupError) -> Self {
+        let (msg, code) = match e {
+            StartupError::Full => (Msg::StartupFull, "startup_full"),
+            StartupError::BadName => (Msg::BadAliasName, "invalid_alias_name"),
+            StartupError::NotFound => (Msg::NotFound, "not_found"),
+        };
+        Error::Failed { msg, code }
+    }
+}
+
+// Estado da aplicação, compartilhado entre as sessões (uma por transporte):
+// hardware, aliases, autoexec, senhas, parâmetros, arquivos e pinos
+pub struct App<B> {
+    pub board: B,
+    pub startup: Startup,
+    pub auth: Auth,
+    pub params: Params,
+    pub files: Files,
+    pub pins: Pins, // A aplicação registra os pinos dos periféricos (`Pins::claim`)
+}
+
+impl<B: Board> App<B> {
+    pub const fn new(board: B) -> Self {
+        Self { board, startup: Startup::new(), auth: Auth::new(), params: Params::new(), files: Files::new(), pins: Pins::new() }
+    }
+
+    // Lê aliases, autoexec, senhas e parâmetros da memória persistente (uma vez,
+    // antes das sessões)
+    pub fn load(&self) {
+        if let Some(store) = self.board.config_flash().and_then(|flash| ConfigStore::open(flash).ok()) {
+            let _ = self.params.load(&store);
+        }
+        let mut startup = [0u8; STARTUP_BYTES];
+        self.board.load_startup(&mut startup);
+        self.startup.load(&startup);
+        let mut credentials = [0u8; CREDENTIALS_BYTES];
+        self.board.load_credentials(&mut credentials);
+        self.auth.load(&credentials);
+    }
+
+    // Idioma escolhido com `lang` (None: nunca escolhido ou placa sem configuração)
+    pub fn saved_lang(&self) -> Option<Lang> {
+        let store = ConfigStore::open(self.board.config_flash()?).ok()?;
+        let mut code = [0u8; 1];
+        match store.get(LANG_KEY, &mut code) {
+            Ok(Some(1)) => Lang::from_code(code[0]),
+            _ => None,
+        }
+    }
+}
+
+// Estado de uma sessão compartilhado com os seus jobs: saída e tabela de jobs
+pub struct Shared<'a, W, B> {
+    pub out: Output<W>,
+    pub app: &'a App<B>,
+    pub jobs: Jobs,
+}
+
+impl<'a, W: Write, B: Board> Shared<'a, W, B> {
+    pub const fn new(io: W, app: &'a App<B>) -> Self {
+        Self { out: Output::new(io), app, jobs: Jobs::new() }
+    }
+}
+
+// Contexto passado aos handlers: transporte, hardware e estado do shell
+pub struct Context<'a, T, B> {
+    pub io: &'a mut T,
+    pub board: &'a B,
+    pub history: &'a dyn Recall,
+    pub settings: &'a mut Settings,
+    pub reply: Reply,      // Resposta JSON do comando atual
+    pub jobs: &'a Jobs,    // Jobs em segundo plano (`jobs`, `kill`)
+    pub job: Option<usize>, // Id do job quando roda em segundo plano
+    pub startup: &'a Startup, // Aliases e autoexec (`alias`, `autoexec`)
+    pub auth: &'a Auth,       // Senhas (`passwd`)
+    pub params: &'a Params,   // Parâmetros (`get`, `set`)
+    pub files: &'a Files,     // Arquivos (`rx`, `sx`, `files`)
+    pub pins: &'a Pins,       // Registro de pinos (`gpio`)
+}
+
+impl<T: Write, B> Context<'_, T, B> {
+    // Escreve um texto no transporte ('\n' vira o fim de linha configurado)
+    pub async fn write_text(&mut self, s: &str) -> Result<(), T::Error> {
+        write_text(self.io, s.as_bytes(), self.settings.output_eol).await
+    }
+
+    // Texto para humanos, no formato de erro dos handlers (suprimido no modo json)
+    pub async fn write_str(&mut self, s: &str) -> Result<(), Error<T::Error>> {
+        if self.json() {
+            return Ok(());
+        }
+        self.write_text(s).await.map_err(Error::Io)
+    }
+
+    // Escreve uma mensagem do catálogo no idioma da sessão (suprimida no modo json)
+    pub async fn write_msg(&mut self, msg: Msg) -> Result<(), Error<T::Error>> {
+        self.write_str(msg.text(self.settings.lang)).await
+    }
+
+    // Respostas em JSON Lines
+    pub fn json(&self) -> bool {
+        self.settings.mode == OutputMode::Json
+    }
+
+    // Campos da resposta JSON (ignorados no modo texto)
+    pub async fn field_str(&mut self, key: &str, value: &str) -> Result<(), Error<T::Error>> {
+        if !self.json() {
+            return Ok(());
+        }
+        self.reply.str(self.io, key, value).await.map_err(Error::Io)
+    }
+
+    pub async fn field_int(&mut self, key: &str, value: i64) -> Result<(), Error<T::Error>> {
+        if !self.json() {
+            return Ok(());
+        }
+        self.reply.int(self.io, key, value).await.map_err(Error::Io)
+    }
+
+    pub async fn field_bool(&mut self, key: &str, value: bool) -> Result<(), Error<T::Error>> {
+        if !self.json() {
+            return Ok(());
+        }
+        self.reply.bool(self.io, key, value).await.map_err(Error::Io)
+    }
+
+    // Listas na resposta JSON (ver `json::Reply`)
+    pub async fn begin_array(&mut self, key: &str) -> Result<(), Error<T::Error>> {
+        if !self.json() {
+            return Ok(());
+        }
+        self.reply.begin_array(self.io, key).await.map_err(Error::Io)
+    }
+
+    pub async fn end_array(&mut self) -> Result<(), Error<T::Error>> {
+        if !self.json() {
+            return Ok(());
+        }
+        self.reply.end_array(self.io).await.map_err(Error::Io)
+    }
+
+    pub async fn item_str(&mut self, value: &str) -> Result<(), Error<T::Error>> {
+        if !self.json() {
+            return Ok(());
+        }
+        self.reply.item_str(self.io, value).await.map_err(Error::Io)
+    }
+
+    pub async fn begin_item(&mut self) -> Result<(), Error<T::Error>> {
+        if !self.json() {
+            return Ok(());
+        }
+        self.reply.begin_item(self.io).await.map_err(Error::Io)
+    }
+
+    pub async fn end_item(&mut self) -> Result<(), Error<T::Error>> {
+        if !self.json() {
+            return Ok(());
+        }
+        self.reply.end_item(self.io).await.map_err(Error::Io)
+    }
+
+    // Encerra um objeto intermediário (ex: uma amostra de um comando contínuo)
+    pub async fn end_record(&mut self) -> Result<(), Error<T::Error>> {
+        if !self.json() {
+            return Ok(());
+        }
+        self.reply.end(self.io, self.settings.output_eol.bytes()).await.map_err(Error::Io)
+    }
+}
+
+// Função para processar uma linha: comandos encadeados com `;`/`&&`, cada um
+// despachado pela tabela de comandos (diretamente ou por `repeat`/`watch`)
+pub async fn process_command<T: Write, B: Board>(
+    line: &str,
+    ctx: &mut Context<'_, T, B>,
+) -> Result<(), T::Error> {
+    let mut ok = true;
+    for (then, segment) in Chain::new(line) {
+        if then == Then::IfOk && !ok {
+            continue; // `a && b` com `a` falhando
+        }
+        ok = run_segment(segment.trim(), ctx).await?;
+    }
+    Ok(())
+}
+
+// Executa um comando da cadeia; retorna se teve sucesso
+async fn run_segment<T: Write, B: Board>(
+    segment: &str,
+    ctx: &mut Context<'_, T, B>,
+) -> Result<bool, T::Error> {
+    // Erros de tokenização são mostrados pelo despacho normal
+    let Ok(tokens) = Tokens::parse(segment) else {
+        return dispatch(segment, ctx).await;
+    };
+    let Some((command, position)) = tokens
+        .get(0)
+        .and_then(|name| registry::find(name.text))
+        .and_then(|command| Some((command, command.command_line()?)))
+    else {
+        return dispatch(segment, ctx).await;
+    };
+
+    // repeat/watch: valida os próprios argumentos e separa o comando interno
+    let args = tokens.args();
+    ctx.reply = Reply::new(Some(command.name)).with_job(ctx.job);
+    if let Err(e) = command.check_args(&args) {
+        return report(ctx, segment, Some(command), Err(Error::Arg(e))).await;
+    }
+    let Some(inner) = args.get(position) else {
+        return Ok(false); // Garantido por `check_args`
+    };
+    if registry::find(inner.text).is_some_and(|c| c.command_line().is_some()) {
+        let nested = Err(Error::Failed { msg: Msg::NestedLoop, code: "nested_loop" });
+        return report(ctx, segment, Some(command), nested).await;
+    }
+    let start = segment.char_indices().nth(inner.col).map_or(segment.len(), |(i, _)| i);
+    let inner = &segment[start..];
+
+    // repeat para no primeiro erro; watch só termina com Ctrl-C ou `kill`
+    let (count, interval_ms) = match command.handler {
+        Handler::Watch => (None, args.fixed(0, Msg::ArgInterval, TIME, WATCH_MIN_MS, WATCH_MAX_MS).unwrap_or(WATCH_MIN_MS)),
+        _ => (Some(args.int(0, Msg::ArgCount, 1, REPEAT_MAX).unwrap_or(1)), 0),
+    };
+    let mut runs = 0;
+    loop {
+        let ok = dispatch(inner, ctx).await?;
+        runs += 1;
+        match count {
+            Some(_) if !ok => return Ok(false),
+            Some(count) if runs >= count => return Ok(true),
+            _ => {},
+        }
+        if interval_ms > 0 {
+            ctx.board.delay_ms(interval_ms as u64).await;
+        }
+    }
+}
+
+// Despacha um comando pela tabela; retorna se teve sucesso
+async fn dispatch<T: Write, B: Board>(
+    cmd: &str,
+    ctx: &mut Context<'_, T, B>,
+) -> Result<bool, T::Error> {
+    // Separa o nome do comando e os argumentos
+    let tokens = match Tokens::parse(cmd) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            ctx.reply = Reply::new(None).with_job(ctx.job);
+            return report(ctx, cmd, None, Err(Error::Arg(e))).await;
+        },
+    };
+    let Some(name) = tokens.get(0) else {
+        return Ok(true); // Comando vazio (não faz nada)
+    };
+    let args = tokens.args();
+
+    let command = registry::find(name.text);
+    ctx.reply = Reply::new(command.map(|c| c.name)).with_job(ctx.job);
+    let result = match command {
+        Some(command) if command.level > ctx.settings.level => {
+            Err(Error::Failed { msg: Msg::PermissionDenied, code: "permission_denied" })
+        },
+        Some(command) => match command.check_args(&args) {
+            Ok(()) => match command.handler.call(ctx, &args).await {
+                Some(result) => result,
+                // repeat/watch só chegam aqui dentro de outro (ver `run_segment`)
+                None => Err(Error::Failed { msg: Msg::NestedLoop, code: "nested_loop" }),
+            },
+            Err(e) => Err(Error::Arg(e)),
+        },
+        None => Err(Error::UnknownCommand),
+    };
+    report(ctx, cmd, command, result).await
+}
+
+// Converte erros de comando em mensagens (ou no objeto JSON); erros de
+// transporte sobem. Retorna se o comando teve sucesso
+async fn report<T: Write, B>(
+    ctx: &mut Context<'_, T, B>,
+    cmd: &str,
+    command: Option<&Command>,
+    result: Result<(), Error<T::Error>>,
+) -> Result<bool, T::Error> {
+    let ok = result.is_ok();
+    if ctx.json() {
+        report_json(ctx, result).await?;
+        return Ok(ok);
+    }
+    match result {
+        Ok(()) => {},
+        Err(Error::Io(e)) => return Err(e),
+        Err(Error::UnknownCommand) => {
+            ctx.write_text(Msg::UnknownCommand.text(ctx.settings.lang)).await?
+        },
+        Err(Error::Arg(e)) => report_arg_error(ctx, cmd, &e, command.map(|c| c.usage)).await?,
+        Err(Error::Failed { msg, .. }) => ctx.write_text(msg.text(ctx.settings.lang)).await?,
+    }
+    Ok(ok)
+}
+
+// Fecha a resposta JSON com o resultado do comando
+async fn report_json<T: Write, B>(
+    ctx: &mut Context<'_, T, B>,
+    result: Result<(), Error<T::Error>>,
+) -> Result<(), T::Error> {
+    let error = match result {
+        Ok(()) => None,
+        Err(Error::Io(e)) => return Err(e),
+        Err(Error::UnknownCommand) => Some(("unknown_command", None, None)),
+        Err(Error::Arg(e)) => Some((e.kind.code(), Some(e), None)),
+        Err(Error::Failed { msg, code }) => Some((code, None, Some(msg))),
+    };
+    ctx.reply.bool(ctx.io, "ok", error.is_none()).await?;
+    if let Some((code, arg, msg)) = error {
+        ctx.reply.str(ctx.io, "error", code).await?;
+        if let Some(arg) = arg {
+            let mut message: String<96> = String::new();
+            let _ = write!(message, "{}", arg.kind.describe(ctx.settings.lang));
+            ctx.reply.int(ctx.io, "col", arg.col as i64).await?;
+            ctx.reply.str(ctx.io, "message", &message).await?;
+        }
+        if let Some(msg) = msg {
+            ctx.reply.str(ctx.io, "message", msg.text(ctx.settings.lang).trim_end()).await?;
+        }
+    }
+    ctx.reply.end(ctx.io, ctx.settings.output_eol.bytes()).await
+}
+
+// Mostra a linha com um marcador sob o token com problema, a mensagem e o uso
+async fn report_arg_error<T: Write, B>(
+    ctx: &mut Context<'_, T, B>,
+    line: &str,
+    error: &ArgError,
+    usage: Option<Msg>,
+) -> Result<(), T::Error> {
+    ctx.write_text("  ").await?;
+    ctx.write_text(line).await?;
+    ctx.write_text("\n  ").await?;
+    for _ in 0..error.col {
+        ctx.write_text(" ").await?;
+    }
+    for _ in 0..error.width.max(1) {
+        ctx.write_text("^").await?;
+    }
+
+    let mut message: String<96> = String::new();
+    let _ = writeln!(message, " {}", error.kind.describe(ctx.settings.lang));
+    ctx.write_text(&message).await?;
+
+    if let Some(usage) = usage {
+        ctx.write_text(Msg::Usage.text(ctx.settings.lang)).await?;
+        ctx.write_text(usage.text(ctx.settings.lang)).await?;
+        ctx.write_text("\n").await?;
+    }
+    Ok(())
+}
+
+// Shell completo: transporte + hardware + editor de linha + histórico
+// (`H` linhas de histórico, linhas de até `L` bytes UTF-8). A entrada é lida
+// só pelo shell; a saída fica em `Shared`, usada também pelos jobs, e o hardware
+// em `App`, comum a todas as sessões
+pub struct Shell<'s, R, W, B, const H: usize = HISTORY_LEN, const L: usize = LINE_LEN> {
+    rx: R,                        // Entrada do transporte (UART, RTT, mock, ...)
+    shared: &'s Shared<'s, W, B>, // Saída, jobs e estado da aplicação
+    utf8: Utf8Decoder,      // Bytes recebidos -> caracteres
+    keys: KeyDecoder,       // Decodificador de sequências ANSI
+    editor: LineEditor<L>,  // Linha sendo digitada
+    history: History<H, L>, // Últimos comandos executados
+    tabbed: bool,        // Última tecla foi um Tab sem completação (lista no segundo)
+    overflow: bool,      // A linha atual recusou caracteres (será descartada)
+    settings: Settings,  // Echo e fins de linha desta sessão
+    eol: EolFilter,      // Normalização do Enter recebido
+    framed: bool,        // Outro lado fala o protocolo binário
+    link: Link,          // Estado do protocolo binário
+    autoexec: bool,      // Executa o script de inicialização ao começar
+    history_store: bool, // Guarda o histórico na memória da placa (uma sessão só)
+    new_password: Option<Line<L>>, // Primeira digitação da nova senha (`passwd`)
+}
+
+// Como terminou um comando em primeiro plano
+enum Outcome<E> {
+    Done(Result<(), E>),
+    Interrupted, // Ctrl-C
+    Closed,      // Fim da entrada
+}
+
+impl<'s, R, W, B> Shell<'s, R, W, B>
+where
+    R: Read<Error = W::Error>,
+    W: Write,
+    B: Board,
+{
+    // Cria o shell sobre a entrada e o estado compartilhado (histórico padrão)
+    pub fn new(rx: R, shared: &'s Shared<'s, W, B>) -> Self {
+        Self::with_history(rx, shared)
+    }
+}
+
+impl<'s, R, W, B, const H: usize, const L: usize> Shell<'s, R, W, B, H, L>
+where
+    R: Read<Error = W::Error>,
+    W: Write,
+    B: Board,
+{
+    // Cria o shell com outras capacidades (ex: `Shell::<_, _, _, 32, 256>::with_history`)
+    pub fn with_history(rx: R, shared: &'s Shared<'s, W, B>) -> Self {
+        Self {
+            rx,
+            shared,
+            utf8: Utf8Decoder::new(),
+            keys: KeyDecoder::new(),
+            editor: LineEditor::new(),
+            history: History::new(),
+            tabbed: false,
+            overflow: false,
+            settings: Settings::default(),
+            eol: EolFilter::default(),
+            framed: false,
+            link: Link::new(),
+            autoexec: true,
+            history_store: true,
+            new_password: None,
+        }
+    }
+
+    // Liga/desliga o autoexec (ex: botão pressionado no reset)
+    pub fn set_autoexec(&mut self, enabled: bool) {
+        self.autoexec = enabled;
+    }
+
+    fn tx(&self) -> Tx<'s, W> {
+        self.shared.out.tx()
+    }
+
+    // Liga/desliga o histórico persistente (a placa guarda o de uma sessão só)
+    pub fn set_history_store(&mut self, enabled: bool) {
+        self.history_store = enabled;
+    }
+
+    // Escreve um texto convertendo '\n' no fim de linha configurado
+    async fn write(&self, text: &[u8]) -> Result<(), W::Error> {
+        write_text(&mut self.tx(), text, self.settings.output_eol).await
+    }
+
+    // Escreve o redesenho do editor (suprimido com echo desligado, no modo json
+    // e durante a digitação de senhas)
+    async fn write_echo(&self, echo: &Echo<L>) -> Result<(), W::Error> {
+        if self.settings.echo && !self.json() && !self.secret() {
+            for chunk in echo.chunks() {
+                self.write(chunk).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Escreve uma mensagem do catálogo no idioma da sessão
+    async fn write_msg(&self, msg: Msg) -> Result<(), W::Error> {
+        self.write(msg.text(self.settings.lang).as_bytes()).await
+    }
+
+    fn json(&self) -> bool {
+        self.settings.mode == OutputMode::Json
+    }
+
+    // A próxima linha é uma senha (login ou `passwd`)
+    fn secret(&self) -> bool {
+        self.settings.level == Level::Guest || self.settings.passwd.is_some()
+    }
+
+    // Prompt antes do próximo comando ou senha (não existe no modo json)
+    async fn prompt(&self) -> Result<(), W::Error> {
+        if self.json() {
+            return Ok(());
+        }
+        match (self.settings.passwd, &self.new_password) {
+            (Some(_), None) => self.write_msg(Msg::NewPasswordPrompt).await,
+            (Some(_), Some(_)) => self.write_msg(Msg::RepeatPasswordPrompt).await,
+            _ if self.settings.level == Level::Guest => self.write_msg(Msg::PasswordPrompt).await,
+            _ => self.write(PROMPT.as_bytes()).await,
+        }
+    }
+
+    // Erro do próprio shell (fora de um comando): mensagem ou objeto JSON
+    async fn shell_error(&self, msg: Msg, code: &str) -> Result<(), W::Error> {
+        if !self.json() {
+            return self.write_msg(msg).await;
+        }
+        let mut tx = self.tx();
+        let mut reply = Reply::new(None);
+        reply.bool(&mut tx, "ok", false).await?;
+        reply.str(&mut tx, "error", code).await?;
+        reply.end(&mut tx, self.settings.output_eol.bytes()).await
+    }
+
+    // Mensagem de boas-vindas (restaura o histórico, o idioma, os aliases e as senhas salvos)
+    pub async fn start(&mut self) -> Result<(), W::Error> {
+        if self.history_store {
+            let history = &mut self.history;
+            self.shared.app.board.with_history_store(&mut |store| history.load(store));
+        }
+        if let Some(lang) = self.shared.app.saved_lang() {
+            self.settings.lang = lang;
+        }
+
+        self.write(b"\n=== STM32F407 Shell Terminal ===\n").await?;
+        self.write_msg(Msg::Banner).await
+    }
+
+    // Trata um byte de texto: decodificação UTF-8 e edição/echo; retorna a linha
+    // completada pelo Enter
+    async fn handle_byte(&mut self, byte: u8) -> Result<Option<Line<L>>, W::Error> {
+        let mut line = None;
+        for c in self.utf8.feed(byte) {
+            if let Some(done) = self.handle_char(c).await? {
+                line = Some(done);
+            }
+        }
+        Ok(line)
+    }
+
+    // Trata um caractere decodificado
+    async fn handle_char(&mut self, c: char) -> Result<Option<Line<L>>, W::Error> {
+        let Some(c) = self.eol.feed(c, self.settings.input_eol) else {
+            return Ok(None); // Metade de um par \n
+        };
+        let Some(key) = self.keys.feed(c) else {
+            return Ok(None); // Sequência de escape incompleta
+        };
+
+        let mut echo = Echo::new();
+        let history: &dyn Recall = if self.secret() { &NoHistory } else { &self.history };
+        let event = self.editor.handle(key, &mut echo, history);
+        self.write_echo(&echo).await?;
+
+        let tabbed = core::mem::take(&mut self.tabbed);
+        match event {
+            Some(Event::Line(_)) if core::mem::take(&mut self.overflow) => {
+                // Não executa uma linha truncada
+                self.shell_error(Msg::LineDiscarded, "line_too_long").await?;
+                self.prompt().await?;
+            },
+            Some(Event::Line(line)) => return Ok(Some(line)),
+            Some(Event::Interrupt) => {
+                self.overflow = false;
+                if self.settings.level > Level::Guest {
+                    self.settings.passwd = None; // Desiste do `passwd`
+                    self.new_password = None;
+                }
+                self.prompt().await?;
+            },
+            Some(Event::Complete) if self.json() || self.secret() => {}, // Só para comandos no terminal
+            Some(Event::Complete) => self.complete(tabbed).await?,
+            Some(Event::TooLong) if self.json() => self.overflow = true, // O erro sai no Enter
+            Some(Event::TooLong) if self.overflow => self.write(b"\x07").await?,
+            Some(Event::TooLong) => {
+                // Avisa uma vez e redesenha a linha (os caracteres seguintes só tocam o sino)
+                self.overflow = true;
+                let mut message: String<64> = String::new();
+                let _ = write!(message, "\n{} {} bytes)\n", Msg::LineTooLong.text(self.settings.lang), L);
+                self.write(message.as_bytes()).await?;
+                self.prompt().await?;
+                let mut echo = Echo::new();
+                self.editor.redraw(&mut echo);
+                self.write_echo(&echo).await?;
+            },
+            None => {},
+        }
+        Ok(None)
+    }
+
+    // Completa a palavra sob o cursor; no segundo Tab lista os candidatos
+    async fn complete(&mut self, tabbed: bool) -> Result<(), W::Error> {
+        let before = self.editor.before_cursor();
+        let completion = complete::complete(&before);
+        let mut echo = Echo::new();
+
+        match completion.count {
+            0 => {},
+            1 => {
+                let _ = self.editor.insert_str(completion.insertion(), &mut echo)
+                    && self.editor.insert_str(" ", &mut echo);
+            },
+            _ if tabbed => {
+                // Lista os candidatos e redesenha o prompt com a linha atual
+                let mut list: heapless::Vec<&'static str, 32> = heapless::Vec::new();
+                complete::candidates(&before, |c| {
+                    let _ = list.push(c);
+                });
+                self.write(b"\n").await?;
+                for candidate in list {
+                    self.write(candidate.as_bytes()).await?;
+                    self.write(b"  ").await?;
+                }
+                self.write(b"\n").await?;
+                self.write(PROMPT.as_bytes()).await?;
+                self.editor.redraw(&mut echo);
+            },
+            _ => {
+                // Completa até o prefixo comum; o próximo Tab lista as opções
+                let _ = self.editor.insert_str(completion.insertion(), &mut echo);
+                self.tabbed = true;
+            },
+        }
+        self.write_echo(&echo).await
+    }
+
+    // Expande `!!`/`!n` e registra no histórico; retorna a linha a executar
+    // (None se a referência ao histórico não existir)
+    async fn expand(&mut self, line: &str) -> Result<Option<Line<L>>, W::Error> {
+        let mut expanded: Line<L> = Line::new();
+        match self.history.expand(line) {
+            Some(Ok(recalled)) => {
+                let _ = expanded.push_str(recalled);
+                // Mostra o comando recuperado antes de executá-lo
+                if !self.json() {
+                    self.write(expanded.as_bytes()).await?;
+                    self.write(b"\n").await?;
+                }
+            },
+            Some(Err(())) => {
+                self.shell_error(Msg::EventNotFound, "event_not_found").await?;
+                return Ok(None);
+            },
+            None => {
+                let _ = expanded.push_str(line);
+            },
+        }
+
+        self.history.push(&expanded);
+        let history = &self.history;
+        if self.history_store {
+            self.shared.app.board.with_history_store(&mut |store| history.save(store));
+        }
+        Ok(Some(expanded))
+    }
+
+    // Substitui os aliases; None se a linha expandida não couber
+    async fn expand_aliases(&self, line: &str) -> Result<Option<Line<L>>, W::Error> {
+        let mut expanded: Line<L> = Line::new();
+        if self.shared.app.startup.expand(line, &mut expanded).is_err() {
+            self.shell_error(Msg::LineDiscarded, "line_too_long").await?;
+            return Ok(None);
+        }
+        Ok(Some(expanded))
+    }
+
+    // Mostra uma linha do autoexec depois do prompt (não vai para o histórico)
+    async fn show_script_line(&self, line: &str) -> Result<(), W::Error> {
+        if self.json() {
+            return Ok(());
+        }
+        self.write(line.as_bytes()).await?;
+        self.write(b"\n").await
+    }
+
+    // Avisa o início de um job ("[1] adc cont")
+    async fn report_job(&self, id: usize, line: &str, state: JobState) -> Result<(), W::Error> {
+        let mut tx = self.tx();
+        if self.json() {
+            let mut reply = Reply::new(None).with_job(Some(id));
+            reply.str(&mut tx, "state", state.name()).await?;
+            reply.str(&mut tx, "line", line).await?;
+            return reply.end(&mut tx, self.settings.output_eol.bytes()).await;
+        }
+        let mut prefix: String<24> = String::new();
+        let _ = write!(prefix, "[{}] ", id);
+        self.write(prefix.as_bytes()).await?;
+        if state != JobState::Running {
+            self.write_msg(state.msg()).await?;
+            self.write(b"  ").await?;
+        }
+        self.write(line.as_bytes()).await?;
+        self.write(b"\n").await
+    }
+
+    // Avisa os jobs que terminaram desde o último prompt
+    async fn report_finished(&self) -> Result<(), W::Error> {
+        while let Some((id, job)) = self.shared.jobs.take_finished() {
+            self.report_job(id, &job.line, job.state).await?;
+        }
+        Ok(())
+    }
+
+    // Loop principal: lê um byte por vez até o fim do transporte, executando os
+    // jobs em paralelo com a edição e com o comando em primeiro plano
+    pub async fn run(&mut self) -> Result<(), W::Error> {
+        let shared = self.shared;
+        let mut slots = [const { None }; MAX_JOBS]; // Futures dos jobs
+        let mut typeahead: Deque<u8, TYPEAHEAD_LEN> = Deque::new();
+        let mut buffer = [0u8; 1]; // Buffer para leitura de um caractere por vez
+
+        with_jobs(&shared.jobs, &mut slots, self.start()).await?;
+        // Próxima linha do autoexec (None depois do fim)
+        let mut script = Some(if self.autoexec { 0 } else { MAX_SCRIPT_LINES });
+        self.finish_script(&mut script);
+        with_jobs(&shared.jobs, &mut slots, self.prompt()).await?;
+        loop {
+            let line = match script.and_then(|index| shared.app.startup.script_line(index)) {
+                Some(line) => {
+                    // Script de inicialização: cada linha aparece como se fosse digitada
+                    script = script.map(|index| index + 1);
+                    with_jobs(&shared.jobs, &mut slots, self.show_script_line(&line)).await?;
+                    let mut copy: Line<L> = Line::new();
+                    let _ = copy.push_str(&line);
+                    copy
+                },
+                None => {
+                    let byte = match typeahead.pop_front() {
+                        Some(byte) => byte,
+                        None => {
+                            // Com login, a sessão termina após SESSION_TIMEOUT_MS sem digitação
+                            // (sem login não há timer: só a leitura)
+                            let timed = self.settings.level > Level::Guest && shared.app.auth.enabled();
+                            let rx = &mut self.rx;
+                            let idle = async {
+                                if !timed {
+                                    return Either::First(rx.read(&mut buffer).await);
+                                }
+                                select(rx.read(&mut buffer), shared.app.board.delay_ms(SESSION_TIMEOUT_MS)).await
+                            };
+                            match with_jobs(&shared.jobs, &mut slots, idle).await {
+                                Either::First(Ok(0)) => return Ok(()), // Transporte encerrado
+                                Either::First(Ok(_)) => buffer[0],
+                                Either::First(Err(e)) => return Err(e),
+                                Either::Second(()) => {
+                                    self.end_session();
+                                    with_jobs(&shared.jobs, &mut slots, self.timed_out()).await?;
+                                    continue;
+                                },
+                            }
+                        },
+                    };
+
+                    // Um 0x00 (que um terminal nunca envia) indica um cliente do protocolo
+                    // binário (só depois do login)
+                    if byte == 0 && !self.framed && self.settings.level > Level::Guest {
+                        // Abandona a linha em edição e passa ao modo binário
+                        self.framed = true;
+                        self.overflow = false;
+                        self.editor.clear();
+                        self.utf8 = Utf8Decoder::new();
+                        self.keys = KeyDecoder::new();
+                        self.link.reset();
+                    }
+                    if self.framed {
+                        let mut tx = shared.out.tx();
+                        let action = self.link.handle_byte(&mut self.rx, &mut tx, shared.app, byte);
+                        if with_jobs(&shared.jobs, &mut slots, action).await? == Action::TextMode {
+                            self.framed = false;
+                            with_jobs(&shared.jobs, &mut slots, self.prompt()).await?;
+                        }
+                        continue;
+                    }
+
+                    let Some(line) = with_jobs(&shared.jobs, &mut slots, self.handle_byte(byte)).await? else {
+                        continue;
+                    };
+                    if self.secret() {
+                        // Senha: não passa pelo histórico nem pelo despacho
+                        with_jobs(&shared.jobs, &mut slots, self.handle_secret(&line)).await?;
+                        with_jobs(&shared.jobs, &mut slots, self.prompt()).await?;
+                        continue;
+                    }
+                    let Some(line) = with_jobs(&shared.jobs, &mut slots, self.expand(&line)).await? else {
+                        with_jobs(&shared.jobs, &mut slots, self.prompt()).await?;
+                        continue;
+                    };
+                    line
+                },
+            };
+            let Some(line) = with_jobs(&shared.jobs, &mut slots, self.expand_aliases(&line)).await? else {
+                with_jobs(&shared.jobs, &mut slots, self.prompt()).await?;
+                continue;
+            };
+
+            if let Some(command) = background(&line) {
+                // Segundo plano: o job recebe uma cópia da linha e das configurações
+                match shared.jobs.start(command.trim_end()) {
+                    Some(id) => {
+                        let mut command_line: Line<L> = Line::new();
+                        let _ = command_line.push_str(command);
+                        slots[id - 1] = Some(jobs::run(shared, id, command_line, self.settings));
+                        let report = self.report_job(id, command.trim(), JobState::Running);
+                        with_jobs(&shared.jobs, &mut slots, report).await?;
+                    },
+                    None => {
+                        let error = self.shell_error(Msg::TooManyJobs, "too_many_jobs");
+                        with_jobs(&shared.jobs, &mut slots, error).await?;
+                    },
+                }
+            } else {
+                // Primeiro plano: Ctrl-C descarta o comando; o resto da digitação fica guardado
+                let mut tx = shared.out.tx();
+                let mut ctx = Context {
+                    io: &mut tx,
+                    board: &shared.app.board,
+                    history: &self.history,
+                    settings: &mut self.settings,
+                    reply: Reply::default(),
+                    jobs: &shared.jobs,
+                    job: None,
+                    startup: &shared.app.startup,
+                    auth: &shared.app.auth,
+                    params: &shared.app.params,
+                    files: &shared.app.files,
+                    pins: &shared.app.pins,
+                };
+                let outcome = match select3(
+                    process_command(&line, &mut ctx),
+                    watch_input(&mut self.rx, &mut typeahead),
+                    jobs::poll(&shared.jobs, &mut slots),
+                )
+                .await
+                {
+                    Either3::First(result) => Outcome::Done(result),
+                    Either3::Second(Ok(true)) => Outcome::Interrupted,
+                    Either3::Second(Ok(false)) => Outcome::Closed,
+                    Either3::Second(Err(e)) => Outcome::Done(Err(e)),
+                    Either3::Third(never) => match never {},
+                };
+                match outcome {
+                    Outcome::Done(result) => result?,
+                    Outcome::Interrupted => {
+                        let interrupted = self.interrupted();
+                        with_jobs(&shared.jobs, &mut slots, interrupted).await?;
+                    },
+                    Outcome::Closed => return Ok(()),
+                }
+                if let Some(transfer) = self.settings.transfer.take() {
+                    typeahead.clear(); // Só bytes do protocolo daqui em diante
+                    self.transfer(transfer).await?;
+                }
+            }
+
+            if self.settings.level == Level::Guest {
+                self.end_session(); // `logout`
+            }
+            self.finish_script(&mut script);
+            jobs::reap(&shared.jobs, &mut slots);
+            with_jobs(&shared.jobs, &mut slots, self.report_finished()).await?;
+            with_jobs(&shared.jobs, &mut slots, self.prompt()).await?;
+        }
+    }
+
+    // Fim do autoexec (executado como admin): começa o login
+    fn finish_script(&mut self, script: &mut Option<usize>) {
+        if script.is_some_and(|index| self.shared.app.startup.script_line(index).is_none()) {
+            *script = None;
+            if self.shared.app.auth.enabled() {
+                self.settings.level = Level::Guest;
+            }
+        }
+    }
+
+    // Fim da sessão (logout ou inatividade): encerra os jobs e volta à senha
+    fn end_session(&mut self) {
+        self.settings.passwd = None;
+        self.new_password = None;
+        if !self.shared.app.auth.enabled() {
+            self.settings.level = Level::Admin; // Sem login não há o que encerrar
+            return;
+        }
+        self.settings.level = Level::Guest;
+        self.shared.jobs.kill_all();
+    }
+
+    // Avisa o fim da sessão por inatividade (a linha em edição é descartada)
+    async fn timed_out(&mut self) -> Result<(), W::Error> {
+        self.editor.clear();
+        self.overflow = false;
+        if !self.json() {
+            self.write(b"\n").await?;
+        }
+        self.shell_error(Msg::SessionTimeout, "session_timeout").await?;
+        self.prompt().await
+    }
+
+    // Trata uma senha digitada: login ou as duas digitações de `passwd`
+    async fn handle_secret(&mut self, password: &str) -> Result<(), W::Error> {
+        if !self.json() {
+            self.write(b"\n").await?; // O Enter não foi ecoado
+        }
+
+        let Some(level) = self.settings.passwd else {
+            return self.login(password).await;
+        };
+        let Some(first) = self.new_password.take() else {
+            let mut copy: Line<L> = Line::new();
+            let _ = copy.push_str(password);
+            self.new_password = Some(copy);
+            return Ok(());
+        };
+        self.settings.passwd = None;
+        if first != password {
+            return self.shell_error(Msg::PasswordMismatch, "password_mismatch").await;
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        self.shared.app.board.salt(&mut salt);
+        if self.shared.app.auth.set(level, password, salt).is_err() {
+            return self.shell_error(Msg::NoAdminPassword, "no_admin_password").await;
+        }
+        let mut credentials = [0u8; CREDENTIALS_BYTES];
+        let len = self.shared.app.auth.save(&mut credentials);
+        if !self.shared.app.board.store_credentials(&credentials[..len]) {
+            return self.shell_error(Msg::StoreFailed, "store_failed").await;
+        }
+        self.shell_reply(Msg::PasswordChanged, None).await
+    }
+
+    // Confere a senha do login (o bloqueio após erros seguidos vale para todas
+    // as sessões, ver `Auth::login`)
+    async fn login(&mut self, password: &str) -> Result<(), W::Error> {
+        let app = self.shared.app;
+        match app.auth.login(password, app.board.uptime_ms()) {
+            Ok(level) => {
+                self.settings.level = level;
+                self.shell_reply(Msg::LoggedIn, Some(level)).await
+            },
+            Err(LoginError::Failed) => self.shell_error(Msg::LoginFailed, "login_failed").await,
+            Err(LoginError::Locked) => self.shell_error(Msg::LoginLocked, "login_locked").await,
+        }
+    }
+
+    // Resposta de sucesso do próprio shell (mensagem ou `{"ok":true}`)
+    async fn shell_reply(&self, msg: Msg, level: Option<Level>) -> Result<(), W::Error> {
+        if !self.json() {
+            self.write_msg(msg).await?;
+            if let Some(level) = level {
+                self.write(level.name().as_bytes()).await?;
+                self.write(b"\n").await?;
+            }
+            return Ok(());
+        }
+        let mut tx = self.tx();
+        let mut reply = Reply::new(None);
+        reply.bool(&mut tx, "ok", true).await?;
+        if let Some(level) = level {
+            reply.str(&mut tx, "level", level.name()).await?;
+        }
+        reply.end(&mut tx, self.settings.output_eol.bytes()).await
+    }
+
+    // Executa a transferência pedida por `rx`/`sx` e mostra o resultado (os jobs
+    // ficam parados para não misturar texto nos blocos)
+    async fn transfer(&mut self, transfer: Transfer) -> Result<(), W::Error> {
+        let app = self.shared.app;
+        let mut tx = self.tx();
+        let flash = app.board.boot_flash().filter(|_| transfer.firmware);
+        let result = match (transfer.send, transfer.name, flash) {
+            (true, Some(name), _) => {
+                xmodem::send(&mut self.rx, &mut tx, &app.board, &app.files, transfer.protocol, name).await
+            },
+            (_, _, Some(flash)) => {
+                let mut sink = DfuSink::new(flash);
+                xmodem::receive(&mut self.rx, &mut tx, &app.board, &mut sink, Protocol::Ymodem, None).await
+            },
+            (_, name, None) => {
+                let name = name.as_ref().map(FileName::as_str);
+                let mut sink = FileSink::new(&app.files);
+                xmodem::receive(&mut self.rx, &mut tx, &app.board, &mut sink, transfer.protocol, name).await
+            },
+        };
+        self.utf8 = Utf8Decoder::new();
+        self.keys = KeyDecoder::new();
+        let (msg, code) = match result {
+            Ok(summary) => match flash {
+                Some(flash) => return self.install_firmware(flash, &summary).await,
+                None => return self.transfer_done(&summary).await,
+            },
+            Err(TransferError::Io(e)) => return Err(e),
+            Err(TransferError::Closed) => return Ok(()), // A próxima leitura encerra a sessão
+            Err(TransferError::Cancelled) => (Msg::TransferCancelled, "transfer_cancelled"),
+            Err(TransferError::Timeout) => (Msg::TransferTimeout, "transfer_timeout"),
+            Err(TransferError::Failed) => (Msg::TransferFailed, "transfer_failed"),
+            Err(TransferError::File(e)) => file_error(e),
+        };
+        self.shell_error(msg, code).await
+    }
+
+    // Resultado de uma transferência: arquivos e bytes (e o último nome no json)
+    async fn transfer_done(&self, summary: &Summary) -> Result<(), W::Error> {
+        if !self.json() {
+            self.write_msg(Msg::TransferDone).await?;
+            self.write(itoa::Buffer::new().format(summary.files).as_bytes()).await?;
+            self.write_msg(Msg::TransferFiles).await?;
+            self.write(itoa::Buffer::new().format(summary.bytes).as_bytes()).await?;
+            return self.write(b" bytes\n").await;
+        }
+        let mut tx = self.tx();
+        let mut reply = Reply::new(None);
+        reply.bool(&mut tx, "ok", true).await?;
+        if let Some(name) = &summary.name {
+            reply.str(&mut tx, "name", name.as_str()).await?;
+        }
+        reply.int(&mut tx, "files", summary.files as i64).await?;
+        reply.int(&mut tx, "bytes", summary.bytes as i64).await?;
+        reply.end(&mut tx, self.settings.output_eol.bytes()).await
+    }
+
+    // Imagem recebida pelo `fw update`: verifica, pede a troca e reinicia
+    async fn install_firmware(&self, flash: &dyn BootFlash, summary: &Summary) -> Result<(), W::Error> {
+        match boot::install(flash, summary.bytes) {
+            Ok(Some(_)) => {},
+            Ok(None) => return self.shell_error(Msg::FirmwareInvalid, "firmware_invalid").await,
+            Err(FlashError) => return self.shell_error(Msg::StoreFailed, "store_failed").await,
+        }
+        self.shell_reply(Msg::FirmwareInstalled, None).await?;
+        let board = &self.shared.app.board;
+        board.delay_ms(RESET_DELAY_MS).await;
+        board.reset();
+        Ok(())
+    }
+
+    // Avisa que o comando em primeiro plano foi interrompido
+    async fn interrupted(&self) -> Result<(), W::Error> {
+        if !self.shared.out.at_line_start() {
+            self.write(b"\n").await?; // Termina a linha (ou objeto) deixado pela metade
+        }
+        if self.json() {
+            return self.shell_error(Msg::Interrupted, "interrupted").await;
+        }
+        self.write(b"^C\n").await
+    }
+
+    // Acesso à entrada e ao hardware (útil em testes)
+    pub fn rx(&mut self) -> &mut R {
+        &mut self.rx
+    }
+
+    pub fn board(&self) -> &'s B {
+        &self.shared.app.board
+    }
+}
+
+// Linha terminada em '&' (mas não em '&&' nem em '\&'): comando para segundo plano
+fn background(line: &str) -> Option<&str> {
+    // Número ímpar de '\' antes do fim: o último caractere é literal
+    let escaped = |text: &str| text.bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 1;
+    let command = line.trim_end().strip_suffix('&')?;
+    if escaped(command) {
+        return None;
+    }
+    match command.strip_suffix('&') {
+        Some(before) if !escaped(before) => None,
+        _ => Some(command),
+    }
+}
+
+// Executa `future` mantendo os jobs rodando enquanto ele espera
+async fn with_jobs<F: Future, J, E>(jobs: &Jobs, slots: &mut [Option<J>; MAX_JOBS], future: F) -> F::Output
+where
+    J: Future<Output = Result<(), E>>,
+{
+    match select(future, jobs::poll(jobs, slots)).await {
+        Either::First(output) => output,
+        Either::Second(never) => match never {},
+    }
+}
+
+// Lê a entrada durante um comando: true no Ctrl-C, false no fim do transporte
+// (os outros bytes são guardados para depois do comando)
+async fn watch_input<R: Read, const N: usize>(rx: &mut R, typeahead: &mut Deque<u8, N>) -> Result<bool, R::Error> {
+    let mut buffer = [0u8; 1];
+    loop {
+        if rx.read(&mut buffer).await? == 0 {
+            return Ok(false);
+        }
+        if buffer[0] == INTERRUPT {
+            return Ok(true);
+        }
+        let _ = typeahead.push_back(buffer[0]); // Descarta o excesso
+    }
+}
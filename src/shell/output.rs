@@ -0,0 +1,57 @@
+// Saída compartilhada entre o shell e os jobs em segundo plano: cada escrita
+// trava o transporte só enquanto dura (as linhas de jobs podem se intercalar)
+
+use core::cell::Cell;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex; // Tudo roda na mesma task
+use embassy_sync::mutex::Mutex;
+use embedded_io_async::{ErrorType, Write};
+
+pub struct Output<W> {
+    io: Mutex<NoopRawMutex, W>,
+    line_start: Cell<bool>, // Último byte enviado terminou uma linha
+}
+
+impl<W: Write> Output<W> {
+    pub const fn new(io: W) -> Self {
+        Self { io: Mutex::new(io), line_start: Cell::new(true) }
+    }
+
+    // Referência para escrever (uma por contexto/job)
+    pub fn tx(&self) -> Tx<'_, W> {
+        Tx { output: self }
+    }
+
+    // O cursor está no início de uma linha
+    pub fn at_line_start(&self) -> bool {
+        self.line_start.get()
+    }
+
+    // Acesso direto ao transporte (útil em testes)
+    pub fn get_mut(&mut self) -> &mut W {
+        self.io.get_mut()
+    }
+}
+
+// Escritor que trava a saída a cada escrita
+pub struct Tx<'a, W> {
+    output: &'a Output<W>,
+}
+
+impl<W: Write> ErrorType for Tx<'_, W> {
+    type Error = W::Error;
+}
+
+impl<W: Write> Write for Tx<'_, W> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, W::Error> {
+        let mut io = self.output.io.lock().await;
+        let n = io.write(buf).await?;
+        if n > 0 {
+            self.output.line_start.set(matches!(buf[n - 1], b'\n' | b'\r'));
+        }
+        Ok(n)
+    }
+
+    async fn flush(&mut self) -> Result<(), W::Error> {
+        self.output.io.lock().await.flush().await
+    }
+}
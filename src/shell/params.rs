@@ -0,0 +1,193 @@
+// Parâmetros de execução tipados (`get`, `set`, `params`): nome, unidade,
+// limites e valor padrão numa tabela única; as tasks leem o valor atual a
+// cada ciclo, então uma mudança vale sem reiniciar
+
+use core::cell::Cell;
+
+use super::args::{Unit, TIME, VOLTAGE};
+use super::config::{ConfigError, ConfigStore};
+use super::i18n::Msg;
+
+// Velocidade serial
+pub static BAUD: &[Unit] = &[Unit { suffix: "bd", scale: 1 }];
+
+// Parâmetros (mesma ordem de `SPECS`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Param {
+    AdcPeriod,   // Intervalo entre leituras do ADC
+    BlinkPeriod, // Tempo aceso/apagado do LED piscante
+    Vref,        // Referência do ADC
+    Correction,  // Fator do divisor resistivo (x10000)
+    Baud,        // Velocidade das UARTs do shell
+}
+
+// Descrição de um parâmetro
+pub struct ParamSpec {
+    pub name: &'static str,      // Nome usado em `get`/`set`
+    pub summary: Msg,            // Descrição curta (`params`)
+    pub units: &'static [Unit],  // Unidades aceitas (vazio = inteiro puro)
+    pub min: i64,
+    pub max: i64,
+    pub default: i64,
+}
+
+impl ParamSpec {
+    // Unidade base exibida junto do valor
+    pub fn unit(&self) -> &'static str {
+        self.units.first().map_or("", |u| u.suffix)
+    }
+}
+
+pub static SPECS: &[ParamSpec] = &[
+    ParamSpec { name: "adc_period", summary: Msg::AdcPeriodParam, units: TIME, min: 10, max: 10_000, default: 100 },
+    ParamSpec { name: "blink_period", summary: Msg::BlinkPeriodParam, units: TIME, min: 50, max: 10_000, default: 500 },
+    ParamSpec { name: "vref", summary: Msg::VrefParam, units: VOLTAGE, min: 1_000, max: 3_600, default: 3_300 },
+    ParamSpec { name: "correction", summary: Msg::CorrectionParam, units: &[], min: 1, max: 100_000, default: 33_333 },
+    ParamSpec { name: "baud", summary: Msg::BaudParam, units: BAUD, min: 1_200, max: 921_600, default: 2_400 },
+];
+
+// Nomes para os argumentos e a completação (mesma ordem de `SPECS`)
+pub const PARAM_NAMES: &[&str] = &["adc_period", "blink_period", "vref", "correction", "baud"];
+
+impl Param {
+    pub const ALL: [Param; 5] = [Param::AdcPeriod, Param::BlinkPeriod, Param::Vref, Param::Correction, Param::Baud];
+
+    pub fn spec(self) -> &'static ParamSpec {
+        &SPECS[self as usize]
+    }
+}
+
+// Falhas ao alterar um parâmetro
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamError {
+    OutOfRange { min: i64, max: i64 },
+}
+
+// Valores atuais (compartilhados entre as sessões e as tasks da aplicação)
+pub struct Params {
+    values: [Cell<i64>; 5],
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Params {
+    pub const fn new() -> Self {
+        Self {
+            values: [
+                Cell::new(SPECS[0].default),
+                Cell::new(SPECS[1].default),
+                Cell::new(SPECS[2].default),
+                Cell::new(SPECS[3].default),
+                Cell::new(SPECS[4].default),
+            ],
+        }
+    }
+
+    pub fn get(&self, param: Param) -> i64 {
+        self.values[param as usize].get()
+    }
+
+    // Altera um valor (na unidade base), conferindo os limites
+    pub fn set(&self, param: Param, value: i64) -> Result<(), ParamError> {
+        let spec = param.spec();
+        if value < spec.min || value > spec.max {
+            return Err(ParamError::OutOfRange { min: spec.min, max: spec.max });
+        }
+        self.values[param as usize].set(value);
+        Ok(())
+    }
+
+    // Volta todos os valores ao padrão
+    pub fn reset(&self) {
+        for param in Param::ALL {
+            self.values[param as usize].set(param.spec().default);
+        }
+    }
+
+    // Aplica os valores gravados (fora dos limites ou ausentes ficam como
+    // estão); retorna quantos foram aplicados
+    pub fn load(&self, store: &ConfigStore) -> Result<usize, ConfigError> {
+        let mut loaded = 0;
+        for param in Param::ALL {
+            let mut value = [0u8; 8];
+            if store.get(param.spec().name, &mut value)? == Some(value.len())
+                && self.set(param, i64::from_le_bytes(value)).is_ok()
+            {
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
+
+    // Grava todos os valores (a flash só é gravada para os que mudaram)
+    pub fn save(&self, store: &mut ConfigStore) -> Result<(), ConfigError> {
+        for param in Param::ALL {
+            store.set(param.spec().name, &self.get(param).to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::config::SimFlash;
+
+    #[test]
+    fn tables_agree() {
+        assert_eq!(SPECS.len(), Param::ALL.len());
+        for (param, name) in Param::ALL.iter().zip(PARAM_NAMES) {
+            assert_eq!(param.spec().name, *name);
+            let spec = param.spec();
+            assert!(spec.min <= spec.default && spec.default <= spec.max, "{}", name);
+        }
+        assert_eq!(Param::Vref.spec().unit(), "mV");
+        assert_eq!(Param::Correction.spec().unit(), "");
+    }
+
+    #[test]
+    fn set_checks_limits() {
+        let params = Params::new();
+        assert_eq!(params.get(Param::BlinkPeriod), 500);
+        params.set(Param::BlinkPeriod, 50).unwrap();
+        assert_eq!(params.set(Param::BlinkPeriod, 49), Err(ParamError::OutOfRange { min: 50, max: 10_000 }));
+        assert_eq!(params.set(Param::BlinkPeriod, 10_001), Err(ParamError::OutOfRange { min: 50, max: 10_000 }));
+        assert_eq!(params.get(Param::BlinkPeriod), 50);
+        params.reset();
+        assert_eq!(params.get(Param::BlinkPeriod), 500);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let flash = SimFlash::<512>::new();
+        let mut store = ConfigStore::format(&flash).unwrap();
+        let params = Params::new();
+        params.set(Param::Vref, 3_000).unwrap();
+        params.set(Param::Baud, 115_200).unwrap();
+        params.save(&mut store).unwrap();
+
+        let restored = Params::new();
+        assert_eq!(restored.load(&ConfigStore::open(&flash).unwrap()), Ok(Param::ALL.len()));
+        assert_eq!(restored.get(Param::Vref), 3_000);
+        assert_eq!(restored.get(Param::Baud), 115_200);
+    }
+
+    #[test]
+    fn load_skips_invalid_values() {
+        let flash = SimFlash::<512>::new();
+        let mut store = ConfigStore::format(&flash).unwrap();
+        store.set("vref", &9_999i64.to_le_bytes()).unwrap(); // Fora dos limites
+        store.set("baud", &[1, 2]).unwrap(); // Tamanho errado
+        store.set("adc_period", &20i64.to_le_bytes()).unwrap();
+
+        let params = Params::new();
+        assert_eq!(params.load(&store), Ok(1));
+        assert_eq!(params.get(Param::AdcPeriod), 20);
+        assert_eq!(params.get(Param::Vref), 3_300);
+        assert_eq!(params.get(Param::Baud), 2_400);
+    }
+}
@@ -0,0 +1,100 @@
+// Registradores de periféricos para o `reg`: layout dos campos com os nomes do
+// `embassy_stm32::pac`. As tabelas são geradas do metadata do stm32-metapac
+// para o chip (ver `build.rs`) e a placa as informa (ver `Board::peripherals`);
+// a leitura passa pelo mapa de memória como no `peek`
+
+// Campo de um registrador; `count` > 1 é uma lista de campos iguais a cada
+// `stride` bits (ex: MODER0..MODER15), numerados a partir de 0
+pub struct Field {
+    pub name: &'static str,
+    pub offset: u8,
+    pub width: u8,
+    pub count: u8,
+    pub stride: u8,
+}
+
+impl Field {
+    // Um bit
+    pub const fn bit(name: &'static str, offset: u8) -> Self {
+        Self { name, offset, width: 1, count: 1, stride: 0 }
+    }
+
+    // Vários bits a partir de `offset`
+    pub const fn bits(name: &'static str, offset: u8, width: u8) -> Self {
+        Self { name, offset, width, count: 1, stride: 0 }
+    }
+
+    // `count` campos de `width` bits a partir de `offset`, a cada `stride` bits
+    pub const fn array(name: &'static str, offset: u8, width: u8, count: u8, stride: u8) -> Self {
+        Self { name, offset, width, count, stride }
+    }
+
+    // Valor do campo `index` (0 se não for lista)
+    pub fn value(&self, register: u32, index: u8) -> u32 {
+        let shift = self.offset as u32 + index as u32 * self.stride as u32;
+        let mask = if self.width >= 32 { u32::MAX } else { (1 << self.width) - 1 };
+        register.checked_shr(shift).unwrap_or(0) & mask
+    }
+}
+
+// Registrador de 32 bits em `offset` do periférico
+pub struct Register {
+    pub name: &'static str,
+    pub offset: u32,
+    pub fields: &'static [Field],
+}
+
+impl Register {
+    pub const fn new(name: &'static str, offset: u32, fields: &'static [Field]) -> Self {
+        Self { name, offset, fields }
+    }
+}
+
+// Instância de um periférico
+pub struct Peripheral {
+    pub name: &'static str,
+    pub base: u32,
+    pub registers: &'static [Register],
+}
+
+impl Peripheral {
+    // Registrador pelo nome (sem diferenciar maiúsculas)
+    pub fn register(&self, name: &str) -> Option<&'static Register> {
+        self.registers.iter().find(|r| r.name.eq_ignore_ascii_case(name))
+    }
+}
+
+// Periférico pelo nome (sem diferenciar maiúsculas)
+pub fn find<'a>(peripherals: &'a [Peripheral], name: &str) -> Option<&'a Peripheral> {
+    peripherals.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_values() {
+        let value = 0b1011_0110_0000_0000_0000_0000_1000_0110;
+        assert_eq!(Field::bit("B1", 1).value(value, 0), 1);
+        assert_eq!(Field::bit("B0", 0).value(value, 0), 0);
+        assert_eq!(Field::bits("LOW", 0, 8).value(value, 0), 0x86);
+        assert_eq!(Field::bits("ALL", 0, 32).value(value, 0), value);
+        // Como o MODER (2 bits por pino) e campos com folga entre si
+        let moder = Field::array("MODER", 0, 2, 16, 2);
+        assert_eq!(moder.value(value, 1), 0b01);
+        assert_eq!(moder.value(value, 15), 0b10);
+        let spaced = Field::array("EN", 4, 1, 4, 8);
+        assert_eq!((0..4).map(|i| spaced.value(0x0000_1010, i)).collect::<std::vec::Vec<_>>(), [1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn lookup_ignores_case() {
+        const REGISTERS: &[Register] = &[Register::new("CR1", 0x0C, &[]), Register::new("BRR", 0x08, &[])];
+        let peripherals = [Peripheral { name: "USART1", base: 0x4001_1000, registers: REGISTERS }];
+        let usart = find(&peripherals, "usart1").unwrap();
+        assert_eq!(usart.register("brr").map(|r| r.offset), Some(0x08));
+        assert!(usart.register("DR").is_none());
+        assert!(find(&peripherals, "usart2").is_none());
+    }
+}
@@ -0,0 +1,113 @@
+// Registro estático de comandos: nome, resumo, uso, argumentos e handler
+// (a ajuda é gerada a partir desta tabela)
+
+use super::args::{ArgError, Args, Unit};
+use super::auth::Level;
+use super::commands::{Handler, COMMANDS};
+use super::i18n::Msg;
+
+// Tipo de um argumento posicional
+pub enum ArgKind {
+    Text,                                                     // Texto livre
+    Int { min: i64, max: i64 },                               // Inteiro decimal/hex
+    Fixed { units: &'static [Unit], min: i64, max: i64 },    // Ponto fixo com unidade
+    Choice(&'static [&'static str]),                          // Um valor da lista
+    Command,                                                  // Nome de comando registrado
+    CommandLine,                                              // Outro comando com seus argumentos (resto da linha)
+}
+
+// Especificação de um argumento posicional
+pub struct ArgSpec {
+    pub name: Msg,          // Nome exibido nas mensagens (no idioma da sessão)
+    pub kind: ArgKind,      // Tipo e limites do valor
+    pub optional: bool,     // Pode ser omitido
+}
+
+impl ArgSpec {
+    // Texto livre obrigatório
+    pub const fn text(name: Msg) -> Self {
+        Self { name, kind: ArgKind::Text, optional: false }
+    }
+
+    // Inteiro obrigatório em [min, max]
+    pub const fn int(name: Msg, min: i64, max: i64) -> Self {
+        Self { name, kind: ArgKind::Int { min, max }, optional: false }
+    }
+
+    // Valor com unidade obrigatório em [min, max] (unidade base)
+    pub const fn fixed(name: Msg, units: &'static [Unit], min: i64, max: i64) -> Self {
+        Self { name, kind: ArgKind::Fixed { units, min, max }, optional: false }
+    }
+
+    // Argumento obrigatório com valores fixos
+    pub const fn choice(name: Msg, choices: &'static [&'static str]) -> Self {
+        Self { name, kind: ArgKind::Choice(choices), optional: false }
+    }
+
+    // Nome de um comando registrado
+    pub const fn command(name: Msg) -> Self {
+        Self { name, kind: ArgKind::Command, optional: false }
+    }
+
+    // Comando completo até o fim da linha (ex: `repeat 3 led toggle`)
+    pub const fn command_line(name: Msg) -> Self {
+        Self { name, kind: ArgKind::CommandLine, optional: false }
+    }
+
+    // Torna o argumento opcional
+    pub const fn opt(self) -> Self {
+        Self { optional: true, ..self }
+    }
+
+    // Valida o argumento `index` conforme o tipo
+    fn check(&self, args: &Args, index: usize) -> Result<(), ArgError> {
+        match self.kind {
+            ArgKind::Text => args.token(index, self.name).map(|_| ()),
+            ArgKind::Int { min, max } => args.int(index, self.name, min, max).map(|_| ()),
+            ArgKind::Fixed { units, min, max } => args.fixed(index, self.name, units, min, max).map(|_| ()),
+            ArgKind::Choice(choices) => args.choice(index, self.name, choices).map(|_| ()),
+            ArgKind::Command | ArgKind::CommandLine => args.token(index, self.name).map(|_| ()),
+        }
+    }
+}
+
+// Entrada da tabela de comandos
+pub struct Command {
+    pub name: &'static str,      // Nome digitado no terminal
+    pub summary: Msg,            // Descrição curta (help)
+    pub usage: Msg,              // Sintaxe completa (help <cmd>)
+    pub args: &'static [ArgSpec], // Argumentos posicionais
+    pub level: Level,            // Privilégio mínimo da sessão
+    pub handler: Handler,        // Função assíncrona que executa o comando
+}
+
+impl Command {
+    // Valida a quantidade e os tipos dos argumentos contra a especificação
+    pub fn check_args(&self, args: &Args) -> Result<(), ArgError> {
+        for (index, spec) in self.args.iter().enumerate() {
+            if spec.optional && args.get(index).is_none() {
+                break;
+            }
+            spec.check(args, index)?;
+            if let ArgKind::CommandLine = spec.kind {
+                return Ok(()); // Os tokens seguintes pertencem ao outro comando
+            }
+        }
+        args.expect_at_most(self.args.len())
+    }
+
+    // Posição do argumento que recebe outro comando (`repeat`, `watch`)
+    pub fn command_line(&self) -> Option<usize> {
+        self.args.iter().position(|spec| matches!(spec.kind, ArgKind::CommandLine))
+    }
+}
+
+// Procura um comando pelo nome
+pub fn find(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+// Todos os comandos registrados
+pub fn all() -> &'static [Command] {
+    COMMANDS
+}
@@ -0,0 +1,180 @@
+// Configurações do terminal: fim de linha na entrada e na saída, echo, idioma,
+// formato das respostas e nível de privilégio da sessão
+
+use embedded_io_async::Write;
+
+use super::auth::Level;
+use super::i18n::Lang;
+use super::xmodem::Transfer;
+
+// Como o Enter chega do terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEol {
+    Cr,   // Só \r encerra a linha (\n é ignorado)
+    Lf,   // Só \n encerra a linha (\r é ignorado)
+    CrLf, // Só o par \r\n encerra a linha
+    Auto, // Qualquer um encerra; o par \r\n ou \n\r conta como um Enter
+}
+
+// Fim de linha enviado ao terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputEol {
+    CrLf,
+    Lf,
+    Cr,
+}
+
+// Formato das respostas dos comandos
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputMode {
+    Human, // Texto para terminal (prompt, echo e mensagens)
+    Json,  // Um objeto JSON por linha, sem prompt nem echo
+}
+
+// Nomes aceitos pelos comandos (mesma ordem dos enums)
+pub const INPUT_EOLS: &[&str] = &["cr", "lf", "crlf", "auto"];
+pub const OUTPUT_EOLS: &[&str] = &["crlf", "lf", "cr"];
+pub const OUTPUT_MODES: &[&str] = &["human", "json"];
+
+impl InputEol {
+    pub const ALL: [InputEol; 4] = [InputEol::Cr, InputEol::Lf, InputEol::CrLf, InputEol::Auto];
+
+    pub fn name(self) -> &'static str {
+        INPUT_EOLS[self as usize]
+    }
+}
+
+impl OutputEol {
+    pub const ALL: [OutputEol; 3] = [OutputEol::CrLf, OutputEol::Lf, OutputEol::Cr];
+
+    pub fn name(self) -> &'static str {
+        OUTPUT_EOLS[self as usize]
+    }
+
+    // Sequência enviada no lugar de cada '\n' do texto
+    pub fn bytes(self) -> &'static [u8] {
+        match self {
+            OutputEol::CrLf => b"\r\n",
+            OutputEol::Lf => b"\n",
+            OutputEol::Cr => b"\r",
+        }
+    }
+}
+
+impl OutputMode {
+    pub const ALL: [OutputMode; 2] = [OutputMode::Human, OutputMode::Json];
+
+    pub fn name(self) -> &'static str {
+        OUTPUT_MODES[self as usize]
+    }
+}
+
+// Configurações de uma sessão do shell
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub echo: bool,            // Ecoa o que é digitado (desligar para scripts)
+    pub input_eol: InputEol,   // Normalização do Enter recebido
+    pub output_eol: OutputEol, // Fim de linha das respostas
+    pub lang: Lang,            // Idioma das mensagens
+    pub mode: OutputMode,      // Texto ou JSON Lines
+    pub level: Level,          // Privilégio da sessão (Guest = aguardando a senha)
+    pub passwd: Option<Level>, // Troca de senha pedida por `passwd` (o shell lê sem eco)
+    pub transfer: Option<Transfer>, // Transferência pedida por `rx`/`sx` (o shell executa)
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            echo: true,
+            input_eol: InputEol::Auto,
+            output_eol: OutputEol::CrLf,
+            lang: Lang::Pt,
+            mode: OutputMode::Human,
+            level: Level::Admin, // Sem senha configurada não há login
+            passwd: None,
+            transfer: None,
+        }
+    }
+}
+
+// Normaliza \r, \n e \r\n recebidos em um único '\r' (Enter)
+#[derive(Default)]
+pub struct EolFilter {
+    last: Option<char>, // Último \r ou \n recebido (para descartar o par)
+}
+
+impl EolFilter {
+    // Retorna o caractere a repassar ao editor (None = descartado)
+    pub fn feed(&mut self, c: char, mode: InputEol) -> Option<char> {
+        let last = self.last.take();
+        match (c, mode) {
+            ('\r', InputEol::Lf) | ('\n', InputEol::Cr) => None,
+            ('\r', InputEol::CrLf) => {
+                self.last = Some(c);
+                None
+            },
+            ('\n', InputEol::CrLf) => (last == Some('\r')).then_some('\r'),
+            ('\r', _) | ('\n', _) => {
+                if mode == InputEol::Auto && matches!((last, c), (Some('\r'), '\n') | (Some('\n'), '\r')) {
+                    return None; // Segunda metade do par
+                }
+                self.last = Some(c);
+                Some('\r')
+            },
+            _ => Some(c),
+        }
+    }
+}
+
+// Escreve um texto trocando cada '\n' pelo fim de linha configurado
+pub async fn write_text<T: Write>(io: &mut T, text: &[u8], eol: OutputEol) -> Result<(), T::Error> {
+    let mut lines = text.split(|&b| b == b'\n');
+    if let Some(first) = lines.next() {
+        io.write_all(first).await?;
+    }
+    for line in lines {
+        io.write_all(eol.bytes()).await?;
+        io.write_all(line).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::testing::{block_on, Mock};
+
+    fn filter(input: &str, mode: InputEol) -> std::string::String {
+        let mut filter = EolFilter::default();
+        input.chars().filter_map(|c| filter.feed(c, mode)).collect()
+    }
+
+    #[test]
+    fn input_line_endings() {
+        assert_eq!(filter("a\rb\nc", InputEol::Cr), "a\rbc");
+        assert_eq!(filter("a\rb\nc", InputEol::Lf), "ab\rc");
+        assert_eq!(filter("a\r\nb\rc\nd", InputEol::CrLf), "a\rbcd");
+        // Auto: cada par conta como um Enter; dois pares, dois Enters
+        assert_eq!(filter("a\r\nb\n\rc\rd\ne", InputEol::Auto), "a\rb\rc\rd\re");
+        assert_eq!(filter("\r\n\r\n", InputEol::Auto), "\r\r");
+        assert_eq!(filter("\r\r", InputEol::Auto), "\r\r");
+    }
+
+    #[test]
+    fn output_line_endings() {
+        for (eol, expected) in [(OutputEol::CrLf, "a\r\n\r\nb\r\n"), (OutputEol::Lf, "a\n\nb\n"), (OutputEol::Cr, "a\r\rb\r")] {
+            let mut mock = Mock::default();
+            block_on(write_text(&mut mock, b"a\n\nb\n", eol)).unwrap();
+            assert_eq!(mock.output, expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn names_follow_the_enums() {
+        for (i, eol) in InputEol::ALL.iter().enumerate() {
+            assert_eq!(eol.name(), INPUT_EOLS[i]);
+        }
+        assert_eq!(OutputEol::Lf.name(), "lf");
+        assert_eq!(OutputMode::Json.name(), "json");
+    }
+}
@@ -0,0 +1,302 @@
+// Aliases (`alias nome = "comando ..."`) e script de inicialização (`autoexec`),
+// guardados juntos na memória persistente da placa (ver `Board::store_startup`)
+
+use core::cell::RefCell;
+use heapless::{String, Vec};
+
+use super::chain::{Chain, Then};
+use super::editor::Line;
+use super::frame::crc16;
+
+// Capacidades
+pub const MAX_ALIASES: usize = 8;
+pub const ALIAS_NAME_LEN: usize = 16;
+pub const MAX_SCRIPT_LINES: usize = 8;
+pub const STARTUP_BYTES: usize = 1024; // Tamanho máximo da cópia persistente
+
+// Cabeçalho da cópia persistente: "SHRC" + tamanho (u16) + CRC-16 (u16)
+const MAGIC: [u8; 4] = *b"SHRC";
+const HEADER_LEN: usize = 8;
+
+// Uma definição de alias
+#[derive(Clone)]
+pub struct Alias {
+    pub name: String<ALIAS_NAME_LEN>,
+    pub value: Line,
+}
+
+// Falhas ao alterar a configuração
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StartupError {
+    Full,     // Tabela cheia, linha longa demais ou não cabe na memória persistente
+    BadName,  // Nome de alias inválido
+    NotFound, // Alias ou linha inexistente
+}
+
+// Aliases e script compartilhados entre o shell e os comandos
+#[derive(Default)]
+pub struct Startup {
+    aliases: RefCell<Vec<Alias, MAX_ALIASES>>,
+    script: RefCell<Vec<Line, MAX_SCRIPT_LINES>>,
+}
+
+impl Startup {
+    pub const fn new() -> Self {
+        Self { aliases: RefCell::new(Vec::new()), script: RefCell::new(Vec::new()) }
+    }
+
+    // Alias pela posição (para listar)
+    pub fn alias(&self, index: usize) -> Option<Alias> {
+        self.aliases.borrow().get(index).cloned()
+    }
+
+    // Valor de um alias pelo nome
+    pub fn find(&self, name: &str) -> Option<Line> {
+        self.aliases.borrow().iter().find(|a| a.name == name).map(|a| a.value.clone())
+    }
+
+    // Define ou substitui um alias
+    pub fn set_alias(&self, name: &str, value: &str) -> Result<(), StartupError> {
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+        if name.is_empty() || !name.chars().all(valid) {
+            return Err(StartupError::BadName);
+        }
+        let mut alias = Alias { name: String::new(), value: Line::new() };
+        alias.name.push_str(name).map_err(|_| StartupError::BadName)?;
+        alias.value.push_str(value).map_err(|_| StartupError::Full)?;
+
+        let previous = self.aliases.borrow().clone();
+        {
+            let mut aliases = self.aliases.borrow_mut();
+            match aliases.iter_mut().find(|a| a.name == name) {
+                Some(existing) => *existing = alias,
+                None => aliases.push(alias).map_err(|_| StartupError::Full)?,
+            }
+        }
+        self.check_fits(|s| *s.aliases.borrow_mut() = previous)
+    }
+
+    // Remove um alias
+    pub fn remove_alias(&self, name: &str) -> Result<(), StartupError> {
+        let mut aliases = self.aliases.borrow_mut();
+        let index = aliases.iter().position(|a| a.name == name).ok_or(StartupError::NotFound)?;
+        aliases.remove(index);
+        Ok(())
+    }
+
+    // Linha do script pela posição
+    pub fn script_line(&self, index: usize) -> Option<Line> {
+        self.script.borrow().get(index).cloned()
+    }
+
+    // Acrescenta uma linha ao fim do script
+    pub fn add_line(&self, line: &str) -> Result<(), StartupError> {
+        let mut copy = Line::new();
+        copy.push_str(line.trim()).map_err(|_| StartupError::Full)?;
+        self.script.borrow_mut().push(copy).map_err(|_| StartupError::Full)?;
+        self.check_fits(|s| {
+            s.script.borrow_mut().pop();
+        })
+    }
+
+    // Remove a linha `index` (0 = primeira)
+    pub fn remove_line(&self, index: usize) -> Result<(), StartupError> {
+        let mut script = self.script.borrow_mut();
+        if index >= script.len() {
+            return Err(StartupError::NotFound);
+        }
+        script.remove(index);
+        Ok(())
+    }
+
+    // Apaga o script
+    pub fn clear_script(&self) {
+        self.script.borrow_mut().clear();
+    }
+
+    // Desfaz a última alteração se o conjunto não couber na memória persistente
+    fn check_fits(&self, undo: impl FnOnce(&Self)) -> Result<(), StartupError> {
+        let mut buf = [0u8; STARTUP_BYTES];
+        if self.save(&mut buf).is_none() {
+            undo(self);
+            return Err(StartupError::Full);
+        }
+        Ok(())
+    }
+
+    // Expande os aliases no início de cada comando da cadeia (um nível, como no
+    // bash: o valor de um alias não é expandido de novo)
+    pub fn expand<const L: usize>(&self, line: &str, out: &mut Line<L>) -> Result<(), StartupError> {
+        let aliases = self.aliases.borrow();
+        let mut push = |text: &str| out.push_str(text).map_err(|_| StartupError::Full);
+        for (i, (then, segment)) in Chain::new(line).enumerate() {
+            if i > 0 {
+                push(if then == Then::IfOk { "&&" } else { ";" })?;
+            }
+            let command = segment.trim_start();
+            let name = command.split_whitespace().next().unwrap_or("");
+            match aliases.iter().find(|a| a.name == name) {
+                Some(alias) => {
+                    push(&segment[..segment.len() - command.len()])?;
+                    push(&alias.value)?;
+                    push(&command[name.len()..])?;
+                },
+                None => push(segment)?,
+            }
+        }
+        Ok(())
+    }
+
+    // Grava aliases e script em `buf` (cada texto terminado em 0; uma entrada
+    // vazia separa os aliases do script); retorna o tamanho usado (None se não couber)
+    pub fn save(&self, buf: &mut [u8]) -> Option<usize> {
+        if buf.len() < HEADER_LEN {
+            return None;
+        }
+        let (header, data) = buf.split_at_mut(HEADER_LEN);
+        let mut len = 0;
+        let mut put = |text: &str| -> Option<()> {
+            let end = len + text.len();
+            data.get_mut(len..end)?.copy_from_slice(text.as_bytes());
+            *data.get_mut(end)? = 0;
+            len = end + 1;
+            Some(())
+        };
+        for alias in self.aliases.borrow().iter() {
+            put(&alias.name)?;
+            put(&alias.value)?;
+        }
+        put("")?;
+        for line in self.script.borrow().iter() {
+            put(line)?;
+        }
+
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..6].copy_from_slice(&(len as u16).to_le_bytes());
+        header[6..8].copy_from_slice(&crc16(&data[..len]).to_le_bytes());
+        Some(HEADER_LEN + len)
+    }
+
+    // Restaura o que `save` gravou (ignora conteúdo inválido, ex: flash apagada)
+    pub fn load(&self, buf: &[u8]) {
+        if buf.len() < HEADER_LEN || buf[..4] != MAGIC {
+            return;
+        }
+        let len = u16::from_le_bytes([buf[4], buf[5]]) as usize;
+        let crc = u16::from_le_bytes([buf[6], buf[7]]);
+        let Some(data) = buf.get(HEADER_LEN..HEADER_LEN + len) else {
+            return;
+        };
+        if crc16(data) != crc {
+            return;
+        }
+
+        let mut texts = data.split(|&b| b == 0).map(|t| core::str::from_utf8(t).unwrap_or(""));
+        while let Some(name) = texts.next().filter(|name| !name.is_empty()) {
+            let _ = self.set_alias(name, texts.next().unwrap_or(""));
+        }
+        for line in texts.filter(|line| !line.is_empty()) {
+            let _ = self.add_line(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(startup: &Startup, line: &str) -> Line {
+        let mut out = Line::new();
+        startup.expand(line, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn aliases() {
+        let startup = Startup::new();
+        startup.set_alias("on", "led on").unwrap();
+        startup.set_alias("st", "status").unwrap();
+        assert_eq!(startup.set_alias("", "x"), Err(StartupError::BadName));
+        assert_eq!(startup.set_alias("a b", "x"), Err(StartupError::BadName));
+        assert_eq!(startup.set_alias("nome-muito-comprido", "x"), Err(StartupError::BadName));
+
+        // Substituir mantém a posição
+        startup.set_alias("on", "led toggle").unwrap();
+        assert_eq!(startup.alias(0).map(|a| a.value), startup.find("on"));
+        assert_eq!(startup.find("on").as_deref(), Some("led toggle"));
+
+        startup.remove_alias("st").unwrap();
+        assert_eq!(startup.remove_alias("st"), Err(StartupError::NotFound));
+        assert!(startup.alias(1).is_none());
+    }
+
+    #[test]
+    fn expansion_per_command_one_level() {
+        let startup = Startup::new();
+        startup.set_alias("on", "led on").unwrap();
+        startup.set_alias("loop", "on; on").unwrap();
+        assert_eq!(expand(&startup, "on"), "led on");
+        assert_eq!(expand(&startup, "status;  on && on x"), "status;  led on && led on x");
+        // O valor não é expandido de novo; argumentos não são aliases
+        assert_eq!(expand(&startup, "loop"), "on; on");
+        assert_eq!(expand(&startup, "help on"), "help on");
+        assert_eq!(expand(&startup, r#"alias x = "on; on""#), r#"alias x = "on; on""#);
+    }
+
+    #[test]
+    fn script_lines() {
+        let startup = Startup::new();
+        startup.add_line("  led on ").unwrap();
+        startup.add_line("status").unwrap();
+        assert_eq!(startup.script_line(0).as_deref(), Some("led on"));
+        startup.remove_line(0).unwrap();
+        assert_eq!(startup.remove_line(1), Err(StartupError::NotFound));
+        assert_eq!(startup.script_line(0).as_deref(), Some("status"));
+        for _ in 1..MAX_SCRIPT_LINES {
+            startup.add_line("status").unwrap();
+        }
+        assert_eq!(startup.add_line("status"), Err(StartupError::Full));
+        startup.clear_script();
+        assert!(startup.script_line(0).is_none());
+    }
+
+    #[test]
+    fn changes_that_do_not_fit_are_undone() {
+        let startup = Startup::new();
+        let long = "x".repeat(127);
+        let mut stored = 0;
+        while startup.set_alias(&std::format!("a{}", stored), &long).is_ok() {
+            stored += 1;
+        }
+        // A tabela aceitaria MAX_ALIASES, mas a cópia persistente não cabe
+        assert!(stored < MAX_ALIASES);
+        assert!(startup.alias(stored).is_none());
+        let mut buf = [0u8; STARTUP_BYTES];
+        assert!(startup.save(&mut buf).is_some());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let startup = Startup::new();
+        startup.set_alias("on", "led on").unwrap();
+        startup.set_alias("empty", "").unwrap();
+        startup.add_line("on").unwrap();
+        startup.add_line("status").unwrap();
+        let mut buf = [0u8; STARTUP_BYTES];
+        let len = startup.save(&mut buf).unwrap();
+
+        let restored = Startup::new();
+        restored.load(&buf[..len]);
+        assert_eq!(restored.find("on").as_deref(), Some("led on"));
+        assert_eq!(restored.find("empty").as_deref(), Some(""));
+        assert_eq!(restored.script_line(1).as_deref(), Some("status"));
+
+        // CRC errado ou flash apagada: nada é carregado
+        buf[HEADER_LEN] ^= 1;
+        let damaged = Startup::new();
+        damaged.load(&buf);
+        damaged.load(&[0xFF; 16]);
+        assert!(damaged.alias(0).is_none() && damaged.script_line(0).is_none());
+        assert_eq!(Startup::new().save(&mut [0u8; 4]), None);
+    }
+}
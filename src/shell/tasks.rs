@@ -0,0 +1,185 @@
+// Monitor das tasks para `ps`/`top`: a aplicação repassa os ganchos de trace
+// do executor (`_embassy_trace_*`) e dá nome às tasks ao criá-las.
+// Dois relógios: ciclos da CPU para a duração dos polls (curtos demais para o
+// timer) e ticks do timer para o tempo ocioso (a CPU dorme e o contador de
+// ciclos para junto)
+
+use heapless::Vec;
+
+// Tasks acompanhadas (as demais são ignoradas)
+pub const MAX_TASKS: usize = 10;
+
+// Nome das tasks criadas sem `name_last`
+const UNNAMED: &str = "?";
+
+// Contadores de uma task
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskStats {
+    pub id: u32, // Endereço da task no executor
+    pub name: &'static str,
+    pub polls: u32,
+    pub busy_cycles: u64, // Soma da duração dos polls
+    pub last_run: u64,    // Tick do fim do último poll (0: nunca rodou)
+}
+
+// Estado do monitor (um por executor)
+#[derive(Clone, Debug)]
+pub struct TaskMonitor {
+    pub tasks: Vec<TaskStats, MAX_TASKS>,
+    pub cycles_hz: u32, // Frequência do contador de ciclos
+    pub ticks_hz: u32,  // Frequência do timer
+    pub idle_ticks: u64, // Soma do tempo ocioso
+    pub now: u64,        // Tick da última atualização
+    running: Option<(usize, u32)>, // Task em poll e o ciclo do início
+    idle_since: Option<u64>,       // Tick em que o executor dormiu
+}
+
+impl TaskMonitor {
+    pub const fn new(cycles_hz: u32, ticks_hz: u32) -> Self {
+        Self {
+            tasks: Vec::new(),
+            cycles_hz,
+            ticks_hz,
+            idle_ticks: 0,
+            now: 0,
+            running: None,
+            idle_since: None,
+        }
+    }
+
+    // Gancho `task_new`
+    pub fn task_new(&mut self, id: u32) {
+        let task = TaskStats { id, name: UNNAMED, polls: 0, busy_cycles: 0, last_run: 0 };
+        let _ = self.tasks.push(task);
+    }
+
+    // Nome da última task criada (chamado logo depois do `spawn`)
+    pub fn name_last(&mut self, name: &'static str) {
+        if let Some(task) = self.tasks.last_mut() {
+            task.name = name;
+        }
+    }
+
+    // Gancho `task_exec_begin`: encerra o período ocioso
+    pub fn exec_begin(&mut self, id: u32, cycles: u32, ticks: u64) {
+        self.wake(ticks);
+        self.running = self.tasks.iter().position(|t| t.id == id).map(|index| (index, cycles));
+    }
+
+    // Gancho `task_exec_end`
+    pub fn exec_end(&mut self, cycles: u32, ticks: u64) {
+        self.now = ticks;
+        if let Some((index, start)) = self.running.take() {
+            let task = &mut self.tasks[index];
+            task.polls = task.polls.wrapping_add(1);
+            task.busy_cycles += cycles.wrapping_sub(start) as u64; // Polls de menos de 25 s a 168 MHz
+            task.last_run = ticks;
+        }
+    }
+
+    // Gancho `executor_idle`: o executor vai dormir (acordar sem task pronta
+    // continua o mesmo período; interrupções contam como ociosas)
+    pub fn idle(&mut self, ticks: u64) {
+        self.now = ticks;
+        self.idle_since.get_or_insert(ticks);
+    }
+
+    // Atualiza o tempo ocioso até `ticks` (antes de uma cópia para `ps`/`top`)
+    pub fn update(&mut self, ticks: u64) {
+        self.now = ticks;
+        if let Some(since) = self.idle_since.as_mut() {
+            self.idle_ticks += ticks - *since;
+            *since = ticks;
+        }
+    }
+
+    fn wake(&mut self, ticks: u64) {
+        self.update(ticks);
+        self.idle_since = None;
+    }
+
+    // Contadores da task `id`
+    pub fn task(&self, id: u32) -> Option<&TaskStats> {
+        self.tasks.iter().find(|t| t.id == id)
+    }
+
+    // Ciclos em microssegundos
+    pub fn cycles_us(&self, cycles: u64) -> u64 {
+        cycles * 1_000_000 / self.cycles_hz.max(1) as u64
+    }
+
+    // Ticks em milissegundos
+    pub fn ticks_ms(&self, ticks: u64) -> u64 {
+        ticks * 1000 / self.ticks_hz.max(1) as u64
+    }
+}
+
+// Carga da CPU em décimos de porcento entre duas leituras: tudo o que não foi
+// ocioso no intervalo (0 se o intervalo é vazio)
+pub fn load_permille(before: &TaskMonitor, after: &TaskMonitor) -> u32 {
+    let elapsed = after.now.saturating_sub(before.now);
+    let idle = after.idle_ticks.saturating_sub(before.idle_ticks).min(elapsed);
+    if elapsed == 0 {
+        return 0;
+    }
+    ((elapsed - idle) * 1000 / elapsed) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Monitor com ciclos a 1 MHz (1 ciclo = 1 µs) e ticks a 1 kHz (1 tick = 1 ms)
+    fn monitor() -> TaskMonitor {
+        let mut monitor = TaskMonitor::new(1_000_000, 1_000);
+        monitor.task_new(0x100);
+        monitor.name_last("shell");
+        monitor.task_new(0x200);
+        monitor
+    }
+
+    #[test]
+    fn polls_and_busy_cycles() {
+        let mut monitor = monitor();
+        assert_eq!(monitor.task(0x200).map(|t| t.name), Some(UNNAMED));
+        monitor.exec_begin(0x100, 1_000, 1);
+        monitor.exec_end(1_250, 2);
+        // O contador de ciclos de 32 bits dá a volta no meio do poll
+        monitor.exec_begin(0x100, u32::MAX - 49, 3);
+        monitor.exec_end(50, 3);
+        let shell = monitor.task(0x100).unwrap();
+        assert_eq!((shell.polls, shell.busy_cycles, shell.last_run), (2, 350, 3));
+        assert_eq!(monitor.cycles_us(shell.busy_cycles), 350);
+        // Task desconhecida não conta
+        monitor.exec_begin(0x300, 0, 4);
+        monitor.exec_end(10, 4);
+        assert!(monitor.task(0x300).is_none());
+    }
+
+    #[test]
+    fn idle_time_and_load() {
+        let mut monitor = monitor();
+        monitor.idle(0);
+        let before = monitor.clone();
+        // Ocioso de 0 a 600 ms (acordar sem task pronta não encerra o período)
+        monitor.idle(300);
+        monitor.exec_begin(0x100, 0, 600);
+        monitor.exec_end(100, 800);
+        monitor.idle(800);
+        monitor.update(1_000);
+        assert_eq!(monitor.idle_ticks, 800);
+        assert_eq!(monitor.ticks_ms(monitor.now), 1_000);
+        assert_eq!(load_permille(&before, &monitor), 200);
+        assert_eq!(load_permille(&monitor, &monitor), 0);
+    }
+
+    #[test]
+    fn table_is_bounded() {
+        let mut monitor = TaskMonitor::new(1, 1);
+        for id in 0..=MAX_TASKS as u32 {
+            monitor.task_new(id);
+        }
+        assert_eq!(monitor.tasks.len(), MAX_TASKS);
+        assert!(monitor.task(MAX_TASKS as u32).is_none());
+    }
+}
@@ -0,0 +1,120 @@
+// Transporte e placa simulados para os testes no host
+// (`cargo test --lib --target x86_64-unknown-linux-gnu`, ou o alvo do host)
+
+use core::cell::{Cell, RefCell};
+use core::convert::Infallible;
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use std::collections::VecDeque;
+use std::string::String;
+use std::vec::Vec;
+
+use embedded_io_async::{ErrorType, Read, Write};
+
+use super::config::{ConfigFlash, SimFlash};
+use super::{App, Board, Shared, Shell};
+
+// Executa uma future até o fim (os mocks nunca esperam de verdade: basta
+// chamar `poll` de novo)
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(value) = future.as_mut().poll(&mut cx) {
+            return value;
+        }
+    }
+}
+
+// Devolve o controle uma vez ao executor (deixa o `select` ver o outro lado)
+pub async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|_| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        Poll::Pending
+    })
+    .await
+}
+
+// Transporte em memória: entrega `input` um byte por leitura e guarda a saída
+#[derive(Default)]
+pub struct Mock {
+    pub input: VecDeque<u8>,
+    pub output: Vec<u8>,
+}
+
+impl Mock {
+    pub fn new(input: &[u8]) -> Self {
+        Self { input: input.iter().copied().collect(), output: Vec::new() }
+    }
+}
+
+impl ErrorType for Mock {
+    type Error = Infallible;
+}
+
+impl Read for Mock {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        yield_now().await;
+        match (buf.first_mut(), self.input.pop_front()) {
+            (Some(slot), Some(byte)) => {
+                *slot = byte;
+                Ok(1)
+            },
+            _ => Ok(0), // Fim da entrada: o shell termina
+        }
+    }
+}
+
+impl Write for Mock {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+}
+
+// Placa simulada: LED, amostras do ADC, setores da configuração e espera que
+// só devolve o controle
+#[derive(Default)]
+pub struct MockBoard {
+    pub led: Cell<bool>,
+    pub samples: RefCell<VecDeque<u16>>,
+    pub config: SimFlash<512>,
+}
+
+impl Board for MockBoard {
+    fn led_enabled(&self) -> bool {
+        self.led.get()
+    }
+
+    fn set_led_enabled(&self, enabled: bool) {
+        self.led.set(enabled)
+    }
+
+    fn try_adc_sample(&self) -> Option<u16> {
+        self.samples.borrow_mut().pop_front()
+    }
+
+    async fn delay_ms(&self, _ms: u64) {
+        yield_now().await
+    }
+
+    fn config_flash(&self) -> Option<&dyn ConfigFlash> {
+        Some(&self.config)
+    }
+}
+
+// Uma sessão completa com `input` digitado; devolve a saída e a placa
+pub fn session<B: Board>(input: &[u8], board: B) -> (String, B) {
+    let app = App::new(board);
+    app.load();
+    let mut shared = Shared::new(Mock::default(), &app);
+    block_on(Shell::new(Mock::new(input), &shared).run()).unwrap();
+    let output = String::from_utf8_lossy(&shared.out.get_mut().output).into_owned();
+    drop(shared);
+    (output, app.board)
+}
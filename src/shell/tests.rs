@@ -0,0 +1,240 @@
+// Sessões completas do shell sobre o transporte simulado: despacho, prompt e
+// mensagens de erro
+
+use core::cell::Cell;
+
+use super::auth::{Level, SALT_LEN};
+use super::gpio::PinId;
+use super::memory::{MemoryRegion, Width};
+use super::registers::{Field, Peripheral, Register};
+use super::testing::{block_on, session, yield_now, Mock, MockBoard};
+use super::{background, App, Board, Shared, Shell, PROMPT};
+
+#[test]
+fn prompt_after_banner_and_each_command() {
+    let (out, _) = session(b"\rled on\r", MockBoard::default());
+    assert!(out.starts_with("\r\n=== STM32F407 Shell Terminal ===\r\n"));
+    assert_eq!(out.matches(PROMPT).count(), 3);
+    assert!(out.ends_with(PROMPT));
+}
+
+#[test]
+fn dispatches_commands() {
+    let (out, board) = session(b"led on\rled toggle\rled toggle\r", MockBoard::default());
+    assert!(board.led.get());
+    assert!(!out.contains("Comando não reconhecido"));
+
+    let board = MockBoard::default();
+    board.samples.borrow_mut().extend([2048]);
+    let (out, _) = session(b"mode json\rled on\rstatus\r", board);
+    assert!(out.contains("{\"cmd\":\"led\",\"led\":true,\"ok\":true}\r\n"));
+    assert!(out.contains(r#"{"cmd":"status","#));
+}
+
+#[test]
+fn reports_errors_as_text() {
+    let (out, board) = session(b"nope\rled maybe\rled\r", MockBoard::default());
+    assert!(out.contains("nope\r\nComando não reconhecido. Digite 'help' para ajuda.\r\n"));
+    assert!(out.contains("      ^^^^^ valor inválido (esperado on|off|toggle)\r\n"));
+    assert!(out.contains("     ^ falta o argumento <estado>\r\n"));
+    assert!(!board.led.get());
+}
+
+#[test]
+fn reports_errors_as_json() {
+    let (out, _) = session(b"mode json\rnope\rled maybe\r", MockBoard::default());
+    assert!(out.contains(r#"{"ok":false,"error":"unknown_command"}"#));
+    assert!(out.contains(r#"{"cmd":"led","ok":false,"error":"invalid_choice","col":4,"#));
+}
+
+#[test]
+fn usage_and_argument_names_follow_the_language() {
+    let (out, _) = session(b"lang en\rled\recho off\rhelp rm\r", MockBoard::default());
+    assert!(out.contains("     ^ missing argument <state>\r\nUsage: led on|off|toggle\r\n"));
+    assert!(out.contains("Echo: off\r\n"));
+    assert!(out.contains("Usage: rm <file>\r\n"));
+}
+
+#[test]
+fn language_is_saved_in_the_config() {
+    let (_, board) = session(b"lang en\r", MockBoard::default());
+    let (out, board) = session(b"nope\r", board);
+    assert!(out.contains("Unknown command. Type 'help' for help.\r\n"));
+
+    // `config reset` apaga a escolha
+    let (_, board) = session(b"config reset\r", board);
+    let (out, _) = session(b"nope\r", board);
+    assert!(out.contains("Comando não reconhecido"));
+}
+
+// Placa cujo timer acorda atrasado (o triplo do pedido) e com uma borda de
+// subida no PA5 aos 10 ms
+#[derive(Default)]
+struct SlowBoard {
+    now: Cell<u64>,
+}
+
+impl Board for SlowBoard {
+    fn led_enabled(&self) -> bool {
+        false
+    }
+
+    fn set_led_enabled(&self, _enabled: bool) {}
+
+    fn try_adc_sample(&self) -> Option<u16> {
+        None
+    }
+
+    async fn delay_ms(&self, ms: u64) {
+        self.now.set(self.now.get() + 3 * ms);
+        yield_now().await
+    }
+
+    fn uptime_ms(&self) -> u64 {
+        self.now.get()
+    }
+
+    fn has_pin(&self, pin: PinId) -> bool {
+        pin.port <= 4
+    }
+
+    fn read_pin(&self, _pin: PinId) -> bool {
+        self.now.get() >= 10
+    }
+}
+
+#[test]
+fn gpio_refuses_pins_missing_from_the_package() {
+    let (out, _) = session(b"gpio read PF0\rgpio read PA05\rgpio read PE15\r", SlowBoard::default());
+    assert_eq!(out.matches("Pino inválido").count(), 2);
+}
+
+#[test]
+fn gpio_watch_timestamps_from_the_board_clock() {
+    // Os backspaces só dão tempo ao `watch` antes do Ctrl-C
+    let mut input = b"mode json\rgpio mode PA5 in\rgpio watch PA5\r".to_vec();
+    input.extend_from_slice(&[0x7f; 32]);
+    input.push(0x03);
+    let (out, _) = session(&input, SlowBoard::default());
+    assert!(out.contains(r#"{"cmd":"gpio","ms":0,"pin":"PA5","level":0}"#));
+    assert!(out.contains(r#"{"cmd":"gpio","ms":30,"pin":"PA5","level":1}"#));
+}
+
+// Placa com um registrador que a leitura altera (como o DR da USART) dentro
+// de um bloco de periféricos; conta as leituras
+#[derive(Default)]
+struct MapBoard {
+    reads: Cell<usize>,
+}
+
+const MAP: [MemoryRegion; 2] = [
+    MemoryRegion { name: "usart1", start: 0x4001_1000, end: 0x4001_1008, writable: true, readable: false },
+    MemoryRegion { name: "apb2", start: 0x4001_0000, end: 0x4001_4C00, writable: true, readable: true },
+];
+
+impl Board for MapBoard {
+    fn led_enabled(&self) -> bool {
+        false
+    }
+
+    fn set_led_enabled(&self, _enabled: bool) {}
+
+    fn try_adc_sample(&self) -> Option<u16> {
+        None
+    }
+
+    async fn delay_ms(&self, _ms: u64) {
+        yield_now().await
+    }
+
+    fn memory_map(&self) -> &[MemoryRegion] {
+        &MAP
+    }
+
+    fn read_memory(&self, _address: u32, _width: Width) -> u32 {
+        self.reads.set(self.reads.get() + 1);
+        0
+    }
+
+    fn peripherals(&self) -> &[Peripheral] {
+        &PERIPHERALS
+    }
+}
+
+const PERIPHERALS: [Peripheral; 1] = [Peripheral {
+    name: "USART1",
+    base: 0x4001_1000,
+    registers: &[
+        Register::new("SR", 0x00, &[Field::bit("RXNE", 5)]),
+        Register::new("DR", 0x04, &[Field::bits("DR", 0, 9)]),
+        Register::new("BRR", 0x08, &[Field::bits("DIV_MANTISSA", 4, 12)]),
+    ],
+}];
+
+#[test]
+fn read_clearing_registers_are_never_read() {
+    let input = b"mode json\rdump 0x40010ff0 32\rpeek 0x40011004\rpoke 0x40011004 0x41\r";
+    let (out, board) = session(input, MapBoard::default());
+    assert!(out.contains(r#"{"cmd":"dump","ok":false,"error":"read_clears","#));
+    assert!(out.contains(r#"{"cmd":"peek","ok":false,"error":"read_clears","#));
+    assert!(out.contains(r#"{"cmd":"poke","address":1073811460,"value":65,"ok":true}"#));
+    assert_eq!(board.reads.get(), 0);
+
+    let (_, board) = session(b"dump 0x40011008 16\r", MapBoard::default());
+    assert_eq!(board.reads.get(), 16);
+}
+
+#[test]
+fn reg_follows_the_read_to_clear_rule() {
+    let (out, board) = session(b"mode json\rreg USART1 DR\rreg usart1\r", MapBoard::default());
+    assert!(out.contains(r#"{"cmd":"reg","ok":false,"error":"read_clears","#));
+    // A listagem mostra os registradores, mas só lê o BRR
+    assert!(out.contains(r#"{"name":"DR","address":1073811460},"#));
+    assert!(out.contains(r#"{"name":"BRR","address":1073811464,"value":0}"#));
+    assert_eq!(board.reads.get(), 1);
+}
+
+#[test]
+fn login_lockout_is_shared_between_sessions() {
+    let app = App::new(MockBoard::default());
+    app.auth.set(Level::Admin, "root", [1; SALT_LEN]).unwrap();
+    let login = |input: &[u8]| {
+        let mut shared = Shared::new(Mock::default(), &app);
+        block_on(Shell::new(Mock::new(input), &shared).run()).unwrap();
+        String::from_utf8_lossy(&shared.out.get_mut().output).into_owned()
+    };
+
+    // Os erros numa porta bloqueiam as outras, até com a senha certa
+    let out = login(b"a\rb\r");
+    assert_eq!(out.matches("Senha incorreta").count(), 2);
+    let out = login(b"c\rroot\r");
+    assert!(out.contains("login bloqueado"));
+    assert!(!out.contains("Nível: admin"));
+    let out = login(b"root\r");
+    assert!(out.contains("login bloqueado"));
+}
+
+#[test]
+fn trailing_ampersand_starts_a_job_unless_escaped() {
+    assert_eq!(background("echo a &  "), Some("echo a "));
+    assert_eq!(background("echo a&"), Some("echo a"));
+    assert_eq!(background(r"echo a\\&"), Some(r"echo a\\"));
+    assert_eq!(background(r"echo a\&&"), Some(r"echo a\&"));
+    assert_eq!(background(r"echo a\&"), None);
+    assert_eq!(background(r"echo a\\\&"), None);
+    assert_eq!(background("echo a && b"), None);
+    assert_eq!(background("echo a &&"), None);
+
+    // O `a&` inteiro chega ao comando, em primeiro plano
+    let (out, _) = session(b"echo a\\&\r", MockBoard::default());
+    assert!(out.contains("  echo a\\&\r\n       ^^^ valor inválido"));
+    assert!(!out.contains("[1]"));
+}
+
+#[test]
+fn repeat_runs_the_inner_command() {
+    let (out, board) = session(b"repeat 3 led toggle\rrepeat 2 watch 1s status\r", MockBoard::default());
+    assert!(board.led.get());
+    assert!(out.contains("repeat/watch não podem repetir outro repeat/watch"));
+    assert!(!out.contains("Comando não reconhecido"));
+}
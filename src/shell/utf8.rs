@@ -0,0 +1,146 @@
+// Decodificação incremental de UTF-8 (um byte por vez, como chega da UART)
+// e classificação de caracteres para edição por grafema
+
+use heapless::Vec;
+
+// Caractere usado para sequências inválidas
+pub const REPLACEMENT: char = '\u{FFFD}';
+
+// Decodificador de UTF-8 byte a byte
+pub struct Utf8Decoder {
+    code: u32,     // Bits acumulados do caractere atual
+    pending: u8,   // Bytes de continuação que ainda faltam
+    min: u32,      // Menor valor válido para o tamanho da sequência (evita "overlong")
+}
+
+impl Default for Utf8Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Utf8Decoder {
+    pub const fn new() -> Self {
+        Self { code: 0, pending: 0, min: 0 }
+    }
+
+    // Processa um byte; retorna os caracteres completados (até dois: um U+FFFD
+    // para uma sequência interrompida e o caractere iniciado pelo byte atual)
+    pub fn feed(&mut self, byte: u8) -> Vec<char, 2> {
+        let mut out = Vec::new();
+        if self.pending > 0 {
+            if byte & 0xC0 == 0x80 {
+                self.code = (self.code << 6) | (byte & 0x3F) as u32;
+                self.pending -= 1;
+                if self.pending == 0 {
+                    let c = match char::from_u32(self.code) {
+                        Some(c) if self.code >= self.min => c,
+                        _ => REPLACEMENT, // "Overlong" ou surrogate
+                    };
+                    let _ = out.push(c);
+                }
+                return out;
+            }
+            // Sequência interrompida: descarta e trata o byte como início
+            self.pending = 0;
+            let _ = out.push(REPLACEMENT);
+        }
+        if let Some(c) = self.start(byte) {
+            let _ = out.push(c);
+        }
+        out
+    }
+
+    // Primeiro byte de uma sequência
+    fn start(&mut self, byte: u8) -> Option<char> {
+        let (bits, pending, min) = match byte {
+            0x00..=0x7F => return Some(byte as char),
+            0xC2..=0xDF => (byte & 0x1F, 1, 0x80),
+            0xE0..=0xEF => (byte & 0x0F, 2, 0x800),
+            0xF0..=0xF4 => (byte & 0x07, 3, 0x1_0000),
+            _ => return Some(REPLACEMENT), // Continuação solta ou byte proibido
+        };
+        self.code = bits as u32;
+        self.pending = pending;
+        self.min = min;
+        None
+    }
+}
+
+// Caracteres sem largura que se juntam ao anterior (acentos combinantes,
+// seletores de variação e o "zero width joiner")
+pub fn is_extend(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{200D}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{E0100}'..='\u{E01EF}'
+    )
+}
+
+// Colunas ocupadas no terminal (0 para combinantes, 2 para CJK e emoji)
+pub fn width(c: char) -> usize {
+    if is_extend(c) {
+        return 0;
+    }
+    match c {
+        '\u{1100}'..='\u{115F}'
+        | '\u{2E80}'..='\u{A4CF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FE30}'..='\u{FE4F}'
+        | '\u{FF00}'..='\u{FF60}'
+        | '\u{FFE0}'..='\u{FFE6}'
+        | '\u{1F300}'..='\u{1F64F}'
+        | '\u{1F900}'..='\u{1F9FF}'
+        | '\u{20000}'..='\u{3FFFD}' => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: &[u8]) -> std::string::String {
+        let mut decoder = Utf8Decoder::new();
+        bytes.iter().flat_map(|&b| decoder.feed(b)).collect()
+    }
+
+    #[test]
+    fn valid_sequences() {
+        for text in ["abc", "ação", "€", "日本", "😀", "e\u{301}"] {
+            assert_eq!(decode(text.as_bytes()), text);
+        }
+    }
+
+    #[test]
+    fn invalid_sequences_become_replacement() {
+        assert_eq!(decode(&[0x80, b'a']), "\u{FFFD}a"); // Continuação solta
+        assert_eq!(decode(&[0xC3, b'a']), "\u{FFFD}a"); // Interrompida
+        assert_eq!(decode(&[0xE2, 0x82, 0xC3, 0xA9]), "\u{FFFD}é");
+        assert_eq!(decode(&[0xC0, 0xAF]), "\u{FFFD}\u{FFFD}"); // Proibido
+        assert_eq!(decode(&[0xE0, 0x80, 0xAF]), "\u{FFFD}"); // "Overlong"
+        assert_eq!(decode(&[0xED, 0xA0, 0x80]), "\u{FFFD}"); // Surrogate
+        assert_eq!(decode(&[0xF4, 0x90, 0x80, 0x80]), "\u{FFFD}"); // Acima de U+10FFFF
+        assert_eq!(decode(&[0xFF]), "\u{FFFD}");
+    }
+
+    #[test]
+    fn widths() {
+        assert_eq!(width('a'), 1);
+        assert_eq!(width('ç'), 1);
+        assert_eq!(width('\u{301}'), 0);
+        assert_eq!(width('\u{200D}'), 0);
+        assert_eq!(width('日'), 2);
+        assert_eq!(width('한'), 2);
+        assert_eq!(width('😀'), 2);
+        assert!(is_extend('\u{1F3FB}'));
+        assert!(!is_extend('a'));
+    }
+}
@@ -0,0 +1,736 @@
+// Transferência de arquivos XMODEM-CRC e YMODEM (lote) sobre o transporte do
+// shell (`rx`/`sx`). Só o modo CRC-16: o receptor pede o início com 'C'
+//
+// Bloco no fio: SOH|STX, número, 255 - número, 128|1024 bytes, CRC-16 (big-endian)
+// YMODEM: o bloco 0 leva "nome\0tamanho" (nome vazio encerra o lote) e o fim de
+// cada arquivo é EOT, NAK, EOT, ACK
+
+use embassy_futures::select::{select, Either};
+use embedded_io_async::{Read, Write};
+
+use super::files::{FileError, FileName, Files};
+use super::frame::crc16_from;
+use super::Board;
+
+const SOH: u8 = 0x01; // Bloco de 128 bytes
+const STX: u8 = 0x02; // Bloco de 1024 bytes
+const EOT: u8 = 0x04; // Fim do arquivo
+const ACK: u8 = 0x06;
+const NAK: u8 = 0x15;
+const CAN: u8 = 0x18; // Dois seguidos cancelam
+const CRC: u8 = b'C'; // Pedido de início no modo CRC
+const SUB: u8 = 0x1A; // Preenchimento do último bloco (ver `FileSink`)
+
+const BLOCK: usize = 128;
+const BLOCK_1K: usize = 1024;
+
+// Tempos e tentativas
+const START_MS: u64 = 3_000;  // Intervalo entre os pedidos de início
+const START_TRIES: u32 = 20;  // ~1 min esperando o outro lado
+const BLOCK_MS: u64 = 10_000; // Espera pelo próximo bloco ou pela resposta
+const BYTE_MS: u64 = 1_000;   // Entre os bytes de um bloco
+const PURGE_MS: u64 = 500;    // Silêncio antes do NAK de um bloco ruim
+const MAX_ERRORS: u32 = 10;   // Erros seguidos antes de desistir
+
+// Nomes aceitos por `rx`/`sx` (mesma ordem de `Protocol`)
+pub const PROTOCOLS: &[&str] = &["xmodem", "ymodem"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    Xmodem, // Um arquivo, blocos de 128 bytes, tamanho múltiplo de 128
+    Ymodem, // Lote com nome e tamanho, blocos de 1024 bytes
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 2] = [Protocol::Xmodem, Protocol::Ymodem];
+
+    pub fn name(self) -> &'static str {
+        PROTOCOLS[self as usize]
+    }
+}
+
+// Transferência pedida por `rx`/`sx` (o shell a executa depois da resposta)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub send: bool,             // `sx` (senão `rx`)
+    pub protocol: Protocol,
+    pub name: Option<FileName>, // Arquivo (no `rx` YMODEM o nome vem do outro lado)
+    pub firmware: bool,         // `fw update`: recebe no slot de firmware
+}
+
+// Destino dos arquivos recebidos (arquivos em RAM, slot de firmware); guarda
+// qual é o arquivo atual
+pub trait Sink {
+    // Começa um arquivo: nome (do comando ou do bloco 0) e tamanho anunciado
+    fn create(&mut self, name: &str, size: Option<usize>) -> Result<(), FileError>;
+    fn append(&mut self, data: &[u8]) -> Result<(), FileError>;
+    // Fim do arquivo (`padded`: pode terminar com o SUB do último bloco);
+    // retorna o tamanho final
+    fn finish(&mut self, padded: bool) -> Result<usize, FileError>;
+    // Falha no meio: descarta o arquivo incompleto
+    fn discard(&mut self);
+}
+
+// Falhas de uma transferência
+#[derive(Debug)]
+pub enum TransferError<E> {
+    Io(E),           // Falha no transporte
+    Closed,          // Fim da entrada
+    Cancelled,       // CAN CAN do outro lado
+    Timeout,         // O outro lado não respondeu
+    Failed,          // Erros demais ou bloco fora de sequência
+    File(FileError), // Arquivo grande demais, nome inválido, ...
+}
+
+impl<E> From<FileError> for TransferError<E> {
+    fn from(e: FileError) -> Self {
+        TransferError::File(e)
+    }
+}
+
+// Resultado de uma transferência
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Summary {
+    pub name: Option<FileName>, // Último arquivo
+    pub files: usize,
+    pub bytes: usize,
+}
+
+// CRC-16/XMODEM (polinômio 0x1021, valor inicial 0)
+pub fn crc16_xmodem(data: &[u8]) -> u16 {
+    crc16_from(0, data)
+}
+
+// Recebe um arquivo (XMODEM, em `name`) ou um lote (YMODEM); o arquivo
+// incompleto é descartado numa falha
+pub async fn receive<R, W, B, S>(
+    rx: &mut R,
+    tx: &mut W,
+    board: &B,
+    sink: &mut S,
+    protocol: Protocol,
+    name: Option<&str>,
+) -> Result<Summary, TransferError<R::Error>>
+where
+    R: Read,
+    W: Write<Error = R::Error>,
+    B: Board,
+    S: Sink,
+{
+    let mut port = Port { rx, tx, board };
+    let mut summary = Summary::default();
+    let result = port.receive(sink, protocol, name, &mut summary).await;
+    if let Err(e) = &result {
+        sink.discard();
+        port.abort(e).await;
+    }
+    result.map(|()| summary)
+}
+
+// Envia o arquivo `name`
+pub async fn send<R, W, B>(
+    rx: &mut R,
+    tx: &mut W,
+    board: &B,
+    files: &Files,
+    protocol: Protocol,
+    name: FileName,
+) -> Result<Summary, TransferError<R::Error>>
+where
+    R: Read,
+    W: Write<Error = R::Error>,
+    B: Board,
+{
+    let mut port = Port { rx, tx, board };
+    let len = files.len(&name).ok_or(FileError::NotFound)?;
+    let result = port.send_file(files, protocol, name, len).await;
+    if let Err(e) = &result {
+        port.abort(e).await;
+    }
+    result.map(|()| Summary { name: Some(name), files: 1, bytes: len })
+}
+
+// Transporte com tempo limite nas leituras
+struct Port<'a, R, W, B> {
+    rx: &'a mut R,
+    tx: &'a mut W,
+    board: &'a B,
+}
+
+impl<R, W, B> Port<'_, R, W, B>
+where
+    R: Read,
+    W: Write<Error = R::Error>,
+    B: Board,
+{
+    // Um byte ou None após `ms` sem dados
+    async fn read_byte(&mut self, ms: u64) -> Result<Option<u8>, TransferError<R::Error>> {
+        let mut byte = [0u8; 1];
+        match select(self.rx.read(&mut byte), self.board.delay_ms(ms)).await {
+            Either::First(Ok(0)) => Err(TransferError::Closed),
+            Either::First(Ok(_)) => Ok(Some(byte[0])),
+            Either::First(Err(e)) => Err(TransferError::Io(e)),
+            Either::Second(()) => Ok(None),
+        }
+    }
+
+    // Preenche `buf` (false se o outro lado parar no meio)
+    async fn read_exact(&mut self, buf: &mut [u8]) -> Result<bool, TransferError<R::Error>> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match select(self.rx.read(&mut buf[filled..]), self.board.delay_ms(BYTE_MS)).await {
+                Either::First(Ok(0)) => return Err(TransferError::Closed),
+                Either::First(Ok(n)) => filled += n,
+                Either::First(Err(e)) => return Err(TransferError::Io(e)),
+                Either::Second(()) => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), TransferError<R::Error>> {
+        self.tx.write_all(bytes).await.map_err(TransferError::Io)
+    }
+
+    // Descarta a entrada até um intervalo de silêncio
+    async fn purge(&mut self) -> Result<(), TransferError<R::Error>> {
+        while self.read_byte(PURGE_MS).await?.is_some() {}
+        Ok(())
+    }
+
+    // Depois de um CAN: o segundo confirma o cancelamento
+    async fn cancelled(&mut self) -> Result<bool, TransferError<R::Error>> {
+        Ok(self.read_byte(BYTE_MS).await? == Some(CAN))
+    }
+
+    // Avisa o outro lado da desistência (se ele ainda estiver ouvindo)
+    async fn abort(&mut self, error: &TransferError<R::Error>) {
+        if !matches!(error, TransferError::Io(_) | TransferError::Closed | TransferError::Cancelled) {
+            let _ = self.write(&[CAN, CAN, CAN]).await;
+        }
+    }
+
+    // Corpo e CRC de um bloco depois do cabeçalho; None = bloco corrompido
+    async fn read_block(&mut self, data: &mut [u8]) -> Result<Option<u8>, TransferError<R::Error>> {
+        let mut seq = [0u8; 2];
+        let mut crc = [0u8; 2];
+        if !self.read_exact(&mut seq).await? || !self.read_exact(data).await? || !self.read_exact(&mut crc).await? {
+            return Ok(None);
+        }
+        if seq[0] != !seq[1] || crc16_xmodem(data) != u16::from_be_bytes(crc) {
+            return Ok(None);
+        }
+        Ok(Some(seq[0]))
+    }
+
+    async fn receive<S: Sink>(
+        &mut self,
+        sink: &mut S,
+        protocol: Protocol,
+        mut name: Option<&str>,
+        summary: &mut Summary,
+    ) -> Result<(), TransferError<R::Error>> {
+        let mut block = [0u8; BLOCK_1K];
+        loop {
+            // Um arquivo por volta (XMODEM: o único; YMODEM: começa pelo bloco 0)
+            let mut open = false; // Dentro de um arquivo
+            if let Some(name) = name.take() {
+                sink.create(name, None)?;
+                summary.name = FileName::new(name);
+                open = true;
+            }
+            let mut size = None;       // Tamanho anunciado no bloco 0
+            let mut received = 0;      // Bytes gravados
+            let mut expected = if open { 1 } else { 0 };
+            let mut last: Option<u8> = None; // Último bloco aceito (repetições recebem ACK)
+            let mut data = false;      // Já chegou um bloco de dados
+            let mut eot = false;       // Primeiro EOT do YMODEM (respondido com NAK)
+            let mut errors = 0;
+            self.write(&[CRC]).await?;
+            loop {
+                let wait = if last.is_some() { BLOCK_MS } else { START_MS };
+                let Some(byte) = self.read_byte(wait).await? else {
+                    errors += 1;
+                    if errors >= if last.is_some() { MAX_ERRORS } else { START_TRIES } {
+                        return Err(TransferError::Timeout);
+                    }
+                    self.write(&[if data { NAK } else { CRC }]).await?;
+                    continue;
+                };
+                match byte {
+                    SOH | STX => {
+                        let len = if byte == SOH { BLOCK } else { BLOCK_1K };
+                        let Some(seq) = self.read_block(&mut block[..len]).await? else {
+                            errors += 1;
+                            if errors >= MAX_ERRORS {
+                                return Err(TransferError::Failed);
+                            }
+                            self.purge().await?;
+                            self.write(&[NAK]).await?;
+                            continue;
+                        };
+                        errors = 0;
+                        if last == Some(seq) {
+                            self.write(&[ACK]).await?; // O ACK anterior se perdeu
+                            continue;
+                        }
+                        if seq != expected {
+                            return Err(TransferError::Failed);
+                        }
+                        last = Some(seq);
+                        expected = seq.wrapping_add(1);
+
+                        if seq == 0 && !open {
+                            // Cabeçalho YMODEM: nome vazio encerra o lote
+                            let Some((file, length)) = parse_header(&block[..len])? else {
+                                self.write(&[ACK]).await?;
+                                return Ok(());
+                            };
+                            sink.create(file, length)?;
+                            summary.name = FileName::new(file);
+                            open = true;
+                            size = length;
+                            self.write(&[ACK, CRC]).await?;
+                            continue;
+                        }
+
+                        if !open {
+                            return Err(TransferError::Failed);
+                        }
+                        let take = size.map_or(len, |size: usize| len.min(size - received));
+                        sink.append(&block[..take])?;
+                        received += take;
+                        data = true;
+                        self.write(&[ACK]).await?;
+                    },
+                    EOT if open => {
+                        if protocol == Protocol::Ymodem && !eot {
+                            eot = true;
+                            self.write(&[NAK]).await?;
+                            continue;
+                        }
+                        self.write(&[ACK]).await?;
+                        received = sink.finish(size.is_none())?;
+                        summary.files += 1;
+                        summary.bytes += received;
+                        if protocol == Protocol::Xmodem {
+                            return Ok(());
+                        }
+                        break; // Próximo cabeçalho
+                    },
+                    EOT if summary.files > 0 => self.write(&[ACK]).await?, // O ACK do EOT se perdeu
+                    CAN if self.cancelled().await? => return Err(TransferError::Cancelled),
+                    _ => {}, // Ruído
+                }
+            }
+        }
+    }
+
+    // Próximo ACK ou NAK (None após BLOCK_MS); 'C' repetidos e ruído são ignorados
+    async fn response(&mut self) -> Result<Option<u8>, TransferError<R::Error>> {
+        loop {
+            match self.read_byte(BLOCK_MS).await? {
+                None => return Ok(None),
+                Some(CAN) if self.cancelled().await? => return Err(TransferError::Cancelled),
+                Some(byte @ (ACK | NAK)) => return Ok(Some(byte)),
+                Some(_) => {},
+            }
+        }
+    }
+
+    // Espera o 'C' do receptor
+    async fn wait_start(&mut self) -> Result<(), TransferError<R::Error>> {
+        let mut tries = 0;
+        loop {
+            match self.read_byte(START_MS).await? {
+                Some(CRC) => return Ok(()),
+                Some(CAN) if self.cancelled().await? => return Err(TransferError::Cancelled),
+                Some(_) => {},
+                None => {
+                    tries += 1;
+                    if tries >= START_TRIES {
+                        return Err(TransferError::Timeout);
+                    }
+                },
+            }
+        }
+    }
+
+    // Envia um bloco até o ACK
+    async fn send_block(&mut self, seq: u8, data: &[u8]) -> Result<(), TransferError<R::Error>> {
+        let mut frame = [0u8; 3 + BLOCK_1K + 2];
+        let len = data.len();
+        frame[0] = if len == BLOCK { SOH } else { STX };
+        frame[1] = seq;
+        frame[2] = !seq;
+        frame[3..3 + len].copy_from_slice(data);
+        frame[3 + len..5 + len].copy_from_slice(&crc16_xmodem(data).to_be_bytes());
+        for _ in 0..MAX_ERRORS {
+            self.write(&frame[..5 + len]).await?;
+            if self.response().await? == Some(ACK) {
+                return Ok(());
+            }
+        }
+        Err(TransferError::Failed)
+    }
+
+    // EOT até o ACK (o receptor YMODEM responde NAK ao primeiro)
+    async fn send_eot(&mut self) -> Result<(), TransferError<R::Error>> {
+        for _ in 0..MAX_ERRORS {
+            self.write(&[EOT]).await?;
+            if self.response().await? == Some(ACK) {
+                return Ok(());
+            }
+        }
+        Err(TransferError::Failed)
+    }
+
+    async fn send_file(
+        &mut self,
+        files: &Files,
+        protocol: Protocol,
+        name: FileName,
+        len: usize,
+    ) -> Result<(), TransferError<R::Error>> {
+        self.wait_start().await?;
+        if protocol == Protocol::Ymodem {
+            let mut header = [0u8; BLOCK];
+            let mut size = itoa::Buffer::new();
+            let (name, size) = (name.as_str().as_bytes(), size.format(len).as_bytes());
+            header[..name.len()].copy_from_slice(name);
+            header[name.len() + 1..name.len() + 1 + size.len()].copy_from_slice(size);
+            self.send_block(0, &header).await?;
+            self.wait_start().await?;
+        }
+
+        let size = if protocol == Protocol::Ymodem { BLOCK_1K } else { BLOCK };
+        let mut block = [0u8; BLOCK_1K];
+        let mut seq = 1u8;
+        let mut offset = 0;
+        while offset < len {
+            let n = files.read(&name, offset, &mut block[..size])?;
+            if n == 0 {
+                break; // Apagado/encurtado por outra sessão
+            }
+            // O resto do último bloco vai com SUB (e num bloco de 128 se couber)
+            let used = if n <= BLOCK { BLOCK } else { size };
+            block[n..used].fill(SUB);
+            self.send_block(seq, &block[..used]).await?;
+            seq = seq.wrapping_add(1);
+            offset += n;
+        }
+        self.send_eot().await?;
+
+        if protocol == Protocol::Ymodem {
+            // Cabeçalho vazio: fim do lote
+            self.wait_start().await?;
+            self.send_block(0, &[0u8; BLOCK]).await?;
+        }
+        Ok(())
+    }
+}
+
+// Nome e tamanho do bloco 0 do YMODEM (None = fim do lote); o caminho é
+// descartado do nome
+fn parse_header<E>(block: &[u8]) -> Result<Option<(&str, Option<usize>)>, TransferError<E>> {
+    let mut fields = block.split(|&b| b == 0);
+    let path = fields.next().unwrap_or_default();
+    if path.is_empty() {
+        return Ok(None);
+    }
+    let base = path.rsplit(|&b| b == b'/').next().unwrap_or_default();
+    let name = core::str::from_utf8(base).map_err(|_| FileError::BadName)?;
+
+    // "tamanho [data modo ...]" em decimal
+    let info = fields.next().unwrap_or_default();
+    let digits = info.split(|&b| b == b' ').next().unwrap_or_default();
+    let size = core::str::from_utf8(digits).ok().and_then(|s| s.parse::<usize>().ok());
+    Ok(Some((name, size)))
+}
+
+#[cfg(test)]
+mod tests {
+    // Transcrições na forma que o lrzsz troca no fio (`sz`/`rz` com
+    // `--xmodem`/`--ymodem`, modo CRC): cada passo é o que o outro lado manda
+    // ou o que o shell tem que responder, byte a byte
+    use core::cell::RefCell;
+    use core::convert::Infallible;
+    use core::future::poll_fn;
+    use core::task::Poll;
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    use embedded_io_async::{ErrorType, Read, Write};
+
+    use super::*;
+    use crate::shell::files::FileSink;
+    use crate::shell::testing::{block_on, MockBoard};
+
+    enum Step {
+        Peer(VecDeque<u8>),   // Bytes do outro lado
+        Expect(VecDeque<u8>), // Resposta esperada do shell
+    }
+
+    use Step::{Expect, Peer};
+
+    fn peer(bytes: &[u8]) -> Step {
+        Peer(bytes.iter().copied().collect())
+    }
+
+    fn expect(bytes: &[u8]) -> Step {
+        Expect(bytes.iter().copied().collect())
+    }
+
+    // Roteiro compartilhado pelos dois lados do transporte. Sem bytes do outro
+    // lado a leitura fica pendente e o tempo limite (que só devolve o controle
+    // no `MockBoard`) vence, como um `sz` calado; no fim do roteiro a entrada fecha
+    struct Script(RefCell<VecDeque<Step>>);
+
+    impl Script {
+        fn new(steps: Vec<Step>) -> Self {
+            Self(RefCell::new(steps.into()))
+        }
+
+        // Nenhuma resposta esperada ficou sem ser enviada
+        fn assert_done(&self) {
+            let steps = self.0.borrow();
+            let pending: Vec<&VecDeque<u8>> = steps.iter().filter_map(|s| match s {
+                Expect(bytes) => Some(bytes),
+                Peer(_) => None,
+            }).collect();
+            assert!(pending.is_empty(), "respostas que faltaram: {pending:x?}");
+        }
+    }
+
+    struct Wire<'a>(&'a Script);
+
+    impl ErrorType for Wire<'_> {
+        type Error = Infallible;
+    }
+
+    impl Read for Wire<'_> {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+            poll_fn(|_| {
+                let mut steps = self.0 .0.borrow_mut();
+                let Some(step) = steps.front_mut() else {
+                    return Poll::Ready(Ok(0));
+                };
+                let Peer(bytes) = step else {
+                    return Poll::Pending; // O outro lado espera a resposta
+                };
+                buf[0] = bytes.pop_front().unwrap();
+                if bytes.is_empty() {
+                    steps.pop_front();
+                }
+                Poll::Ready(Ok(1))
+            })
+            .await
+        }
+    }
+
+    impl Write for Wire<'_> {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+            let mut steps = self.0 .0.borrow_mut();
+            for (i, &byte) in buf.iter().enumerate() {
+                let Some(Expect(bytes)) = steps.front_mut() else {
+                    panic!("resposta inesperada {:x?}", &buf[i..]);
+                };
+                assert_eq!(bytes.pop_front(), Some(byte), "byte {i} de {buf:x?}");
+                if bytes.is_empty() {
+                    steps.pop_front();
+                }
+            }
+            Ok(buf.len())
+        }
+    }
+
+    // Bloco como o `sz` monta: `data` completado com `fill` até 128 ou 1024
+    fn block(seq: u8, data: &[u8], len: usize, fill: u8) -> Vec<u8> {
+        let mut body = data.to_vec();
+        body.resize(len, fill);
+        let mut frame = vec![if len == BLOCK { SOH } else { STX }, seq, !seq];
+        frame.extend_from_slice(&body);
+        frame.extend_from_slice(&crc16_xmodem(&body).to_be_bytes());
+        frame
+    }
+
+    // Bloco 0 do `sz --ymodem`: "nome\0tamanho data modo 0 restantes bytes"
+    fn header(name: &str, size: usize) -> Vec<u8> {
+        let info = format!("{name}\0{size} 15113543211 100644 0 1 {size}");
+        block(0, info.as_bytes(), BLOCK, 0)
+    }
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + 3) as u8).collect()
+    }
+
+    fn receive_with(
+        steps: Vec<Step>,
+        files: &Files,
+        protocol: Protocol,
+        name: Option<&str>,
+    ) -> Result<Summary, TransferError<Infallible>> {
+        let script = Script::new(steps);
+        let board = MockBoard::default();
+        let mut sink = FileSink::new(files);
+        let result = block_on(receive(&mut Wire(&script), &mut Wire(&script), &board, &mut sink, protocol, name));
+        script.assert_done();
+        result
+    }
+
+    fn contents(files: &Files, name: &str) -> Option<Vec<u8>> {
+        let name = FileName::new(name).unwrap();
+        let mut buf = vec![0u8; files.len(&name)?];
+        files.read(&name, 0, &mut buf).unwrap();
+        Some(buf)
+    }
+
+    #[test]
+    fn xmodem_receive() {
+        // `sz --xmodem`: dois blocos, o último completado com SUB
+        let file = data(200);
+        let files = Files::new();
+        let steps = vec![
+            expect(b"C"),
+            peer(&block(1, &file[..128], BLOCK, SUB)),
+            expect(&[ACK]),
+            peer(&block(2, &file[128..], BLOCK, SUB)),
+            expect(&[ACK]),
+            peer(&[EOT]),
+            expect(&[ACK]),
+        ];
+        let summary = receive_with(steps, &files, Protocol::Xmodem, Some("cal.bin")).unwrap();
+        assert_eq!((summary.files, summary.bytes), (1, 200));
+        assert_eq!(contents(&files, "cal.bin"), Some(file));
+    }
+
+    #[test]
+    fn corrupt_block_is_nacked_and_resent() {
+        // O `sz` atrasado perde o primeiro 'C'; um bloco chega com um bit
+        // trocado e é repetido depois do NAK
+        let file = data(128);
+        let good = block(1, &file, BLOCK, SUB);
+        let mut bad = good.clone();
+        bad[40] ^= 0x10;
+        let files = Files::new();
+        let steps = vec![
+            expect(b"C"),
+            expect(b"C"),
+            peer(&bad),
+            expect(&[NAK]),
+            peer(&good),
+            expect(&[ACK]),
+            peer(&[EOT]),
+            expect(&[ACK]),
+        ];
+        let summary = receive_with(steps, &files, Protocol::Xmodem, Some("a.bin")).unwrap();
+        assert_eq!(summary.bytes, 128);
+        assert_eq!(contents(&files, "a.bin"), Some(file));
+    }
+
+    #[test]
+    fn duplicate_block_is_acked_once() {
+        // O ACK do bloco 1 se perde e o `sz` o repete
+        let file = data(256);
+        let first = block(1, &file[..128], BLOCK, SUB);
+        let files = Files::new();
+        let steps = vec![
+            expect(b"C"),
+            peer(&first),
+            expect(&[ACK]),
+            peer(&first),
+            expect(&[ACK]),
+            peer(&block(2, &file[128..], BLOCK, SUB)),
+            expect(&[ACK]),
+            peer(&[EOT]),
+            expect(&[ACK]),
+        ];
+        let summary = receive_with(steps, &files, Protocol::Xmodem, Some("b.bin")).unwrap();
+        assert_eq!(summary.bytes, 256);
+        assert_eq!(contents(&files, "b.bin"), Some(file));
+    }
+
+    #[test]
+    fn cancel_discards_partial_file() {
+        // Ctrl-C no `sz`: dez CAN e dez backspaces, sem resposta do shell
+        let file = data(1024);
+        let files = Files::new();
+        let mut cancel = vec![CAN; 10];
+        cancel.extend_from_slice(&[0x08; 10]);
+        let steps = vec![
+            expect(b"C"),
+            peer(&header("log.txt", 2000)),
+            expect(&[ACK, CRC]),
+            peer(&block(1, &file, BLOCK_1K, SUB)),
+            expect(&[ACK]),
+            peer(&cancel),
+        ];
+        let result = receive_with(steps, &files, Protocol::Ymodem, None);
+        assert!(matches!(result, Err(TransferError::Cancelled)));
+        assert_eq!(contents(&files, "log.txt"), None);
+    }
+
+    #[test]
+    fn ymodem_batch_receive() {
+        // `sz --ymodem`: cabeçalho, bloco de 1K, resto em bloco de 128, EOT
+        // duas vezes e o cabeçalho vazio que fecha o lote
+        let mut file = data(1100);
+        file[1099] = SUB;
+        let files = Files::new();
+        let steps = vec![
+            expect(b"C"),
+            peer(&header("dir/tab.csv", 1100)),
+            expect(&[ACK, CRC]),
+            peer(&block(1, &file[..1024], BLOCK_1K, SUB)),
+            expect(&[ACK]),
+            peer(&block(2, &file[1024..], BLOCK, SUB)),
+            expect(&[ACK]),
+            peer(&[EOT]),
+            expect(&[NAK]),
+            peer(&[EOT]),
+            expect(&[ACK]),
+            expect(b"C"),
+            peer(&block(0, &[], BLOCK, 0)),
+            expect(&[ACK]),
+        ];
+        let summary = receive_with(steps, &files, Protocol::Ymodem, None).unwrap();
+        assert_eq!((summary.files, summary.bytes), (1, 1100));
+        assert_eq!(summary.name, FileName::new("tab.csv"));
+        // O tamanho do cabeçalho corta o SUB, mesmo que o arquivo termine nele
+        assert_eq!(contents(&files, "tab.csv"), Some(file));
+    }
+
+    #[test]
+    fn ymodem_send_retries_nacked_block() {
+        // `rz --ymodem` do outro lado, que rejeita o primeiro bloco de dados
+        let file = data(200);
+        let files = Files::new();
+        let name = FileName::new("cap.bin").unwrap();
+        files.create(name).unwrap();
+        files.append(&name, &file).unwrap();
+        let first = block(1, &file, BLOCK_1K, SUB);
+        let steps = vec![
+            peer(b"C"),
+            expect(&block(0, b"cap.bin\x00200", BLOCK, 0)),
+            peer(&[ACK]),
+            peer(b"C"),
+            expect(&first),
+            peer(&[NAK]),
+            expect(&first),
+            peer(&[ACK]),
+            expect(&[EOT]),
+            peer(&[NAK]),
+            expect(&[EOT]),
+            peer(&[ACK]),
+            peer(b"C"),
+            expect(&block(0, &[], BLOCK, 0)),
+            peer(&[ACK]),
+        ];
+        let script = Script::new(steps);
+        let board = MockBoard::default();
+        let result = block_on(send(&mut Wire(&script), &mut Wire(&script), &board, &files, Protocol::Ymodem, name));
+        script.assert_done();
+        let summary = result.unwrap();
+        assert_eq!((summary.files, summary.bytes), (1, 200));
+    }
+}
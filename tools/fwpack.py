@@ -0,0 +1,33 @@
+#!/usr/bin/env python3
+# Acrescenta à imagem da aplicação o descritor que o `fw update` exige:
+# "STFW", tamanho da imagem (u32 little-endian) e SHA-256 dela (ver src/boot.rs)
+#
+#   cargo objcopy --release -- -O binary app.bin
+#   tools/fwpack.py app.bin app.fw
+#   no shell: fw update; no terminal: enviar app.fw por YMODEM
+
+import hashlib
+import struct
+import sys
+
+SLOT_SIZE = 256 * 1024
+DESCRIPTOR_LEN = 8 + 32
+
+
+def main():
+    if len(sys.argv) != 3:
+        sys.exit("uso: fwpack.py <imagem.bin> <saida.fw>")
+    with open(sys.argv[1], "rb") as f:
+        image = f.read()
+    # A imagem e o descritor recebido junto precisam deixar livre a cópia do
+    # descritor no fim do slot
+    if len(image) + 2 * DESCRIPTOR_LEN > SLOT_SIZE:
+        sys.exit(f"imagem grande demais: {len(image)} bytes")
+    descriptor = b"STFW" + struct.pack("<I", len(image)) + hashlib.sha256(image).digest()
+    with open(sys.argv[2], "wb") as f:
+        f.write(image + descriptor)
+    print(f"{sys.argv[2]}: {len(image)} bytes + descritor, sha256 {descriptor[8:].hex()}")
+
+
+if __name__ == "__main__":
+    main()